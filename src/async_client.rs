@@ -0,0 +1,123 @@
+// Async Last.fm scrobble API 2.0 client
+use std::collections::HashMap;
+
+use crate::auth::Credentials;
+use crate::client::{
+    authenticated_params, check_response, decode_batch_scrobbles, decode_now_playing, decode_scrobble,
+    decode_session, signed_params, ApiOperation,
+};
+use crate::endpoints::ApiEndpoints;
+use crate::error::ScrobblerError;
+use crate::models::responses::{BatchScrobbleResponse, NowPlayingResponse, ScrobbleResponse, SessionResponse};
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+pub struct AsyncLastFm {
+    auth: Credentials,
+    endpoints: ApiEndpoints,
+    http_client: reqwest::Client,
+}
+
+impl AsyncLastFm {
+    pub fn new(api_key: &str, api_secret: &str) -> Self {
+        Self {
+            auth: Credentials::new_partial(api_key, api_secret),
+            endpoints: ApiEndpoints::default(),
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn endpoints(&self) -> &ApiEndpoints {
+        &self.endpoints
+    }
+
+    pub fn set_endpoints(&mut self, endpoints: ApiEndpoints) {
+        self.endpoints = endpoints;
+    }
+
+    pub fn set_user_credentials(&mut self, username: &str, password: &str) {
+        self.auth.set_user_credentials(username, password);
+    }
+
+    pub fn set_user_token(&mut self, token: &str) {
+        self.auth.set_user_token(token);
+    }
+
+    pub async fn authenticate_with_password(&mut self) -> Result<SessionResponse> {
+        let params = self.auth.get_auth_request_params()?;
+
+        let body = self.api_request(&ApiOperation::AuthMobileSession, params).await?;
+        let session = decode_session(&body)?;
+
+        self.auth.set_session_key(&session.key);
+
+        Ok(session)
+    }
+
+    pub async fn authenticate_with_token(&mut self) -> Result<SessionResponse> {
+        let params = self.auth.get_auth_request_params()?;
+
+        let body = self.api_request(&ApiOperation::AuthWebSession, params).await?;
+        let session = decode_session(&body)?;
+
+        self.auth.set_session_key(&session.key);
+
+        Ok(session)
+    }
+
+    pub fn authenticate_with_session_key(&mut self, session_key: &str) {
+        self.auth.set_session_key(session_key);
+    }
+
+    pub fn session_key(&self) -> Option<&str> {
+        self.auth.session_key()
+    }
+
+    pub fn clear_session_key(&mut self) {
+        self.auth.clear_session_key();
+    }
+
+    pub async fn send_now_playing(&self, params: &HashMap<String, String>) -> Result<NowPlayingResponse> {
+        let body = self.send_authenticated_request(&ApiOperation::NowPlaying, params).await?;
+        decode_now_playing(&body)
+    }
+
+    pub async fn send_scrobble(&self, params: &HashMap<String, String>) -> Result<ScrobbleResponse> {
+        let body = self.send_authenticated_request(&ApiOperation::Scrobble, params).await?;
+        decode_scrobble(&body)
+    }
+
+    pub async fn send_batch_scrobbles(&self, params: &HashMap<String, String>) -> Result<BatchScrobbleResponse> {
+        let body = self.send_authenticated_request(&ApiOperation::Scrobble, params).await?;
+        decode_batch_scrobbles(&body)
+    }
+
+    async fn send_authenticated_request(
+        &self,
+        operation: &ApiOperation,
+        params: &HashMap<String, String>,
+    ) -> Result<String> {
+        let req_params = authenticated_params(&self.auth, params)?;
+        self.api_request(operation, req_params).await
+    }
+
+    async fn api_request(&self, operation: &ApiOperation, params: HashMap<String, String>) -> Result<String> {
+        let params = signed_params(&self.auth, operation, params);
+
+        let resp = self
+            .http_client
+            .post(self.endpoints.api_url())
+            .form(&params)
+            .send()
+            .await
+            .map_err(|err| ScrobblerError::Transport(err.to_string()))?;
+
+        let status = resp.status().as_u16();
+        let resp_body = resp
+            .text()
+            .await
+            .map_err(|err| ScrobblerError::Transport(format!("Failed to read response body: {err}")))?;
+
+        check_response(status, resp_body)
+    }
+}
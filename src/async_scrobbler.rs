@@ -0,0 +1,268 @@
+use crate::async_client::AsyncLastFm;
+use crate::endpoints::ApiEndpoints;
+use crate::error::ScrobblerError;
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::models::responses::{BatchScrobbleResponse, NowPlayingResponse, ScrobbleResponse, SessionResponse};
+use crate::scrobbler::Scrobbler;
+use crate::session::Session;
+
+use std::result;
+
+type Result<T> = result::Result<T, ScrobblerError>;
+
+/// An asynchronous Last.fm Scrobbler client. Submits song play information to Last.fm.
+///
+/// The async counterpart of [`Scrobbler`], for use in async runtimes such as tokio without blocking the executor.
+/// It offers the same authentication, Now Playing and Scrobble methods; they behave identically, sign requests the
+/// same way and return the same response types. Only available with the `async` cargo feature enabled.
+///
+/// High-level example usage:
+/// ```ignore
+/// let mut scrobbler = AsyncScrobbler::new(api_key, api_secret);
+/// scrobbler.authenticate_with_password(username, password).await?;
+///
+/// let song = Scrobble::new("Example Artist", "Example Song", "Example Album");
+/// scrobbler.scrobble(&song).await?;
+/// ```
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+pub struct AsyncScrobbler {
+    client: AsyncLastFm,
+    session: Option<Session>,
+}
+
+impl AsyncScrobbler {
+    /// Creates a new `AsyncScrobbler` instance with the given Last.fm API Key and API Secret. See [`Scrobbler::new`].
+    ///
+    /// [`Scrobbler::new`]: struct.Scrobbler.html#method.new
+    #[must_use]
+    pub fn new(api_key: &str, api_secret: &str) -> Self {
+        Self {
+            client: AsyncLastFm::new(api_key, api_secret),
+            session: None,
+        }
+    }
+
+    /// Sets the endpoints of the scrobbling service, such as [`ApiEndpoints::libre_fm`]. Defaults to Last.fm. See
+    /// [`ScrobblerBuilder::endpoints`].
+    ///
+    /// [`ApiEndpoints::libre_fm`]: struct.ApiEndpoints.html#method.libre_fm
+    /// [`ScrobblerBuilder::endpoints`]: struct.ScrobblerBuilder.html#method.endpoints
+    pub fn with_endpoints(&mut self, endpoints: ApiEndpoints) -> &mut Self {
+        self.client.set_endpoints(endpoints);
+        self
+    }
+
+    /// Gets the [`ApiEndpoints`] of the service the client talks to.
+    ///
+    /// [`ApiEndpoints`]: struct.ApiEndpoints.html
+    #[must_use]
+    pub fn endpoints(&self) -> &ApiEndpoints {
+        self.client.endpoints()
+    }
+
+    /// Authenticates a Last.fm user with the given username and password. See
+    /// [`Scrobbler::authenticate_with_password`].
+    ///
+    /// # Errors
+    /// Fails in the same cases as [`Scrobbler::authenticate_with_password`].
+    ///
+    /// [`Scrobbler::authenticate_with_password`]: struct.Scrobbler.html#method.authenticate_with_password
+    pub async fn authenticate_with_password(&mut self, username: &str, password: &str) -> Result<SessionResponse> {
+        self.client.set_user_credentials(username, password);
+        let result = self.client.authenticate_with_password().await;
+        self.update_session(result)
+    }
+
+    /// Authenticates a Last.fm user with an authentication token. See [`Scrobbler::authenticate_with_token`].
+    ///
+    /// # Errors
+    /// Fails in the same cases as [`Scrobbler::authenticate_with_token`].
+    ///
+    /// [`Scrobbler::authenticate_with_token`]: struct.Scrobbler.html#method.authenticate_with_token
+    pub async fn authenticate_with_token(&mut self, token: &str) -> Result<SessionResponse> {
+        self.client.set_user_token(token);
+        let result = self.client.authenticate_with_token().await;
+        self.update_session(result)
+    }
+
+    /// Authenticates a Last.fm user with a session key. See [`Scrobbler::authenticate_with_session_key`].
+    ///
+    /// [`Scrobbler::authenticate_with_session_key`]: struct.Scrobbler.html#method.authenticate_with_session_key
+    pub fn authenticate_with_session_key(&mut self, session_key: &str) {
+        self.authenticate_with_session(&Session::from_key(session_key));
+    }
+
+    /// Authenticates a Last.fm user with a previously saved [`Session`]. See
+    /// [`Scrobbler::authenticate_with_session`].
+    ///
+    /// [`Session`]: struct.Session.html
+    /// [`Scrobbler::authenticate_with_session`]: struct.Scrobbler.html#method.authenticate_with_session
+    pub fn authenticate_with_session(&mut self, session: &Session) {
+        self.client.authenticate_with_session_key(&session.key);
+        self.session = Some(session.clone());
+    }
+
+    /// Registers the given [`Scrobble`]/track as the currently authenticated user's "now playing" track. See
+    /// [`Scrobbler::now_playing`].
+    ///
+    /// # Errors
+    /// Fails in the same cases as [`Scrobbler::now_playing`].
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`Scrobbler::now_playing`]: struct.Scrobbler.html#method.now_playing
+    pub async fn now_playing(&self, scrobble: &Scrobble) -> Result<NowPlayingResponse> {
+        let params = scrobble.as_map();
+
+        self.client.send_now_playing(&params).await
+    }
+
+    /// Registers a scrobble (play) of the given [`Scrobble`]/track. See [`Scrobbler::scrobble`].
+    ///
+    /// # Errors
+    /// Fails in the same cases as [`Scrobbler::scrobble`].
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    pub async fn scrobble(&self, scrobble: &Scrobble) -> Result<ScrobbleResponse> {
+        let params = Scrobbler::scrobble_params(scrobble)?;
+
+        self.client.send_scrobble(&params).await
+    }
+
+    /// Registers a scrobble (play) of a collection of tracks. See [`Scrobbler::scrobble_batch`].
+    ///
+    /// # Errors
+    /// Fails in the same cases as [`Scrobbler::scrobble_batch`].
+    ///
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    pub async fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<BatchScrobbleResponse> {
+        let params = Scrobbler::validated_batch_params(batch)?;
+
+        self.client.send_batch_scrobbles(&params).await
+    }
+
+    /// Gets the session key the client is currently authenticated with. Returns `None` if not authenticated.
+    #[must_use]
+    pub fn session_key(&self) -> Option<&str> {
+        self.client.session_key()
+    }
+
+    /// Gets the [`Session`] the client is currently authenticated with. Returns `None` if not authenticated.
+    ///
+    /// [`Session`]: struct.Session.html
+    #[must_use]
+    pub fn session(&self) -> Option<&Session> {
+        self.session.as_ref()
+    }
+
+    // Keeps `session` in sync with the client's session key, so a failed authentication leaves neither set
+    fn update_session(&mut self, result: Result<SessionResponse>) -> Result<SessionResponse> {
+        self.session = result.as_ref().ok().map(Session::from);
+        if self.session.is_none() {
+            self.client.clear_session_key();
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::mock;
+
+    #[tokio::test]
+    async fn check_async_scrobbler_auth_and_scrobble() {
+        let mut scrobbler = AsyncScrobbler::new("api_key", "api_secret");
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_status(403)
+            .with_body(r#"{"error": 4, "message": "Authentication Failed"}"#)
+            .create();
+
+        let resp = scrobbler.authenticate_with_password("user", "pass").await;
+        assert_eq!(resp.unwrap_err().api_error_code(), Some(4));
+        assert!(scrobbler.session().is_none());
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::UrlEncoded(
+                "method".into(),
+                "auth.getMobileSession".into(),
+            ))
+            .with_body(
+                r#"
+                {
+                    "session": {
+                        "key": "key",
+                        "subscriber": 1337,
+                        "name": "foo floyd"
+                    }
+                }
+            "#,
+            )
+            .create();
+
+        let resp = scrobbler.authenticate_with_password("user", "pass").await;
+        assert!(resp.is_ok());
+        assert_eq!(scrobbler.session_key(), Some("key"));
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("method".into(), "track.scrobble".into()),
+                mockito::Matcher::UrlEncoded("sk".into(), "key".into()),
+                mockito::Matcher::UrlEncoded("timestamp".into(), "1337".into()),
+            ]))
+            .with_body(
+                r#"
+            {
+                "scrobbles": [{
+                        "artist": [ "0", "foo floyd and the fruit flies" ],
+                        "album": [ "1", "old bananas" ],
+                        "albumArtist": [ "0", "foo floyd"],
+                        "track": [ "1", "old bananas"],
+                        "timestamp": "2019-10-04 13:23:40"
+                }]
+            }
+            "#,
+            )
+            .create();
+
+        let mut scrobble = Scrobble::new("foo floyd and the fruit flies", "old bananas", "old bananas");
+        scrobble.with_timestamp(1337);
+        assert!(scrobbler.scrobble(&scrobble).await.is_ok());
+
+        let err = scrobbler.scrobble_batch(&ScrobbleBatch::new()).await.unwrap_err();
+        assert!(matches!(err, ScrobblerError::InvalidBatch(_)));
+    }
+
+    #[tokio::test]
+    async fn check_async_scrobbler_endpoints() {
+        let mut scrobbler = AsyncScrobbler::new("api_key", "api_secret");
+        assert_eq!(scrobbler.endpoints(), &ApiEndpoints::last_fm());
+
+        let api_url = format!("{}/custom/2.0/", mockito::server_url());
+        let endpoints = ApiEndpoints::new(&api_url, "https://scrobble.example.com/auth/").unwrap();
+        scrobbler.with_endpoints(endpoints.clone());
+        scrobbler.authenticate_with_session_key("key");
+        assert_eq!(scrobbler.endpoints(), &endpoints);
+
+        let _m = mock("POST", "/custom/2.0/")
+            .match_body(mockito::Matcher::UrlEncoded("method".into(), "track.updateNowPlaying".into()))
+            .with_body(
+                r#"
+            {
+                "nowplaying": {
+                    "artist": [ "0", "foo floyd and the fruit flies" ],
+                    "album": [ "1", "old bananas" ],
+                    "albumArtist": [ "0", "foo floyd"],
+                    "track": [ "1", "old bananas"]
+                }
+            }
+            "#,
+            )
+            .create();
+
+        let scrobble = Scrobble::new("foo floyd and the fruit flies", "old bananas", "old bananas");
+        assert!(scrobbler.now_playing(&scrobble).await.is_ok());
+    }
+}
@@ -0,0 +1,532 @@
+// Legacy Audioscrobbler 1.2 submission protocol client
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::auth::{handshake_token, md5_hex};
+use crate::clock::{Clock, SystemClock};
+use crate::error::ScrobblerError;
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::transport::{HttpMethod, HttpRequest, Transport, UreqTransport};
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+/// Handshake URL of the Last.fm Audioscrobbler 1.2 submission server
+pub const HANDSHAKE_URL: &str = "http://post.audioscrobbler.com/";
+
+const PROTOCOL_VERSION: &str = "1.2.1";
+
+// Maximum number of scrobbles in a single submission
+const MAX_SUBMISSION_SIZE: usize = 50;
+
+// Consecutive hard failures of now playing/submission requests after which a new handshake is made
+const MAX_HARD_FAILURES: u32 = 3;
+
+// Delay before handshaking again after a failed handshake, doubled on each further failure up to the maximum
+#[allow(clippy::duration_suboptimal_units)]
+const INITIAL_HANDSHAKE_DELAY: Duration = Duration::from_secs(60);
+#[allow(clippy::duration_suboptimal_units)]
+const MAX_HANDSHAKE_DELAY: Duration = Duration::from_secs(120 * 60);
+
+
+#[derive(Clone, Copy)]
+enum RequestKind {
+    NowPlaying,
+    Submission,
+}
+
+#[derive(Clone, Debug)]
+struct ProtocolSession {
+    id: String,
+    now_playing_url: String,
+    submission_url: String,
+}
+
+impl ProtocolSession {
+    fn url(&self, kind: RequestKind) -> &str {
+        match kind {
+            RequestKind::NowPlaying => &self.now_playing_url,
+            RequestKind::Submission => &self.submission_url,
+        }
+    }
+}
+
+// Status line of a handshake or submission reply
+enum ProtocolReply {
+    Ok,
+    BadSession,
+    BadAuth,
+    Banned,
+    BadTime,
+    Failed(String),
+    Unexpected(String),
+}
+
+impl ProtocolReply {
+    fn parse(line: &str) -> Self {
+        match line {
+            "OK" => Self::Ok,
+            "BADSESSION" => Self::BadSession,
+            "BADAUTH" => Self::BadAuth,
+            "BANNED" => Self::Banned,
+            "BADTIME" => Self::BadTime,
+            line => match line.strip_prefix("FAILED") {
+                Some(reason) => Self::Failed(reason.trim().to_owned()),
+                None => Self::Unexpected(line.to_owned()),
+            },
+        }
+    }
+
+    // Hard failures are those that may succeed later: anything other than a definitive rejection by the server
+    fn is_hard_failure(&self) -> bool {
+        !matches!(self, Self::BadAuth | Self::Banned | Self::BadTime)
+    }
+
+    fn into_error(self) -> ScrobblerError {
+        match self {
+            Self::Ok => ScrobblerError::Decode("Unexpected reply: OK".to_owned()),
+            Self::BadSession => ScrobblerError::Decode("Unexpected reply: BADSESSION".to_owned()),
+            Self::BadAuth => ScrobblerError::NotAuthenticated("Invalid username or password".to_owned()),
+            Self::Banned => ScrobblerError::Protocol("This client version has been banned".to_owned()),
+            Self::BadTime => ScrobblerError::Protocol("The system clock is incorrect".to_owned()),
+            Self::Failed(reason) => ScrobblerError::Protocol(reason),
+            Self::Unexpected(reply) => ScrobblerError::Decode(format!("Unexpected reply: {reply}")),
+        }
+    }
+}
+
+/// A client for the legacy Audioscrobbler 1.2 submission protocol.
+///
+/// Some self-hosted servers and hardware bridges only speak this older, line-based protocol rather than the Last.fm
+/// 2.0 API used by [`Scrobbler`]. It takes the same [`Scrobble`] values. The handshake is made on the first request,
+/// and made again automatically when the server replies `BADSESSION` or after three consecutive hard failures.
+///
+/// As required by the protocol, failed handshakes are not retried immediately: the client waits one minute after the
+/// first failure, doubling the delay after each further failure up to two hours. Requests made while waiting fail
+/// with a [`ScrobblerError::Protocol`] error without contacting the server; see [`LegacyScrobbler::retry_after`].
+///
+/// # Usage
+/// ```ignore
+/// let mut scrobbler = LegacyScrobbler::new("tst", "1.0", "username", "password");
+/// scrobbler.set_handshake_url("http://scrobbler.example.com/");
+///
+/// let song = Scrobble::new("Example Artist", "Example Song", "Example Album");
+/// scrobbler.now_playing(&song)?;
+/// scrobbler.scrobble(&song)?;
+/// ```
+///
+/// # Protocol Documentation
+/// [Audioscrobbler Submissions Protocol v1.2.1](https://web.archive.org/web/2010/http://www.audioscrobbler.net/development/protocol/)
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`Scrobble`]: struct.Scrobble.html
+/// [`ScrobblerError::Protocol`]: enum.ScrobblerError.html#variant.Protocol
+/// [`LegacyScrobbler::retry_after`]: struct.LegacyScrobbler.html#method.retry_after
+pub struct LegacyScrobbler<C: Clock = SystemClock> {
+    client_id: String,
+    client_version: String,
+    username: String,
+    password_hash: String,
+    handshake_url: String,
+    transport: Box<dyn Transport>,
+    clock: C,
+
+    session: Option<ProtocolSession>,
+    hard_failures: u32,
+    handshake_delay: Option<Duration>,
+    next_handshake: Option<SystemTime>,
+}
+
+impl LegacyScrobbler<SystemClock> {
+    /// Creates a client identified by the given client ID & version, authenticating as the given user.
+    ///
+    /// Client IDs are issued by the server operator; `tst` is the ID reserved for testing.
+    #[must_use]
+    pub fn new(client_id: &str, client_version: &str, username: &str, password: &str) -> Self {
+        Self::with_clock(client_id, client_version, username, password, SystemClock)
+    }
+}
+
+impl<C: Clock> LegacyScrobbler<C> {
+    /// Creates a client reading the current time from the given [`Clock`]. See [`LegacyScrobbler::new`].
+    ///
+    /// [`Clock`]: trait.Clock.html
+    /// [`LegacyScrobbler::new`]: struct.LegacyScrobbler.html#method.new
+    pub fn with_clock(client_id: &str, client_version: &str, username: &str, password: &str, clock: C) -> Self {
+        Self {
+            client_id: client_id.to_owned(),
+            client_version: client_version.to_owned(),
+            username: username.to_owned(),
+            password_hash: md5_hex(password),
+            handshake_url: HANDSHAKE_URL.to_owned(),
+            transport: Box::new(UreqTransport::new()),
+            clock,
+            session: None,
+            hard_failures: 0,
+            handshake_delay: None,
+            next_handshake: None,
+        }
+    }
+
+    /// Sets the handshake URL of the submission server. Defaults to [`HANDSHAKE_URL`].
+    ///
+    /// [`HANDSHAKE_URL`]: constant.HANDSHAKE_URL.html
+    pub fn set_handshake_url(&mut self, url: &str) {
+        url.clone_into(&mut self.handshake_url);
+        self.session = None;
+    }
+
+    /// Sets the HTTP [`Transport`] requests are sent with. Defaults to [`UreqTransport`].
+    ///
+    /// [`Transport`]: trait.Transport.html
+    /// [`UreqTransport`]: struct.UreqTransport.html
+    pub fn set_transport<T: Transport + 'static>(&mut self, transport: T) {
+        self.transport = Box::new(transport);
+    }
+
+    /// Makes a handshake with the submission server, starting a new session.
+    ///
+    /// Handshakes are made automatically when needed, so this is only useful to check the user's credentials up
+    /// front.
+    ///
+    /// # Errors
+    /// `BADAUTH` replies are returned as [`ScrobblerError::NotAuthenticated`], other failure replies as
+    /// [`ScrobblerError::Protocol`]. While a failed handshake is backing off, a [`ScrobblerError::Protocol`] error is
+    /// returned without contacting the server.
+    ///
+    /// [`ScrobblerError::NotAuthenticated`]: enum.ScrobblerError.html#variant.NotAuthenticated
+    /// [`ScrobblerError::Protocol`]: enum.ScrobblerError.html#variant.Protocol
+    pub fn handshake(&mut self) -> Result<()> {
+        if let Some(delay) = self.retry_after() {
+            return Err(ScrobblerError::Protocol(format!(
+                "Handshake failed, retrying is not allowed for another {} seconds",
+                delay.as_secs()
+            )));
+        }
+
+        self.session = None;
+        self.hard_failures = 0;
+
+        let (err, hard_failure) = match self.send_handshake() {
+            Ok(Ok(session)) => {
+                self.session = Some(session);
+                self.handshake_delay = None;
+                self.next_handshake = None;
+                return Ok(());
+            }
+            Ok(Err(reply)) => {
+                let hard_failure = reply.is_hard_failure();
+                (reply.into_error(), hard_failure)
+            }
+            Err(err) => (err, true),
+        };
+
+        if hard_failure {
+            let delay = self
+                .handshake_delay
+                .map_or(INITIAL_HANDSHAKE_DELAY, |delay| (delay * 2).min(MAX_HANDSHAKE_DELAY));
+            self.handshake_delay = Some(delay);
+            self.next_handshake = Some(self.clock.now() + delay);
+        }
+        Err(err)
+    }
+
+    /// Time left before a handshake may be retried after a failed handshake, or `None` if one can be made now
+    pub fn retry_after(&self) -> Option<Duration> {
+        let next_handshake = self.next_handshake?;
+        next_handshake.duration_since(self.clock.now()).ok().filter(|d| !d.is_zero())
+    }
+
+    /// Returns true if a handshake has been made and its session is still valid
+    pub fn has_session(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// Registers the given [`Scrobble`]/track as the user's "now playing" track.
+    ///
+    /// # Errors
+    /// Returns an error if the handshake or the request fails, or the server replies with a failure.
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    pub fn now_playing(&mut self, scrobble: &Scrobble) -> Result<()> {
+        let mut params = HashMap::new();
+        params.insert("a".to_string(), scrobble.artist().to_owned());
+        params.insert("t".to_string(), scrobble.track().to_owned());
+        params.insert("b".to_string(), scrobble.album().to_owned());
+        params.insert("l".to_string(), scrobble.duration().map(|d| d.to_string()).unwrap_or_default());
+        params.insert("n".to_string(), scrobble.track_number().map(|n| n.to_string()).unwrap_or_default());
+        params.insert("m".to_string(), scrobble.mbid().unwrap_or_default().to_owned());
+
+        self.submit(RequestKind::NowPlaying, &params)
+    }
+
+    /// Submits a play of the given [`Scrobble`]/track. The current time is used if the scrobble has no timestamp.
+    ///
+    /// # Errors
+    /// Returns an error if the handshake or the submission fails, or the server replies with a failure.
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    pub fn scrobble(&mut self, scrobble: &Scrobble) -> Result<()> {
+        let params = self.submission_params(std::iter::once(scrobble))?;
+        self.submit(RequestKind::Submission, &params)
+    }
+
+    /// Submits plays of a collection of tracks in one request. Batches must hold between 1 and 50 scrobbles.
+    ///
+    /// # Errors
+    /// Returns a `ScrobblerError::InvalidBatch` error if the batch is empty or holds more than 50 scrobbles. Otherwise
+    /// fails in the same cases as `LegacyScrobbler::scrobble`.
+    pub fn scrobble_batch(&mut self, batch: &ScrobbleBatch) -> Result<()> {
+        if batch.len() > MAX_SUBMISSION_SIZE {
+            return Err(ScrobblerError::InvalidBatch(
+                "Scrobble batch too large (must be 50 or fewer scrobbles)".to_owned(),
+            ));
+        } else if batch.is_empty() {
+            return Err(ScrobblerError::InvalidBatch("Scrobble batch is empty".to_owned()));
+        }
+
+        let params = self.submission_params(batch.iter())?;
+        self.submit(RequestKind::Submission, &params)
+    }
+
+    fn submission_params<'a, I>(&self, scrobbles: I) -> Result<HashMap<String, String>>
+    where
+        I: Iterator<Item = &'a Scrobble>,
+    {
+        let now = self.clock.now().duration_since(UNIX_EPOCH)?.as_secs();
+        let mut params = HashMap::new();
+
+        for (i, scrobble) in scrobbles.enumerate() {
+            // Source: 'P' chosen by the user, 'E' personalised recommendation
+            let source = if scrobble.chosen_by_user().unwrap_or(true) { "P" } else { "E" };
+            let timestamp = scrobble.timestamp().unwrap_or(now);
+
+            params.insert(format!("a[{i}]"), scrobble.artist().to_owned());
+            params.insert(format!("t[{i}]"), scrobble.track().to_owned());
+            params.insert(format!("i[{i}]"), timestamp.to_string());
+            params.insert(format!("o[{i}]"), source.to_owned());
+            params.insert(format!("r[{i}]"), String::new());
+            params.insert(format!("l[{i}]"), scrobble.duration().map(|d| d.to_string()).unwrap_or_default());
+            params.insert(format!("b[{i}]"), scrobble.album().to_owned());
+            params.insert(format!("n[{i}]"), scrobble.track_number().map(|n| n.to_string()).unwrap_or_default());
+            params.insert(format!("m[{i}]"), scrobble.mbid().unwrap_or_default().to_owned());
+        }
+
+        Ok(params)
+    }
+
+    // Sends a now playing or submission request, handshaking first if needed and once more on BADSESSION
+    fn submit(&mut self, kind: RequestKind, params: &HashMap<String, String>) -> Result<()> {
+        let mut session_rejected = false;
+        loop {
+            if self.session.is_none() {
+                self.handshake()?;
+            }
+
+            let session = self.session.clone().expect("Session set by handshake");
+            let mut req_params = params.clone();
+            req_params.insert("s".to_string(), session.id.clone());
+
+            let result = self.send(HttpMethod::Post, session.url(kind), req_params);
+            match result.map(|lines| ProtocolReply::parse(&lines[0])) {
+                Ok(ProtocolReply::Ok) => {
+                    self.hard_failures = 0;
+                    return Ok(());
+                }
+                Ok(ProtocolReply::BadSession) if !session_rejected => {
+                    session_rejected = true;
+                    self.session = None;
+                }
+                Ok(ProtocolReply::BadSession) => {
+                    self.session = None;
+                    return Err(ScrobblerError::Protocol("Session rejected after new handshake".to_owned()));
+                }
+                result => {
+                    let err = match result {
+                        Ok(reply) => reply.into_error(),
+                        Err(err) => err,
+                    };
+                    self.hard_failures += 1;
+                    if self.hard_failures >= MAX_HARD_FAILURES {
+                        self.session = None;
+                    }
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    // Sends a handshake, returning the new session or the server's failure reply
+    fn send_handshake(&self) -> Result<std::result::Result<ProtocolSession, ProtocolReply>> {
+        let timestamp = self.clock.now().duration_since(UNIX_EPOCH)?.as_secs().to_string();
+
+        let mut params = HashMap::new();
+        params.insert("hs".to_string(), "true".to_string());
+        params.insert("p".to_string(), PROTOCOL_VERSION.to_string());
+        params.insert("c".to_string(), self.client_id.clone());
+        params.insert("v".to_string(), self.client_version.clone());
+        params.insert("u".to_string(), self.username.clone());
+        params.insert("a".to_string(), handshake_token(&self.password_hash, &timestamp));
+        params.insert("t".to_string(), timestamp);
+
+        let lines = self.send(HttpMethod::Get, &self.handshake_url, params)?;
+        match ProtocolReply::parse(&lines[0]) {
+            ProtocolReply::Ok if lines.len() >= 4 => Ok(Ok(ProtocolSession {
+                id: lines[1].clone(),
+                now_playing_url: lines[2].clone(),
+                submission_url: lines[3].clone(),
+            })),
+            ProtocolReply::Ok => Err(ScrobblerError::Decode("Incomplete handshake reply".to_owned())),
+            reply => Ok(Err(reply)),
+        }
+    }
+
+    // Sends a request, returning the non-empty lines of the reply
+    fn send(&self, method: HttpMethod, url: &str, params: HashMap<String, String>) -> Result<Vec<String>> {
+        let resp = self.transport.send(&HttpRequest::new(method, url, params))?;
+        if resp.status >= 400 {
+            return Err(ScrobblerError::HttpStatus {
+                status: resp.status,
+                body: resp.body,
+            });
+        }
+
+        let lines: Vec<String> = resp
+            .body
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_owned)
+            .collect();
+        if lines.is_empty() {
+            return Err(ScrobblerError::Decode("Empty reply".to_owned()));
+        }
+
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::RecordingTransport;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    const HANDSHAKE_OK: &str = "OK\nsession-1\nhttp://np.example.com/np\nhttp://np.example.com/submit\n";
+
+    fn make_scrobbler(now: &Rc<Cell<u64>>) -> (LegacyScrobbler<impl Clock>, RecordingTransport) {
+        let now = Rc::clone(now);
+        let clock = move || UNIX_EPOCH + Duration::from_secs(now.get());
+
+        let transport = RecordingTransport::new();
+        let mut scrobbler = LegacyScrobbler::with_clock("tst", "1.0", "user", "hunter2", clock);
+        scrobbler.set_handshake_url("http://hs.example.com/");
+        scrobbler.set_transport(transport.clone());
+
+        (scrobbler, transport)
+    }
+
+    #[test]
+    fn check_legacy_handshake_and_submit() {
+        let now = Rc::new(Cell::new(1_000_000));
+        let (mut scrobbler, transport) = make_scrobbler(&now);
+
+        transport.push_response(200, HANDSHAKE_OK);
+        transport.push_response(200, "OK\n");
+        transport.push_response(200, "OK\n");
+
+        let mut scrobble = Scrobble::new("foo floyd and the fruit flies", "old bananas", "old bananas");
+        scrobble.with_duration(215).with_timestamp(1337);
+        scrobbler.now_playing(&scrobble).unwrap();
+        scrobbler.scrobble(&scrobble).unwrap();
+        assert!(scrobbler.has_session());
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+
+        let handshake = &requests[0];
+        assert_eq!(handshake.method, HttpMethod::Get);
+        assert_eq!(handshake.params["hs"], "true");
+        assert_eq!(handshake.params["p"], "1.2.1");
+        assert_eq!(handshake.params["u"], "user");
+        assert_eq!(handshake.params["t"], "1000000");
+        assert_eq!(handshake.params["a"], handshake_token(&md5_hex("hunter2"), "1000000"));
+
+        assert_eq!(requests[1].url, "http://np.example.com/np");
+        assert_eq!(requests[1].params["s"], "session-1");
+        assert_eq!(requests[1].params["l"], "215");
+
+        assert_eq!(requests[2].url, "http://np.example.com/submit");
+        assert_eq!(requests[2].params["a[0]"], "foo floyd and the fruit flies");
+        assert_eq!(requests[2].params["i[0]"], "1337");
+        assert_eq!(requests[2].params["o[0]"], "P");
+    }
+
+    #[test]
+    fn check_legacy_bad_session_rehandshake() {
+        let now = Rc::new(Cell::new(1_000_000));
+        let (mut scrobbler, transport) = make_scrobbler(&now);
+
+        transport.push_response(200, HANDSHAKE_OK);
+        transport.push_response(200, "BADSESSION\n");
+        transport.push_response(200, "OK\nsession-2\nhttp://np.example.com/np\nhttp://np.example.com/submit\n");
+        transport.push_response(200, "OK\n");
+
+        let scrobble = Scrobble::new("foo floyd and the fruit flies", "old bananas", "old bananas");
+        scrobbler.scrobble(&scrobble).unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[2].params["hs"], "true");
+        assert_eq!(requests[3].params["s"], "session-2");
+        assert_eq!(requests[3].params["i[0]"], "1000000");
+
+        let tracks = vec![("Artist", "Track", "Album"); 51];
+        let err = scrobbler.scrobble_batch(&ScrobbleBatch::from(tracks)).unwrap_err();
+        assert!(matches!(err, ScrobblerError::InvalidBatch(_)));
+    }
+
+    #[test]
+    fn check_legacy_hard_failures() {
+        let now = Rc::new(Cell::new(1_000_000));
+        let (mut scrobbler, transport) = make_scrobbler(&now);
+        let scrobble = Scrobble::new("foo floyd and the fruit flies", "old bananas", "old bananas");
+
+        // Failed handshakes back off for 1 minute, then 2 minutes...
+        transport.push_response(200, "FAILED Server overloaded\n");
+        let err = scrobbler.now_playing(&scrobble).unwrap_err();
+        assert!(matches!(err, ScrobblerError::Protocol(ref msg) if msg == "Server overloaded"));
+        assert_eq!(scrobbler.retry_after(), Some(INITIAL_HANDSHAKE_DELAY));
+
+        assert!(scrobbler.handshake().is_err());
+        assert_eq!(transport.requests().len(), 1);
+
+        now.set(now.get() + 60);
+        assert_eq!(scrobbler.retry_after(), None);
+        transport.push_result(Err(ScrobblerError::Transport("offline".to_owned())));
+        assert!(scrobbler.handshake().is_err());
+        assert_eq!(scrobbler.retry_after(), Some(INITIAL_HANDSHAKE_DELAY * 2));
+
+        now.set(now.get() + 120);
+        transport.push_response(200, HANDSHAKE_OK);
+        scrobbler.handshake().unwrap();
+        assert_eq!(scrobbler.retry_after(), None);
+
+        // ...and three failed submissions fall back to a new handshake
+        for _ in 0..MAX_HARD_FAILURES {
+            assert!(scrobbler.has_session());
+            transport.push_response(500, "Internal Server Error");
+            assert_eq!(scrobbler.scrobble(&scrobble).unwrap_err().http_status(), Some(500));
+        }
+        assert!(!scrobbler.has_session());
+
+        transport.push_response(200, "BADAUTH\n");
+        let err = scrobbler.handshake().unwrap_err();
+        assert!(matches!(err, ScrobblerError::NotAuthenticated(_)));
+        assert_eq!(scrobbler.retry_after(), None);
+
+        transport.push_response(200, "BANNED\n");
+        assert!(matches!(scrobbler.handshake(), Err(ScrobblerError::Protocol(_))));
+        assert_eq!(scrobbler.retry_after(), None);
+    }
+}
@@ -1,7 +1,10 @@
 // Authentication utilities for Last.fm Scrobble API 2.0
 use std::collections::HashMap;
 
+use crate::error::ScrobblerError;
+
 #[derive(PartialEq, Debug)]
+#[allow(clippy::struct_field_names)]
 pub struct Credentials {
     // Application specific key & secret
     api_key: String,
@@ -52,18 +55,24 @@ impl Credentials {
         }));
 
         // Invalidate session because we have new credentials
-        self.clear_session_key()
+        self.clear_session_key();
     }
 
     pub fn set_user_token(&mut self, token: &str) {
         self.credentials = Some(CredentialsVariant::Token(token.to_owned()));
-        self.clear_session_key()
+        self.clear_session_key();
+    }
+
+    // Forgets the user token / credentials, and the session they were used for
+    pub fn clear_user_credentials(&mut self) {
+        self.credentials = None;
+        self.clear_session_key();
     }
 
     // Invalidates session. Usually because we have new user token / credentials, which invalidates
     // the current session.
-    fn clear_session_key(&mut self) {
-        self.session_key = None
+    pub fn clear_session_key(&mut self) {
+        self.session_key = None;
     }
 
     pub fn set_session_key(&mut self, key: &str) {
@@ -71,7 +80,7 @@ impl Credentials {
     }
 
     pub fn session_key(&self) -> Option<&str> {
-        self.session_key.as_ref().map(std::ops::Deref::deref)
+        self.session_key.as_deref()
     }
 
     // Returns true if we are currently authenticated (have a valid session token set)
@@ -79,14 +88,15 @@ impl Credentials {
         self.session_key.is_some()
     }
 
-    pub fn get_auth_request_params(&self) -> Result<HashMap<String, String>, String> {
-        let credentials = self
-            .credentials
-            .as_ref()
-            .ok_or("No user credentials available")?;
+    pub fn get_auth_request_params(&self) -> Result<HashMap<String, String>, ScrobblerError> {
+        let credentials = self.credentials.as_ref().ok_or_else(|| {
+            ScrobblerError::NotAuthenticated("No user credentials available".to_string())
+        })?;
 
         if self.api_key.is_empty() || self.api_secret.is_empty() {
-            return Err("Invalid authentication parameters".to_string());
+            return Err(ScrobblerError::NotAuthenticated(
+                "Invalid authentication parameters".to_string(),
+            ));
         }
 
         let mut params = HashMap::new();
@@ -95,7 +105,9 @@ impl Credentials {
         match credentials {
             CredentialsVariant::UserSupplied(user_credentials) => {
                 if !user_credentials.can_authenticate() {
-                    return Err("Invalid authentication credentials".to_string());
+                    return Err(ScrobblerError::NotAuthenticated(
+                        "Invalid authentication credentials".to_string(),
+                    ));
                 }
                 params.insert("username".to_string(), user_credentials.username.clone());
                 params.insert("password".to_string(), user_credentials.password.clone());
@@ -108,6 +120,18 @@ impl Credentials {
         Ok(params)
     }
 
+    pub fn api_key(&self) -> &str {
+        &self.api_key
+    }
+
+    // Params for signed requests made on behalf of the client application, rather than a user (e.g. auth.getToken)
+    pub fn get_client_request_params(&self) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        params.insert("api_key".to_string(), self.api_key.clone());
+
+        params
+    }
+
     pub fn get_request_params(&self) -> HashMap<String, String> {
         let mut params = HashMap::new();
         params.insert("api_key".to_string(), self.api_key.clone());
@@ -132,15 +156,26 @@ impl Credentials {
 
         let mut sig = String::new();
         for k in keys {
-            sig.push_str((k.to_string() + sig_params[k].as_str()).as_str())
+            sig.push_str(k);
+            sig.push_str(sig_params[k].as_str());
         }
 
         sig.push_str(self.api_secret.as_str());
 
-        format!("{:x}", md5::compute(sig.as_bytes()))
+        md5_hex(&sig)
     }
 }
 
+// Lowercase hex MD5 digest, as used by both request signatures & the Audioscrobbler 1.2 protocol
+pub fn md5_hex(input: &str) -> String {
+    format!("{:x}", md5::compute(input.as_bytes()))
+}
+
+// Audioscrobbler 1.2 handshake auth token: md5(md5(password) + timestamp)
+pub fn handshake_token(password_hash: &str, timestamp: &str) -> String {
+    md5_hex(&format!("{password_hash}{timestamp}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,8 +183,8 @@ mod tests {
     #[test]
     fn check_user_credentials() {
         let empty = UserCredentials {
-            username: "".into(),
-            password: "".into(),
+            username: String::new(),
+            password: String::new(),
         };
 
         assert!(!UserCredentials::can_authenticate(&empty));
@@ -177,14 +212,13 @@ mod tests {
 
     #[test]
     fn check_set_user_creds() {
-        let mut auth_creds = Credentials::new_partial("Key".into(), "Secret".into());
-        auth_creds.set_user_credentials("Username".into(), "Password".into());
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_user_credentials("Username", "Password");
 
         let internal_creds = auth_creds.credentials.unwrap();
 
-        let creds = match internal_creds {
-            CredentialsVariant::UserSupplied(val) => val,
-            _ => panic!("Invalid UserCredentials Value"),
+        let CredentialsVariant::UserSupplied(creds) = internal_creds else {
+            panic!("Invalid UserCredentials Value")
         };
 
         assert_eq!(creds.username, "Username");
@@ -193,14 +227,13 @@ mod tests {
 
     #[test]
     fn check_set_user_token() {
-        let mut auth_creds = Credentials::new_partial("Key".into(), "Secret".into());
-        auth_creds.set_user_token("Token".into());
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_user_token("Token");
 
         let token = auth_creds.credentials.unwrap();
 
-        let token = match token {
-            CredentialsVariant::Token(val) => val,
-            _ => panic!("Invalid Token"),
+        let CredentialsVariant::Token(token) = token else {
+            panic!("Invalid Token")
         };
 
         assert_eq!(token, "Token");
@@ -208,8 +241,8 @@ mod tests {
 
     #[test]
     fn check_set_session_key_and_is_authed() {
-        let mut auth_creds = Credentials::new_partial("Key".into(), "Secret".into());
-        auth_creds.set_session_key("SomeKey".into());
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_session_key("SomeKey");
         let key = auth_creds.session_key().unwrap();
 
         assert_eq!(key, "SomeKey");
@@ -218,13 +251,13 @@ mod tests {
 
     #[test]
     fn check_auth_req_params_and_get_signature() {
-        let mut auth_creds = Credentials::new_partial("Key".into(), "Secret".into());
-        auth_creds.set_user_token("Token".into());
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_user_token("Token");
         let param_map = auth_creds.get_auth_request_params().unwrap();
 
         assert_eq!(param_map["token"], "Token");
 
-        auth_creds.set_user_credentials("Foo".into(), "Bar".into());
+        auth_creds.set_user_credentials("Foo", "Bar");
         let param_map = auth_creds.get_auth_request_params().unwrap();
 
         assert_eq!(param_map["username"], "Foo");
@@ -232,19 +265,39 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    #[should_panic(expected = "No user credentials available")]
     fn check_get_bad_params() {
         let auth_creds = Credentials::new_partial("Key", "Secret");
         auth_creds.get_auth_request_params().unwrap();
     }
 
+    #[test]
+    fn check_client_req_params() {
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_session_key("SomeKey");
+        let req_params = auth_creds.get_client_request_params();
+
+        assert_eq!(req_params["api_key"], "Key");
+        assert!(!req_params.contains_key("sk"));
+    }
+
     #[test]
     fn check_req_params() {
-        let mut auth_creds = Credentials::new_partial("Key".into(), "Secret".into());
-        auth_creds.set_session_key("SomeKey".into());
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_session_key("SomeKey");
         let req_params = auth_creds.get_request_params();
 
-        assert_eq!(req_params["api_key".into()], "Key");
-        assert_eq!(req_params["sk".into()], "SomeKey");
+        assert_eq!(req_params["api_key"], "Key");
+        assert_eq!(req_params["sk"], "SomeKey");
+    }
+
+    #[test]
+    fn check_handshake_token() {
+        let password_hash = md5_hex("hunter2");
+        assert_eq!(password_hash, "2ab96390c7dbe3439de74d0c9b0b1767");
+        assert_eq!(
+            handshake_token(&password_hash, "1337"),
+            md5_hex("2ab96390c7dbe3439de74d0c9b0b17671337")
+        );
     }
 }
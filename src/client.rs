@@ -1,49 +1,261 @@
 // Last.fm scrobble API 2.0 client
 use std::collections::HashMap;
 use std::fmt;
-use ureq;
 
 use crate::auth::Credentials;
+use crate::clock::{Clock, Sleeper};
+use crate::endpoints::ApiEndpoints;
+use crate::error::ScrobblerError;
 use crate::models::responses::{
-    AuthResponse, BatchScrobbleResponse, BatchScrobbleResponseWrapper, NowPlayingResponse,
-    NowPlayingResponseWrapper, ScrobbleResponse, ScrobbleResponseWrapper, SessionResponse,
+    AlbumInfo, AlbumInfoResponseWrapper, ApiErrorResponse, ArtistCorrection, ArtistCorrectionResponseWrapper,
+    ArtistInfo, ArtistInfoResponseWrapper, AuthResponse, AuthTokenResponse, BatchScrobbleResponse,
+    BatchScrobbleResponseWrapper, NowPlayingResponse, NowPlayingResponseWrapper, RecentTracksPage,
+    RecentTracksResponseWrapper, ScrobbleCounts, ScrobbleResponse, ScrobbleResponseWrapper, SessionResponse,
+    TrackCorrection, TrackCorrectionResponseWrapper, TrackInfo, TrackInfoResponseWrapper,
 };
+use crate::retry::Retrier;
+use crate::transport::{HttpMethod, HttpRequest, Transport, UreqTransport};
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
 
 pub enum ApiOperation {
+    AuthToken,
     AuthWebSession,
     AuthMobileSession,
     NowPlaying,
     Scrobble,
+    Love,
+    Unlove,
+    RecentTracks,
+    TrackInfo,
+    ArtistInfo,
+    AlbumInfo,
+    TrackCorrection,
+    ArtistCorrection,
 }
 
+#[allow(clippy::uninlined_format_args)]
 impl fmt::Display for ApiOperation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let str = match *self {
+            Self::AuthToken => "auth.getToken",
             Self::AuthWebSession => "auth.getSession",
             Self::AuthMobileSession => "auth.getMobileSession",
             Self::NowPlaying => "track.updateNowPlaying",
             Self::Scrobble => "track.scrobble",
+            Self::Love => "track.love",
+            Self::Unlove => "track.unlove",
+            Self::RecentTracks => "user.getRecentTracks",
+            Self::TrackInfo => "track.getInfo",
+            Self::ArtistInfo => "artist.getInfo",
+            Self::AlbumInfo => "album.getInfo",
+            Self::TrackCorrection => "track.getCorrection",
+            Self::ArtistCorrection => "artist.getCorrection",
         };
         write!(f, "{}", str)
     }
 }
 
+// Request building & response decoding below is shared between the blocking `LastFm` client and the async client, so
+// the two always sign requests and interpret responses identically.
+
+/// Adds the method name, response format & request signature to a request's parameters
+pub fn signed_params(
+    auth: &Credentials,
+    operation: &ApiOperation,
+    mut params: HashMap<String, String>,
+) -> HashMap<String, String> {
+    let signature = auth.get_signature(operation.to_string(), &params);
+
+    params.insert("method".to_string(), operation.to_string());
+    params.insert("api_sig".to_string(), signature);
+    // Not part of the signature, see https://www.last.fm/api/authspec#_8-signing-calls
+    params.insert("format".to_string(), "json".to_string());
+
+    params
+}
+
+/// Adds the API key, method name & response format to the parameters of a read request, which needs no signature
+pub fn unsigned_params(
+    auth: &Credentials,
+    operation: &ApiOperation,
+    params: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut req_params = auth.get_client_request_params();
+    for (k, v) in params {
+        req_params.insert(k.clone(), v.clone());
+    }
+
+    req_params.insert("method".to_string(), operation.to_string());
+    req_params.insert("format".to_string(), "json".to_string());
+
+    req_params
+}
+
+/// Merges a request's parameters with the authenticated user's session parameters
+pub fn authenticated_params(
+    auth: &Credentials,
+    params: &HashMap<String, String>,
+) -> Result<HashMap<String, String>> {
+    if !auth.is_authenticated() {
+        return Err(ScrobblerError::NotAuthenticated(
+            "No session key available".to_string(),
+        ));
+    }
+
+    let mut req_params = auth.get_request_params();
+    for (k, v) in params {
+        req_params.insert(k.clone(), v.clone());
+    }
+
+    Ok(req_params)
+}
+
+/// Checks an API response for errors, returning the response body on success
+pub fn check_response(status: u16, body: String) -> Result<String> {
+    // Last.fm reports API errors as a JSON body with a numbered error code. These are usually, but not always,
+    // sent with a non-success HTTP status, so always check for one.
+    if let Ok(api_error) = serde_json::from_str::<ApiErrorResponse>(&body) {
+        return Err(ScrobblerError::Api {
+            code: api_error.error,
+            message: api_error.message,
+        });
+    }
+
+    if status >= 400 {
+        return Err(ScrobblerError::HttpStatus { status, body });
+    }
+
+    Ok(body)
+}
+
+pub fn decode_token(body: &str) -> Result<String> {
+    let decoded: AuthTokenResponse = serde_json::from_str(body)?;
+    Ok(decoded.token)
+}
+
+pub fn decode_session(body: &str) -> Result<SessionResponse> {
+    let decoded: AuthResponse = serde_json::from_str(body)?;
+    Ok(decoded.session)
+}
+
+pub fn decode_now_playing(body: &str) -> Result<NowPlayingResponse> {
+    let decoded: NowPlayingResponseWrapper = serde_json::from_str(body)?;
+    Ok(decoded.nowplaying)
+}
+
+pub fn decode_scrobble(body: &str) -> Result<ScrobbleResponse> {
+    let decoded: ScrobbleResponseWrapper = serde_json::from_str(body)?;
+    Ok(decoded.scrobbles.scrobble)
+}
+
+pub fn decode_batch_scrobbles(body: &str) -> Result<BatchScrobbleResponse> {
+    let wrapper: BatchScrobbleResponseWrapper = serde_json::from_str(body)?;
+    let scrobbles = wrapper.scrobbles.scrobbles;
+
+    // Count the scrobbles ourselves if the response has no `@attr` summary
+    let counts = wrapper.scrobbles.counts.unwrap_or_else(|| {
+        let ignored = scrobbles.iter().filter(|scrobble| scrobble.is_ignored()).count();
+        ScrobbleCounts {
+            accepted: u32::try_from(scrobbles.len() - ignored).unwrap_or(u32::MAX),
+            ignored: u32::try_from(ignored).unwrap_or(u32::MAX),
+        }
+    });
+
+    Ok(BatchScrobbleResponse { scrobbles, counts })
+}
+
+pub fn decode_recent_tracks(body: &str) -> Result<RecentTracksPage> {
+    let decoded: RecentTracksResponseWrapper = serde_json::from_str(body)?;
+    Ok(decoded.recenttracks)
+}
+
+pub fn decode_track_info(body: &str) -> Result<TrackInfo> {
+    let decoded: TrackInfoResponseWrapper = serde_json::from_str(body)?;
+    Ok(decoded.track)
+}
+
+pub fn decode_artist_info(body: &str) -> Result<ArtistInfo> {
+    let decoded: ArtistInfoResponseWrapper = serde_json::from_str(body)?;
+    Ok(decoded.artist)
+}
+
+pub fn decode_album_info(body: &str) -> Result<AlbumInfo> {
+    let decoded: AlbumInfoResponseWrapper = serde_json::from_str(body)?;
+    Ok(decoded.album)
+}
+
+// A response without a correction gives `None`
+pub fn decode_track_correction(body: &str) -> Result<Option<TrackCorrection>> {
+    let decoded: TrackCorrectionResponseWrapper = serde_json::from_str(body)?;
+    Ok(decoded.corrections.into_iter().next())
+}
+
+pub fn decode_artist_correction(body: &str) -> Result<Option<ArtistCorrection>> {
+    let decoded: ArtistCorrectionResponseWrapper = serde_json::from_str(body)?;
+    Ok(decoded.corrections.into_iter().next())
+}
+
+// Write methods such as track.love respond with an empty JSON object on success
+pub fn decode_empty(body: &str) -> Result<()> {
+    serde_json::from_str::<serde_json::Value>(body)?;
+    Ok(())
+}
+
+pub fn build_auth_url(endpoints: &ApiEndpoints, auth: &Credentials, params: &[(&str, &str)]) -> String {
+    let mut url = url::Url::parse(endpoints.auth_url()).expect("Invalid auth URL");
+    url.query_pairs_mut()
+        .append_pair("api_key", auth.api_key())
+        .extend_pairs(params);
+
+    url.to_string()
+}
+
 pub struct LastFm {
     auth: Credentials,
-    http_client: ureq::Agent,
+    endpoints: ApiEndpoints,
+    transport: Box<dyn Transport>,
+    retrier: Retrier,
 }
 
 impl LastFm {
     pub fn new(api_key: &str, api_secret: &str) -> Self {
+        Self::with_config(api_key, api_secret, ApiEndpoints::default(), Box::new(UreqTransport::new()))
+    }
+
+    pub fn with_config(
+        api_key: &str,
+        api_secret: &str,
+        endpoints: ApiEndpoints,
+        transport: Box<dyn Transport>,
+    ) -> Self {
         let partial_auth = Credentials::new_partial(api_key, api_secret);
-        let http_client = ureq::agent();
 
         Self {
             auth: partial_auth,
-            http_client,
+            endpoints,
+            transport,
+            retrier: Retrier::default(),
         }
     }
 
+    pub fn endpoints(&self) -> &ApiEndpoints {
+        &self.endpoints
+    }
+
+    pub fn set_retrier(&mut self, retrier: Retrier) {
+        self.retrier = retrier;
+    }
+
+    // The retrier's clock & sleeper also time other waits, such as auth token polling
+    pub fn clock(&self) -> &dyn Clock {
+        self.retrier.clock.as_ref()
+    }
+
+    pub fn sleeper(&self) -> &dyn Sleeper {
+        self.retrier.sleeper.as_ref()
+    }
+
     pub fn set_user_credentials(&mut self, username: &str, password: &str) {
         self.auth.set_user_credentials(username, password);
     }
@@ -52,34 +264,41 @@ impl LastFm {
         self.auth.set_user_token(token);
     }
 
-    pub fn authenticate_with_password(&mut self) -> Result<SessionResponse, String> {
-        let params = self.auth.get_auth_request_params()?;
+    pub fn get_token(&self) -> Result<String> {
+        let params = self.auth.get_client_request_params();
 
-        let body = self
-            .api_request(&ApiOperation::AuthMobileSession, params)
-            .map_err(|msg| format!("Authentication failed: {}", msg))?;
-
-        let decoded: AuthResponse = serde_json::from_str(body.as_str())
-            .map_err(|err| format!("Authentication failed: {}", err))?;
+        let body = self.api_request(&ApiOperation::AuthToken, params)?;
+        decode_token(&body)
+    }
 
-        self.auth.set_session_key(&decoded.session.key);
+    pub fn auth_url(&self, token: &str) -> String {
+        build_auth_url(&self.endpoints, &self.auth, &[("token", token)])
+    }
 
-        Ok(decoded.session)
+    pub fn auth_url_with_callback(&self, callback_url: &str) -> String {
+        build_auth_url(&self.endpoints, &self.auth, &[("cb", callback_url)])
     }
 
-    pub fn authenticate_with_token(&mut self) -> Result<SessionResponse, String> {
+    pub fn authenticate_with_password(&mut self) -> Result<SessionResponse> {
         let params = self.auth.get_auth_request_params()?;
 
-        let body = self
-            .api_request(&ApiOperation::AuthWebSession, params)
-            .map_err(|msg| format!("Authentication failed: {}", msg))?;
+        let body = self.api_request(&ApiOperation::AuthMobileSession, params)?;
+        let session = decode_session(&body)?;
+
+        self.auth.set_session_key(&session.key);
+
+        Ok(session)
+    }
+
+    pub fn authenticate_with_token(&mut self) -> Result<SessionResponse> {
+        let params = self.auth.get_auth_request_params()?;
 
-        let decoded: AuthResponse = serde_json::from_str(body.as_str())
-            .map_err(|err| format!("Authentication failed: {}", err))?;
+        let body = self.api_request(&ApiOperation::AuthWebSession, params)?;
+        let session = decode_session(&body)?;
 
-        self.auth.set_session_key(&decoded.session.key);
+        self.auth.set_session_key(&session.key);
 
-        Ok(decoded.session)
+        Ok(session)
     }
 
     /// Authenticates with a session key 
@@ -87,125 +306,118 @@ impl LastFm {
     /// This requires no initial authentication with the API, so we simply store the key. It must be a valid session
     /// key. Session keys are documented at `Scrobbler::authenticate_with_session_key`.
     pub fn authenticate_with_session_key(&mut self, session_key: &str) {
-        self.auth.set_session_key(session_key)
+        self.auth.set_session_key(session_key);
     }
 
     pub fn session_key(&self) -> Option<&str> {
         self.auth.session_key()
     }
 
-    pub fn send_now_playing(
-        &self,
-        params: &HashMap<String, String>,
-    ) -> Result<NowPlayingResponse, String> {
-        let body = self
-            .send_authenticated_request(&ApiOperation::NowPlaying, params)
-            .map_err(|msg| format!("Now playing request failed: {}", msg))?;
+    pub fn clear_session_key(&mut self) {
+        self.auth.clear_session_key();
+    }
 
-        let decoded: NowPlayingResponseWrapper = serde_json::from_str(body.as_str())
-            .map_err(|msg| format!("Now playing request failed: {}", msg))?;
+    pub fn clear_user_credentials(&mut self) {
+        self.auth.clear_user_credentials();
+    }
 
-        Ok(decoded.nowplaying)
+    pub fn send_now_playing(&self, params: &HashMap<String, String>) -> Result<NowPlayingResponse> {
+        let body = self.send_authenticated_request(&ApiOperation::NowPlaying, params)?;
+        decode_now_playing(&body)
     }
 
-    pub fn send_scrobble(
-        &self,
-        params: &HashMap<String, String>,
-    ) -> Result<ScrobbleResponse, String> {
-        let body = self
-            .send_authenticated_request(&ApiOperation::Scrobble, params)
-            .map_err(|msg| format!("Scrobble request failed: {}", msg))?;
+    pub fn send_scrobble(&self, params: &HashMap<String, String>) -> Result<ScrobbleResponse> {
+        let body = self.send_authenticated_request(&ApiOperation::Scrobble, params)?;
+        decode_scrobble(&body)
+    }
 
-        let decoded: ScrobbleResponseWrapper = serde_json::from_str(body.as_str())
-            .map_err(|msg| format!("Scrobble request failed: {}", msg))?;
+    pub fn send_batch_scrobbles(&self, params: &HashMap<String, String>) -> Result<BatchScrobbleResponse> {
+        let body = self.send_authenticated_request(&ApiOperation::Scrobble, params)?;
+        decode_batch_scrobbles(&body)
+    }
 
-        Ok(decoded.scrobbles.scrobble)
+    pub fn send_love(&self, params: &HashMap<String, String>) -> Result<()> {
+        let body = self.send_authenticated_request(&ApiOperation::Love, params)?;
+        decode_empty(&body)
     }
 
-    pub fn send_batch_scrobbles(
-        &self,
-        params: &HashMap<String, String>,
-    ) -> Result<BatchScrobbleResponse, String> {
-        let body = self
-            .send_authenticated_request(&ApiOperation::Scrobble, params)
-            .map_err(|msg| format!("Batch scrobble request failed: {}", msg))?;
+    pub fn send_unlove(&self, params: &HashMap<String, String>) -> Result<()> {
+        let body = self.send_authenticated_request(&ApiOperation::Unlove, params)?;
+        decode_empty(&body)
+    }
 
-        let wrapper: BatchScrobbleResponseWrapper = serde_json::from_str(body.as_str())
-            .map_err(|msg| format!("Batch scrobble request failed: {}", msg))?;
+    pub fn get_recent_tracks(&self, params: &HashMap<String, String>) -> Result<RecentTracksPage> {
+        let body = self.send_read_request(&ApiOperation::RecentTracks, params)?;
+        decode_recent_tracks(&body)
+    }
 
-        Ok(BatchScrobbleResponse {
-            scrobbles: wrapper.scrobbles.scrobbles,
-        })
+    pub fn get_track_info(&self, params: &HashMap<String, String>) -> Result<TrackInfo> {
+        let body = self.send_read_request(&ApiOperation::TrackInfo, params)?;
+        decode_track_info(&body)
+    }
+
+    pub fn get_artist_info(&self, params: &HashMap<String, String>) -> Result<ArtistInfo> {
+        let body = self.send_read_request(&ApiOperation::ArtistInfo, params)?;
+        decode_artist_info(&body)
+    }
+
+    pub fn get_album_info(&self, params: &HashMap<String, String>) -> Result<AlbumInfo> {
+        let body = self.send_read_request(&ApiOperation::AlbumInfo, params)?;
+        decode_album_info(&body)
+    }
+
+    pub fn get_track_correction(&self, params: &HashMap<String, String>) -> Result<Option<TrackCorrection>> {
+        let body = self.send_read_request(&ApiOperation::TrackCorrection, params)?;
+        decode_track_correction(&body)
+    }
+
+    pub fn get_artist_correction(&self, params: &HashMap<String, String>) -> Result<Option<ArtistCorrection>> {
+        let body = self.send_read_request(&ApiOperation::ArtistCorrection, params)?;
+        decode_artist_correction(&body)
+    }
+
+    /// Sends a request for public data, which needs only an API key and is sent unsigned with GET
+    pub fn send_read_request(&self, operation: &ApiOperation, params: &HashMap<String, String>) -> Result<String> {
+        let params = unsigned_params(&self.auth, operation, params);
+        let request = HttpRequest::new(HttpMethod::Get, self.endpoints.api_url(), params);
+
+        self.send(operation, &request, true)
     }
 
     pub fn send_authenticated_request(
         &self,
         operation: &ApiOperation,
         params: &HashMap<String, String>,
-    ) -> Result<String, String> {
-        if !self.auth.is_authenticated() {
-            return Err("Not authenticated".to_string());
-        }
-
-        let mut req_params = self.auth.get_request_params();
-        for (k, v) in params {
-            req_params.insert(k.clone(), v.clone());
-        }
-
-        self.api_request(&operation, req_params)
+    ) -> Result<String> {
+        let req_params = authenticated_params(&self.auth, params)?;
+        self.api_request(operation, req_params)
     }
 
     fn api_request(
         &self,
         operation: &ApiOperation,
         params: HashMap<String, String>,
-    ) -> Result<String, String> {
-        let resp = self
-            .send_request(&operation, params)
-            .map_err(|err| err.to_string())?;
-
-        if resp.error() {
-            return Err(format!("Non Success status ({})", resp.status()));
-        }
-
-        let resp_body = resp
-            .into_string()
-            .map_err(|_| "Failed to read response body".to_string())?;
+    ) -> Result<String> {
+        let params = signed_params(&self.auth, operation, params);
+        let request = HttpRequest::new(HttpMethod::Post, self.endpoints.api_url(), params);
 
-        Ok(resp_body)
+        self.send(operation, &request, false)
     }
 
-    fn send_request(
-        &self,
-        operation: &ApiOperation,
-        mut params: HashMap<String, String>,
-    ) -> Result<ureq::Response, String> {
-        #[cfg(not(test))]
-        let url = "https://ws.audioscrobbler.com/2.0/?format=json";
-        #[cfg(test)]
-        let url = &mockito::server_url();
-
-        let signature = self.auth.get_signature(operation.to_string(), &params);
-
-        params.insert("method".to_string(), operation.to_string());
-        params.insert("api_sig".to_string(), signature);
-
-        let params: Vec<(&str, &str)> = params
-            .iter()
-            .map(|(k, v)| (k.as_str(), v.as_str()))
-            .collect();
-
-        let resp = self.http_client.post(url).send_form(&params[..]);
-        match resp.synthetic_error() {
-            None => Ok(resp),
-            Some(e) => Err(e.to_string()),
-        }
+    // Sends the request, retrying transient failures as set by the retry policy. Only `idempotent` requests are
+    // retried after transport failures.
+    fn send(&self, operation: &ApiOperation, request: &HttpRequest, idempotent: bool) -> Result<String> {
+        self.retrier.run(&operation.to_string(), idempotent, || {
+            let resp = self.transport.send(request)?;
+            check_response(resp.status, resp.body)
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::responses::IgnoreReason;
     use mockito::mock;
 
     #[test]
@@ -234,6 +446,85 @@ mod tests {
         assert!(resp.is_ok());
     }
 
+    #[test]
+    fn check_api_error_responses() {
+        let mut client = LastFm::new("key", "secret");
+        client.auth.set_session_key("SeshKey");
+        let params = HashMap::new();
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_status(403)
+            .with_body(r#"{"error": 9, "message": "Invalid session key - Please re-authenticate"}"#)
+            .create();
+
+        let err = client.send_scrobble(&params).unwrap_err();
+        assert_eq!(err.api_error_code(), Some(9));
+        match err {
+            ScrobblerError::Api { message, .. } => {
+                assert_eq!(message, "Invalid session key - Please re-authenticate");
+            }
+            other => panic!("Unexpected error: {other:?}"),
+        }
+
+        // API errors can also be sent with a success status
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_body(r#"{"error": 11, "message": "Service Offline"}"#)
+            .create();
+
+        let err = client.send_scrobble(&params).unwrap_err();
+        assert_eq!(err.api_error_code(), Some(11));
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_status(502)
+            .with_body("Bad Gateway")
+            .create();
+
+        let err = client.send_scrobble(&params).unwrap_err();
+        assert_eq!(err.http_status(), Some(502));
+        assert!(err.api_error_code().is_none());
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_body("not json")
+            .create();
+
+        let err = client.send_scrobble(&params).unwrap_err();
+        assert!(matches!(err, ScrobblerError::Decode(_)));
+    }
+
+    #[test]
+    fn check_unauthenticated_request() {
+        let client = LastFm::new("key", "secret");
+        let err = client
+            .send_authenticated_request(&ApiOperation::Scrobble, &HashMap::new())
+            .unwrap_err();
+        assert!(matches!(err, ScrobblerError::NotAuthenticated(_)));
+    }
+
+    #[test]
+    fn check_get_token_and_auth_urls() {
+        let client = LastFm::new("key", "secret");
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("method".into(), "auth.getToken".into()),
+                mockito::Matcher::UrlEncoded("api_key".into(), "key".into()),
+            ]))
+            .with_body(r#"{"token": "cf45fe5a3e3cebe168480a086d7fe481"}"#)
+            .create();
+
+        let token = client.get_token().unwrap();
+        assert_eq!(token, "cf45fe5a3e3cebe168480a086d7fe481");
+
+        assert_eq!(
+            client.auth_url(&token),
+            "https://www.last.fm/api/auth/?api_key=key&token=cf45fe5a3e3cebe168480a086d7fe481"
+        );
+        assert_eq!(
+            client.auth_url_with_callback("http://example.com/callback?a=b"),
+            "https://www.last.fm/api/auth/?api_key=key&cb=http%3A%2F%2Fexample.com%2Fcallback%3Fa%3Db"
+        );
+    }
+
     #[test]
     fn check_send_scrobble() {
         let _m = mock("POST", mockito::Matcher::Any).create();
@@ -331,6 +622,74 @@ mod tests {
 
         let resp = client.send_batch_scrobbles(&params);
         assert!(resp.is_ok());
+        assert_eq!(resp.unwrap().counts, ScrobbleCounts { accepted: 2, ignored: 0 });
+    }
+
+    #[test]
+    fn check_decode_ignored_scrobbles() {
+        let resp = decode_batch_scrobbles(
+            r##"
+            {
+                "scrobbles": {
+                    "scrobble": [
+                        {
+                            "artist": { "corrected": "0", "#text": "foo floyd and the fruit flies" },
+                            "album": { "corrected": "0", "#text": "old bananas" },
+                            "albumArtist": { "corrected": "0", "#text": "" },
+                            "track": { "corrected": "0", "#text": "old bananas" },
+                            "timestamp": "1337",
+                            "ignoredMessage": { "code": "3", "#text": "Timestamp too old" }
+                        },
+                        {
+                            "artist": { "corrected": "0", "#text": "foo floyd and the fruit flies" },
+                            "album": { "corrected": "0", "#text": "old bananas" },
+                            "albumArtist": { "corrected": "0", "#text": "" },
+                            "track": { "corrected": "0", "#text": "old bananas" },
+                            "timestamp": "1337",
+                            "ignoredMessage": { "code": "5", "#text": "" }
+                        }
+                    ],
+                    "@attr": { "accepted": 0, "ignored": "2" }
+                }
+            }
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(resp.counts, ScrobbleCounts { accepted: 0, ignored: 2 });
+        assert!(!resp.all_accepted());
+
+        let reasons: Vec<_> = resp.scrobbles.iter().map(ScrobbleResponse::ignore_reason).collect();
+        assert_eq!(
+            reasons,
+            vec![Some(IgnoreReason::TimestampTooOld), Some(IgnoreReason::DailyLimitExceeded)]
+        );
+
+        assert_eq!(IgnoreReason::from_code(0), None);
+        assert_eq!(IgnoreReason::from_code(1), Some(IgnoreReason::ArtistIgnored));
+        assert_eq!(IgnoreReason::from_code(2).map(IgnoreReason::code), Some(2));
+        assert_eq!(IgnoreReason::from_code(4), Some(IgnoreReason::TimestampTooNew));
+        assert_eq!(IgnoreReason::from_code(42), Some(IgnoreReason::Other(42)));
+    }
+
+    #[test]
+    fn check_decode_malformed_ignored_message() {
+        let ignored_messages = [r##"{ "code": "", "#text": "" }"##, r#"{ "code": "ignored" }"#, r##"{ "#text": "" }"##];
+        for ignored_message in ignored_messages {
+            let body = format!(
+                r##"{{ "scrobbles": {{ "scrobble": [{{
+                    "artist": {{ "corrected": "0", "#text": "foo floyd and the fruit flies" }},
+                    "album": {{ "corrected": "0", "#text": "old bananas" }},
+                    "albumArtist": {{ "corrected": "0", "#text": "" }},
+                    "track": {{ "corrected": "0", "#text": "old bananas" }},
+                    "timestamp": "1337",
+                    "ignoredMessage": {ignored_message}
+                }}] }} }}"##
+            );
+
+            let err = decode_batch_scrobbles(&body).unwrap_err();
+            assert!(matches!(err, ScrobblerError::Decode(_)), "{err:?}");
+        }
     }
 
     #[test]
@@ -401,4 +760,57 @@ mod tests {
         client.authenticate_with_session_key("seshkey");
         assert_eq!("seshkey", client.session_key().unwrap());
     }
+
+    #[test]
+    fn check_decode_extended_recent_tracks() {
+        let page = decode_recent_tracks(
+            r##"
+            {
+                "recenttracks": {
+                    "track": [
+                        {
+                            "artist": {
+                                "url": "https://www.last.fm/music/foo+floyd",
+                                "name": "foo floyd",
+                                "image": [],
+                                "mbid": "2f9ecbed-27be-40e6-abca-6de49d50299e"
+                            },
+                            "mbid": "",
+                            "album": { "mbid": "", "#text": "old bananas" },
+                            "name": "old bananas",
+                            "url": "https://www.last.fm/music/foo+floyd/_/old+bananas",
+                            "image": [
+                                { "size": "small", "#text": "https://example.com/34s.png" },
+                                { "size": "extralarge", "#text": "https://example.com/300x300.png" }
+                            ],
+                            "streamable": "0",
+                            "loved": "1",
+                            "date": { "uts": "1600000000", "#text": "13 Sep 2020, 12:26" }
+                        }
+                    ],
+                    "@attr": { "user": "username", "page": "3", "perPage": "50", "totalPages": "40", "total": "1999" }
+                }
+            }
+        "##,
+        )
+        .unwrap();
+
+        assert_eq!(page.pagination.page, 3);
+        assert_eq!(page.pagination.total_pages, 40);
+        assert_eq!(page.pagination.total, 1999);
+
+        let track = &page.tracks[0];
+        assert_eq!(track.artist.name, "foo floyd");
+        assert_eq!(track.artist.mbid.as_deref(), Some("2f9ecbed-27be-40e6-abca-6de49d50299e"));
+        assert_eq!(track.album.mbid, None);
+        assert_eq!(track.mbid, None);
+        assert_eq!(track.loved, Some(true));
+        assert!(!track.now_playing);
+        assert_eq!(track.images[1].size, "extralarge");
+        assert_eq!(track.images[1].url, "https://example.com/300x300.png");
+
+        let scrobble = track.to_scrobble();
+        assert_eq!(scrobble.artist(), "foo floyd");
+        assert_eq!(scrobble.timestamp(), Some(1_600_000_000));
+    }
 }
@@ -0,0 +1,72 @@
+// Injectable time source
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// A source of the current time.
+///
+/// Components that make time-based decisions (such as [`PlayTracker`]) take a `Clock`, so they can be driven by a
+/// fake clock in tests. [`SystemClock`] is the default, real-time implementation. Closures returning a `SystemTime`
+/// also implement `Clock`.
+///
+/// [`PlayTracker`]: struct.PlayTracker.html
+/// [`SystemClock`]: struct.SystemClock.html
+pub trait Clock {
+    /// Returns the current time
+    fn now(&self) -> SystemTime;
+}
+
+/// A [`Clock`] reading the system's real-time clock.
+///
+/// [`Clock`]: trait.Clock.html
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> SystemTime,
+{
+    fn now(&self) -> SystemTime {
+        self()
+    }
+}
+
+/// Waits for a period of time.
+///
+/// Used alongside a [`Clock`] by components that wait between attempts (such as the retries of a [`RetryPolicy`]),
+/// so tests can run without actually waiting. [`ThreadSleeper`] is the default implementation. Closures taking a
+/// `Duration` also implement `Sleeper`.
+///
+/// [`Clock`]: trait.Clock.html
+/// [`RetryPolicy`]: struct.RetryPolicy.html
+/// [`ThreadSleeper`]: struct.ThreadSleeper.html
+pub trait Sleeper {
+    /// Blocks for the given duration
+    fn sleep(&self, duration: Duration);
+}
+
+/// A [`Sleeper`] blocking the current thread with `std::thread::sleep`.
+///
+/// [`Sleeper`]: trait.Sleeper.html
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadSleeper;
+
+impl Sleeper for ThreadSleeper {
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+impl<F> Sleeper for F
+where
+    F: Fn(Duration),
+{
+    fn sleep(&self, duration: Duration) {
+        self(duration);
+    }
+}
@@ -0,0 +1,153 @@
+// Applying Last.fm metadata corrections to Scrobbles
+use std::fmt;
+
+use crate::models::metadata::Scrobble;
+
+/// A metadata field of a [`Scrobble`] that Last.fm can correct
+///
+/// [`Scrobble`]: struct.Scrobble.html
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ScrobbleField {
+    Artist,
+    Track,
+    Album,
+    AlbumArtist,
+}
+
+impl fmt::Display for ScrobbleField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match *self {
+            Self::Artist => "artist",
+            Self::Track => "track",
+            Self::Album => "album",
+            Self::AlbumArtist => "album artist",
+        };
+        write!(f, "{str}")
+    }
+}
+
+/// A single field changed by a correction, with its value before & after
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FieldChange {
+    pub field: ScrobbleField,
+    pub from: String,
+    pub to: String,
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: \"{}\" -> \"{}\"", self.field, self.from, self.to)
+    }
+}
+
+/// A copy of a [`Scrobble`] with Last.fm's metadata corrections applied, and the list of fields that changed.
+///
+/// Returned by the `apply` methods of the correction lookup responses, such as [`TrackCorrection::apply`]. Other
+/// values of the scrobble (timestamp, duration...) are kept as they were.
+///
+/// # Usage
+/// ```ignore
+/// if let Some(correction) = scrobbler.track_correction(track.artist(), track.track())? {
+///     let corrected = correction.apply(&track);
+///     for change in &corrected.changes {
+///         println!("Last.fm suggests {change}");
+///     }
+/// }
+/// ```
+///
+/// [`Scrobble`]: struct.Scrobble.html
+/// [`TrackCorrection::apply`]: responses/struct.TrackCorrection.html#method.apply
+#[derive(Clone, Debug)]
+pub struct CorrectedScrobble {
+    pub scrobble: Scrobble,
+    /// Fields whose value was changed, in the order they were corrected
+    pub changes: Vec<FieldChange>,
+}
+
+impl CorrectedScrobble {
+    pub(crate) fn new(original: &Scrobble) -> Self {
+        Self {
+            scrobble: original.clone(),
+            changes: Vec::new(),
+        }
+    }
+
+    /// Returns true if any field was changed by the correction
+    #[must_use]
+    pub fn is_changed(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// Returns the change made to the given field, if it was changed
+    #[must_use]
+    pub fn change(&self, field: ScrobbleField) -> Option<&FieldChange> {
+        self.changes.iter().find(|change| change.field == field)
+    }
+
+    // Sets a field to its corrected value, recording the change if the value differs
+    pub(crate) fn set(&mut self, field: ScrobbleField, value: &str) {
+        let current = match field {
+            ScrobbleField::Artist => self.scrobble.artist(),
+            ScrobbleField::Track => self.scrobble.track(),
+            ScrobbleField::Album => self.scrobble.album(),
+            ScrobbleField::AlbumArtist => self.scrobble.album_artist().unwrap_or_default(),
+        };
+        if current == value {
+            return;
+        }
+
+        let from = current.to_owned();
+        match field {
+            ScrobbleField::Artist => self.scrobble.with_artist(value),
+            ScrobbleField::Track => self.scrobble.with_track(value),
+            ScrobbleField::Album => self.scrobble.with_album(value),
+            ScrobbleField::AlbumArtist => self.scrobble.with_album_artist(value),
+        };
+
+        self.changes.push(FieldChange {
+            field,
+            from,
+            to: value.to_owned(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_corrected_scrobble_changes() {
+        let mut original = Scrobble::new("foo floyd", "old bananas", "old bananas");
+        original.with_duration(241);
+
+        let mut corrected = CorrectedScrobble::new(&original);
+        assert!(!corrected.is_changed());
+
+        corrected.set(ScrobbleField::Track, "old bananas");
+        corrected.set(ScrobbleField::AlbumArtist, "");
+        assert!(!corrected.is_changed());
+
+        corrected.set(ScrobbleField::Album, "Old Bananas");
+        corrected.set(ScrobbleField::AlbumArtist, "Foo Floyd");
+
+        assert_eq!(
+            corrected.changes,
+            vec![
+                FieldChange {
+                    field: ScrobbleField::Album,
+                    from: "old bananas".to_owned(),
+                    to: "Old Bananas".to_owned(),
+                },
+                FieldChange {
+                    field: ScrobbleField::AlbumArtist,
+                    from: String::new(),
+                    to: "Foo Floyd".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(corrected.scrobble.album_artist(), Some("Foo Floyd"));
+        assert_eq!(corrected.scrobble.duration(), Some(241));
+        assert_eq!(corrected.changes[0].to_string(), "album: \"old bananas\" -> \"Old Bananas\"");
+    }
+}
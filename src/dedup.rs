@@ -0,0 +1,234 @@
+// Local index of recently submitted scrobbles, used to avoid submitting the same play twice
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::models::responses::ChunkedScrobbleResponse;
+
+const DEFAULT_CAPACITY: usize = 1000;
+const DEFAULT_TOLERANCE_SECS: u64 = 30;
+
+/// A bounded index of recently submitted scrobbles, used by [`Scrobbler::scrobble_deduplicated`] and
+/// [`Scrobbler::scrobble_batch_deduplicated`] to drop duplicate plays before they are sent.
+///
+/// A scrobble is a duplicate of an indexed scrobble with the same artist & track (ignoring case) whose timestamp
+/// differs by at most the filter's tolerance, 30 seconds by default. A track must be longer than 30 seconds to be
+/// scrobbled, so two genuine plays of it are never that close together. This catches a crashed player replaying its
+/// queue, or a scrobble re-sent with a freshly generated timestamp.
+///
+/// The index holds at most `capacity` scrobbles (1000 by default), forgetting the oldest (by timestamp) first. It can
+/// be seeded with the plays Last.fm already has with [`Scrobbler::seed_duplicate_filter`], or with locally known
+/// plays using [`DuplicateFilter::insert`].
+///
+/// # Usage
+/// ```ignore
+/// let mut filter = DuplicateFilter::new();
+/// let mut query = RecentTracksQuery::new("username");
+/// query.with_limit(200);
+/// scrobbler.seed_duplicate_filter(&mut filter, &query)?;
+///
+/// match scrobbler.scrobble_deduplicated(&song, &mut filter)? {
+///     Some(response) => println!("Scrobbled {}", response.track),
+///     None => println!("Already scrobbled"),
+/// }
+/// ```
+///
+/// [`Scrobbler::scrobble_deduplicated`]: struct.Scrobbler.html#method.scrobble_deduplicated
+/// [`Scrobbler::scrobble_batch_deduplicated`]: struct.Scrobbler.html#method.scrobble_batch_deduplicated
+/// [`Scrobbler::seed_duplicate_filter`]: struct.Scrobbler.html#method.seed_duplicate_filter
+/// [`DuplicateFilter::insert`]: struct.DuplicateFilter.html#method.insert
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DuplicateFilter {
+    capacity: usize,
+    tolerance: u64,
+    // (timestamp, artist, track), with artist & track lowercased
+    entries: BTreeSet<(u64, String, String)>,
+}
+
+impl Default for DuplicateFilter {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            tolerance: DEFAULT_TOLERANCE_SECS,
+            entries: BTreeSet::new(),
+        }
+    }
+}
+
+impl DuplicateFilter {
+    /// Creates an empty filter holding up to 1000 scrobbles, with a 30 second timestamp tolerance
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of scrobbles held, dropping the oldest ones if the filter already holds more
+    pub fn with_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.capacity = capacity;
+        self.evict();
+        self
+    }
+
+    /// Sets how far apart (at most) the timestamps of two plays of a track can be for them to be duplicates. Only
+    /// whole seconds are used.
+    pub fn with_tolerance(&mut self, tolerance: Duration) -> &mut Self {
+        self.tolerance = tolerance.as_secs();
+        self
+    }
+
+    /// Returns the maximum number of scrobbles held
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns how far apart the timestamps of two plays of a track can be for them to be duplicates
+    #[must_use]
+    pub fn tolerance(&self) -> Duration {
+        Duration::from_secs(self.tolerance)
+    }
+
+    /// Returns the number of scrobbles held
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no scrobbles are held
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns true if the given scrobble duplicates a scrobble held by the filter. Scrobbles without a timestamp are
+    /// never duplicates.
+    #[must_use]
+    pub fn is_duplicate(&self, scrobble: &Scrobble) -> bool {
+        scrobble.timestamp().is_some_and(|timestamp| {
+            let (artist, track) = key(scrobble);
+            self.entries
+                .range((timestamp.saturating_sub(self.tolerance), String::new(), String::new())..)
+                .take_while(|(other, _, _)| *other <= timestamp.saturating_add(self.tolerance))
+                .any(|(_, a, t)| *a == artist && *t == track)
+        })
+    }
+
+    /// Adds a scrobble to the filter, so later plays of the same track at (about) the same time are duplicates.
+    /// Scrobbles without a timestamp are not added.
+    pub fn insert(&mut self, scrobble: &Scrobble) {
+        if let Some(timestamp) = scrobble.timestamp() {
+            let (artist, track) = key(scrobble);
+            self.entries.insert((timestamp, artist, track));
+            self.evict();
+        }
+    }
+
+    /// Removes every scrobble from the filter
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // Splits a batch into the batch indices of its new scrobbles & its duplicates, including scrobbles repeated
+    // within the batch
+    pub(crate) fn partition(&self, batch: &ScrobbleBatch) -> (Vec<usize>, Vec<usize>) {
+        let mut pending = self.clone();
+        pending.capacity = usize::MAX;
+
+        let (mut new, mut duplicates) = (Vec::new(), Vec::new());
+        for (i, scrobble) in batch.iter().enumerate() {
+            if pending.is_duplicate(scrobble) {
+                duplicates.push(i);
+            } else {
+                pending.insert(scrobble);
+                new.push(i);
+            }
+        }
+
+        (new, duplicates)
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            self.entries.pop_first();
+        }
+    }
+}
+
+fn key(scrobble: &Scrobble) -> (String, String) {
+    (
+        scrobble.artist().trim().to_lowercase(),
+        scrobble.track().trim().to_lowercase(),
+    )
+}
+
+/// Response to [`Scrobbler::scrobble_batch_deduplicated`]
+///
+/// [`Scrobbler::scrobble_batch_deduplicated`]: struct.Scrobbler.html#method.scrobble_batch_deduplicated
+#[derive(Debug, Default)]
+pub struct DeduplicatedBatchResponse {
+    /// Batch indices of the scrobbles dropped as duplicates, which were not sent
+    pub duplicates: Vec<usize>,
+    /// Batch indices of the scrobbles that were sent, in submission order
+    pub submitted: Vec<usize>,
+    /// Response to the submission of the non-duplicate scrobbles. Its indices are positions in `submitted`; use
+    /// [`DeduplicatedBatchResponse::batch_index`] to map them back to the original batch.
+    ///
+    /// [`DeduplicatedBatchResponse::batch_index`]: struct.DeduplicatedBatchResponse.html#method.batch_index
+    pub response: ChunkedScrobbleResponse,
+}
+
+impl DeduplicatedBatchResponse {
+    /// Returns the index in the original batch of the scrobble at the given index of `response`
+    #[must_use]
+    pub fn batch_index(&self, index: usize) -> Option<usize> {
+        self.submitted.get(index).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrobble(track: &str, timestamp: u64) -> Scrobble {
+        let mut scrobble = Scrobble::new("foo floyd", track, "old bananas");
+        scrobble.with_timestamp(timestamp);
+        scrobble
+    }
+
+    #[test]
+    fn check_duplicate_filter() {
+        let mut filter = DuplicateFilter::new();
+        filter.insert(&scrobble("old bananas", 1_600_000_000));
+        filter.insert(&Scrobble::new("foo floyd", "no timestamp", ""));
+        assert_eq!(filter.len(), 1);
+
+        assert!(filter.is_duplicate(&scrobble("old bananas", 1_600_000_000)));
+        assert!(filter.is_duplicate(&scrobble("Old Bananas ", 1_600_000_030)));
+        assert!(filter.is_duplicate(&scrobble("old bananas", 1_599_999_970)));
+        assert!(!filter.is_duplicate(&scrobble("old bananas", 1_600_000_031)));
+        assert!(!filter.is_duplicate(&scrobble("new bananas", 1_600_000_000)));
+        assert!(!filter.is_duplicate(&Scrobble::new("foo floyd", "old bananas", "")));
+
+        filter.with_tolerance(Duration::from_secs(0));
+        assert!(!filter.is_duplicate(&scrobble("old bananas", 1_600_000_001)));
+
+        // The oldest scrobbles are dropped first
+        filter.insert(&scrobble("new bananas", 1_600_000_300));
+        filter.insert(&scrobble("older bananas", 1_599_999_000));
+        filter.with_capacity(2);
+        assert_eq!(filter.len(), 2);
+        assert!(!filter.is_duplicate(&scrobble("older bananas", 1_599_999_000)));
+        filter.insert(&scrobble("older bananas", 1_599_999_000));
+        assert!(!filter.is_duplicate(&scrobble("older bananas", 1_599_999_000)));
+        assert!(filter.is_duplicate(&scrobble("new bananas", 1_600_000_300)));
+
+        let batch = ScrobbleBatch::from(vec![
+            scrobble("new bananas", 1_600_000_300),
+            scrobble("ripe bananas", 1_600_000_600),
+            scrobble("ripe bananas", 1_600_000_600),
+            scrobble("old bananas", 1_600_000_001),
+        ]);
+        assert_eq!(filter.partition(&batch), (vec![1, 3], vec![0, 2]));
+        assert_eq!(filter.len(), 2);
+    }
+}
@@ -0,0 +1,109 @@
+// API endpoints of Last.fm compatible scrobbling services
+use crate::error::ScrobblerError;
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+const LAST_FM_API_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+const LAST_FM_AUTH_URL: &str = "https://www.last.fm/api/auth/";
+const LIBRE_FM_API_URL: &str = "https://libre.fm/2.0/";
+const LIBRE_FM_AUTH_URL: &str = "https://libre.fm/api/auth/";
+
+/// The endpoints of a scrobbling service speaking the Last.fm 2.0 API.
+///
+/// A [`Scrobbler`] talks to Last.fm by default. Other services implementing the same API (such as Libre.fm, Maloja
+/// or a self-hosted server) can be used by building the `Scrobbler` with their endpoints, see
+/// [`Scrobbler::builder`].
+///
+/// # Usage
+/// ```ignore
+/// let scrobbler = Scrobbler::builder(api_key, api_secret)
+///     .endpoints(ApiEndpoints::libre_fm())
+///     .build();
+///
+/// let self_hosted = ApiEndpoints::new("https://scrobble.example.com/2.0/", "https://scrobble.example.com/auth/")?;
+/// ```
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`Scrobbler::builder`]: struct.Scrobbler.html#method.builder
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ApiEndpoints {
+    api_url: String,
+    auth_url: String,
+}
+
+impl ApiEndpoints {
+    /// Creates endpoints for a custom service, from the root URL of its 2.0 API and the web URL users visit to
+    /// approve authentication tokens.
+    ///
+    /// # Errors
+    /// Returns an error if either URL is not a valid absolute URL.
+    pub fn new(api_url: &str, auth_url: &str) -> Result<Self> {
+        for url in [api_url, auth_url] {
+            url::Url::parse(url).map_err(|err| ScrobblerError::new(format!("Invalid URL '{url}': {err}")))?;
+        }
+
+        Ok(Self {
+            api_url: api_url.to_owned(),
+            auth_url: auth_url.to_owned(),
+        })
+    }
+
+    /// The Last.fm endpoints, used by default
+    #[must_use]
+    pub fn last_fm() -> Self {
+        #[cfg(not(test))]
+        let api_url = LAST_FM_API_URL.to_owned();
+        #[cfg(test)]
+        let api_url = mockito::server_url();
+
+        Self {
+            api_url,
+            auth_url: LAST_FM_AUTH_URL.to_owned(),
+        }
+    }
+
+    /// The [Libre.fm](https://libre.fm) endpoints
+    #[must_use]
+    pub fn libre_fm() -> Self {
+        Self {
+            api_url: LIBRE_FM_API_URL.to_owned(),
+            auth_url: LIBRE_FM_AUTH_URL.to_owned(),
+        }
+    }
+
+    /// Root URL of the service's 2.0 API
+    #[must_use]
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    /// Web URL users visit to approve authentication tokens
+    #[must_use]
+    pub fn auth_url(&self) -> &str {
+        &self.auth_url
+    }
+}
+
+impl Default for ApiEndpoints {
+    fn default() -> Self {
+        Self::last_fm()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_endpoints() {
+        assert_eq!(ApiEndpoints::default(), ApiEndpoints::last_fm());
+        assert_eq!(ApiEndpoints::last_fm().auth_url(), LAST_FM_AUTH_URL);
+        assert_eq!(ApiEndpoints::libre_fm().api_url(), LIBRE_FM_API_URL);
+
+        let custom = ApiEndpoints::new("https://scrobble.example.com/2.0/", "https://scrobble.example.com/auth/");
+        assert_eq!(custom.unwrap().api_url(), "https://scrobble.example.com/2.0/");
+
+        assert!(ApiEndpoints::new("scrobble.example.com", LAST_FM_AUTH_URL).is_err());
+        assert!(ApiEndpoints::new(LAST_FM_API_URL, "").is_err());
+    }
+}
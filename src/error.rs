@@ -1,28 +1,91 @@
 use std::error::Error as StdError;
 use std::fmt;
+use std::io;
 use std::time::SystemTimeError;
 
 /// Represents an Error that occurred while interacting with the Last.fm API
 /// 
-/// `ScrobblerError` contains an error message, which is set when an error occurs and exposed via Trait standard error
-/// Trait implementations. 
+/// Each variant describes a distinct class of failure, so clients can decide how to react (retry, re-authenticate,
+/// fix the submitted data...) without inspecting error message strings. All variants have a human-readable
+/// message exposed via the standard `Display`/`Error` Trait implementations.
+/// 
+/// Errors reported by the Last.fm API itself (for example `{"error": 9, "message": "Invalid session key"}`) are
+/// returned as [`ScrobblerError::Api`], carrying the numbered API error code and the message from the response body.
+/// The full list of codes is given in the [Last.fm API Documentation](https://www.last.fm/api/errorcodes).
 /// 
 /// Most error handling for clients can operate off the `Ok`/`Err` signaling from the `Result` types of API operations,
 /// however this error type is exposed in case you want to implement more complex error handling.
+/// 
+/// [`ScrobblerError::Api`]: enum.ScrobblerError.html#variant.Api
 #[derive(Debug)]
-pub struct ScrobblerError {
-    err_msg: String,
+#[non_exhaustive]
+pub enum ScrobblerError {
+    /// The HTTP request could not be sent, or the response could not be read (DNS, connection, TLS, I/O failures)
+    Transport(String),
+    /// The API responded with a non-success HTTP status and no Last.fm error could be parsed from the body
+    HttpStatus { status: u16, body: String },
+    /// The API response body could not be decoded into the expected response type
+    Decode(String),
+    /// The request requires user authentication (or user credentials) that have not been provided
+    NotAuthenticated(String),
+    /// A [`ScrobbleBatch`] was rejected before submission (for example: empty, or too many scrobbles)
+    ///
+    /// [`ScrobbleBatch`]: struct.ScrobbleBatch.html
+    InvalidBatch(String),
+    /// A numbered error returned by the Last.fm API, with the message given in the response body
+    Api { code: u32, message: String },
+    /// A failure reply from the Audioscrobbler 1.2 submission protocol (`BANNED`, `BADTIME`, `FAILED <reason>`...)
+    Protocol(String),
+    /// A file being imported (such as a `.scrobbler.log` or listening history export) could not be read as that format
+    Import(String),
+    /// Reading or writing local state (stored sessions, queues...) failed
+    Io(String),
+    /// The current time could not be read from the system clock (for example: it is set before the Unix epoch)
+    Time(String),
+    /// Any other error
+    Other(String),
 }
 
 impl ScrobblerError {
+    #[must_use]
     pub fn new(err_msg: String) -> Self {
-        ScrobblerError { err_msg }
+        ScrobblerError::Other(err_msg)
+    }
+
+    /// Returns the numbered Last.fm API error code, if this error was reported by the Last.fm API
+    #[must_use]
+    pub fn api_error_code(&self) -> Option<u32> {
+        match *self {
+            ScrobblerError::Api { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Returns the HTTP status code of the API response that caused this error, where one is known
+    #[must_use]
+    pub fn http_status(&self) -> Option<u16> {
+        match *self {
+            ScrobblerError::HttpStatus { status, .. } => Some(status),
+            _ => None,
+        }
     }
 }
 
 impl fmt::Display for ScrobblerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.err_msg)
+        match self {
+            ScrobblerError::Transport(msg) => write!(f, "HTTP transport error: {msg}"),
+            ScrobblerError::HttpStatus { status, .. } => write!(f, "Non Success status ({status})"),
+            ScrobblerError::Decode(msg) => write!(f, "Failed to decode API response: {msg}"),
+            ScrobblerError::NotAuthenticated(msg) => write!(f, "Not authenticated: {msg}"),
+            ScrobblerError::InvalidBatch(msg) => write!(f, "Invalid scrobble batch: {msg}"),
+            ScrobblerError::Api { code, message } => write!(f, "Last.fm API error {code}: {message}"),
+            ScrobblerError::Protocol(msg) => write!(f, "Audioscrobbler protocol error: {msg}"),
+            ScrobblerError::Import(msg) => write!(f, "Import error: {msg}"),
+            ScrobblerError::Io(msg) => write!(f, "I/O error: {msg}"),
+            ScrobblerError::Time(msg) => write!(f, "System clock error: {msg}"),
+            ScrobblerError::Other(msg) => write!(f, "{msg}"),
+        }
     }
 }
 
@@ -30,7 +93,7 @@ impl StdError for ScrobblerError {}
 
 impl From<SystemTimeError> for ScrobblerError {
     fn from(error: SystemTimeError) -> Self {
-        Self::new(error.to_string())
+        ScrobblerError::Time(error.to_string())
     }
 }
 
@@ -39,3 +102,15 @@ impl From<String> for ScrobblerError {
         Self::new(error)
     }
 }
+
+impl From<serde_json::Error> for ScrobblerError {
+    fn from(error: serde_json::Error) -> Self {
+        ScrobblerError::Decode(error.to_string())
+    }
+}
+
+impl From<io::Error> for ScrobblerError {
+    fn from(error: io::Error) -> Self {
+        ScrobblerError::Io(error.to_string())
+    }
+}
@@ -0,0 +1,162 @@
+// Reading a user's listening history back from Last.fm
+use std::collections::HashMap;
+use std::vec;
+
+use crate::error::ScrobblerError;
+use crate::models::responses::RecentTrack;
+use crate::scrobbler::Scrobbler;
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+/// Parameters of a `user.getRecentTracks` request, used with [`Scrobbler::recent_tracks`] and
+/// [`Scrobbler::recent_tracks_page`].
+///
+/// Only the username is required. Other options are set with the builder-style `with_*` methods.
+///
+/// # Usage
+/// ```ignore
+/// let mut query = RecentTracksQuery::new("username");
+/// query.with_from(1_600_000_000).with_limit(200).with_extended(true);
+/// ```
+///
+/// # Last.fm API Documentation
+/// [user.getRecentTracks API Method Documentation](https://www.last.fm/api/show/user.getRecentTracks)
+///
+/// [`Scrobbler::recent_tracks`]: struct.Scrobbler.html#method.recent_tracks
+/// [`Scrobbler::recent_tracks_page`]: struct.Scrobbler.html#method.recent_tracks_page
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RecentTracksQuery {
+    user: String,
+    from: Option<u64>,
+    to: Option<u64>,
+    limit: Option<u32>,
+    extended: bool,
+}
+
+impl RecentTracksQuery {
+    /// Creates a query for the listening history of the given Last.fm user
+    #[must_use]
+    pub fn new(user: &str) -> Self {
+        Self {
+            user: user.to_owned(),
+            from: None,
+            to: None,
+            limit: None,
+            extended: false,
+        }
+    }
+
+    /// Only returns tracks scrobbled after this UNIX timestamp
+    pub fn with_from(&mut self, from: u64) -> &mut Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Only returns tracks scrobbled before this UNIX timestamp
+    pub fn with_to(&mut self, to: u64) -> &mut Self {
+        self.to = Some(to);
+        self
+    }
+
+    /// Sets the number of tracks per page. Last.fm defaults to 50, and allows at most 200.
+    pub fn with_limit(&mut self, limit: u32) -> &mut Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Requests extended data: full artist details and whether the user has loved each track
+    pub fn with_extended(&mut self, extended: bool) -> &mut Self {
+        self.extended = extended;
+        self
+    }
+
+    #[must_use]
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    pub(crate) fn params(&self, page: u32) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        params.insert("user".to_string(), self.user.clone());
+        params.insert("page".to_string(), page.to_string());
+
+        if let Some(from) = self.from {
+            params.insert("from".to_string(), from.to_string());
+        }
+        if let Some(to) = self.to {
+            params.insert("to".to_string(), to.to_string());
+        }
+        if let Some(limit) = self.limit {
+            params.insert("limit".to_string(), limit.to_string());
+        }
+        if self.extended {
+            params.insert("extended".to_string(), "1".to_string());
+        }
+
+        params
+    }
+}
+
+/// Lazy iterator over a user's listening history, created with [`Scrobbler::recent_tracks`].
+///
+/// Pages are requested one at a time as the iterator advances, until `@attr.totalPages` is reached. If a page request
+/// fails, its error is yielded and iteration ends.
+///
+/// [`Scrobbler::recent_tracks`]: struct.Scrobbler.html#method.recent_tracks
+pub struct RecentTracks<'a> {
+    scrobbler: &'a Scrobbler,
+    query: RecentTracksQuery,
+    next_page: u32,
+    total_pages: Option<u32>,
+    tracks: vec::IntoIter<RecentTrack>,
+    finished: bool,
+}
+
+impl<'a> RecentTracks<'a> {
+    pub(crate) fn new(scrobbler: &'a Scrobbler, query: RecentTracksQuery) -> Self {
+        Self {
+            scrobbler,
+            query,
+            next_page: 1,
+            total_pages: None,
+            tracks: Vec::new().into_iter(),
+            finished: false,
+        }
+    }
+
+    /// Total number of pages, known once the first page has been fetched
+    #[must_use]
+    pub fn total_pages(&self) -> Option<u32> {
+        self.total_pages
+    }
+}
+
+impl Iterator for RecentTracks<'_> {
+    type Item = Result<RecentTrack>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(track) = self.tracks.next() {
+                return Some(Ok(track));
+            }
+
+            if self.finished || self.total_pages.is_some_and(|total| self.next_page > total) {
+                return None;
+            }
+
+            match self.scrobbler.recent_tracks_page(&self.query, self.next_page) {
+                Ok(page) => {
+                    self.total_pages = Some(page.pagination.total_pages);
+                    self.next_page += 1;
+                    // Stop on an empty page, rather than trusting totalPages to be accurate
+                    self.finished = page.tracks.is_empty();
+                    self.tracks = page.tracks.into_iter();
+                }
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
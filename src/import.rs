@@ -0,0 +1,102 @@
+// Results shared by the listening history importers
+use std::fmt;
+
+use crate::models::metadata::ScrobbleBatch;
+
+/// The scrobbles read from a listening history file, with the entries that could not be imported.
+///
+/// The `batch` can be submitted with [`Scrobbler::scrobble_batch_chunked`], whose responses refer to scrobbles by
+/// batch index. [`ImportReport::line`] maps a batch index back to the line of the file it was read from.
+///
+/// [`Scrobbler::scrobble_batch_chunked`]: struct.Scrobbler.html#method.scrobble_batch_chunked
+/// [`ImportReport::line`]: struct.ImportReport.html#method.line
+#[derive(Clone, Debug)]
+pub struct ImportReport {
+    pub batch: ScrobbleBatch,
+    /// The (1-based) line number each scrobble of the batch was read from, by batch index
+    pub lines: Vec<usize>,
+    /// Entries which were not imported, in file order
+    pub rejected: Vec<RejectedLine>,
+}
+
+impl ImportReport {
+    /// Returns the line number the scrobble at the given batch index was read from
+    #[must_use]
+    pub fn line(&self, index: usize) -> Option<usize> {
+        self.lines.get(index).copied()
+    }
+
+    /// Returns true if every entry of the file was imported
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.rejected.is_empty()
+    }
+
+    pub(crate) fn new() -> Self {
+        Self {
+            batch: ScrobbleBatch::new(),
+            lines: Vec::new(),
+            rejected: Vec::new(),
+        }
+    }
+
+    pub(crate) fn reject(&mut self, line: usize, reason: RejectReason) {
+        self.rejected.push(RejectedLine { line, reason });
+    }
+}
+
+/// An entry of a listening history file which was not imported
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RejectedLine {
+    /// The (1-based) line number of the entry
+    pub line: usize,
+    pub reason: RejectReason,
+}
+
+impl fmt::Display for RejectedLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.reason)
+    }
+}
+
+/// A record of a listening history file holding an array of records (such as a Spotify export) which was not
+/// imported
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RejectedRecord {
+    /// The (0-based) position of the record in the file's array
+    pub index: usize,
+    pub reason: RejectReason,
+}
+
+impl fmt::Display for RejectedRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Record {}: {}", self.index, self.reason)
+    }
+}
+
+/// Why an entry of a listening history file was not imported
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RejectReason {
+    /// The track was skipped rather than listened to, so should not be scrobbled
+    Skipped,
+    /// The entry has the wrong number of fields
+    FieldCount { expected: usize, found: usize },
+    /// A required field is empty
+    MissingField(&'static str),
+    /// A field's value could not be parsed
+    InvalidField { field: &'static str, value: String },
+    /// The entry could not be parsed at all, e.g. malformed JSON or CSV quoting
+    Malformed(String),
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Skipped => write!(f, "Track was skipped"),
+            Self::FieldCount { expected, found } => write!(f, "Expected {expected} fields, found {found}"),
+            Self::MissingField(field) => write!(f, "Missing {field}"),
+            Self::InvalidField { field, value } => write!(f, "Invalid {field} \"{value}\""),
+            Self::Malformed(msg) => write!(f, "Malformed entry: {msg}"),
+        }
+    }
+}
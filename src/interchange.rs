@@ -0,0 +1,617 @@
+// CSV & JSON-lines import/export of scrobble history
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use serde_json as json;
+
+use crate::error::ScrobblerError;
+use crate::import::{ImportReport, RejectReason};
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::timestamp::TimestampFormat;
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+/// A column of a [`ScrobbleLayout`], holding one [`Scrobble`] field
+///
+/// [`ScrobbleLayout`]: struct.ScrobbleLayout.html
+/// [`Scrobble`]: struct.Scrobble.html
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ScrobbleColumn {
+    Artist,
+    Track,
+    Album,
+    Timestamp,
+    AlbumArtist,
+    Duration,
+    TrackNumber,
+    Mbid,
+    Context,
+    StreamId,
+    ChosenByUser,
+}
+
+impl ScrobbleColumn {
+    /// All columns, in the order they are written
+    pub const ALL: [ScrobbleColumn; 11] = [
+        Self::Artist,
+        Self::Track,
+        Self::Album,
+        Self::Timestamp,
+        Self::AlbumArtist,
+        Self::Duration,
+        Self::TrackNumber,
+        Self::Mbid,
+        Self::Context,
+        Self::StreamId,
+        Self::ChosenByUser,
+    ];
+
+    /// The default column name, which matches the `Scrobble` field name
+    #[must_use]
+    pub fn default_name(self) -> &'static str {
+        match self {
+            Self::Artist => "artist",
+            Self::Track => "track",
+            Self::Album => "album",
+            Self::Timestamp => "timestamp",
+            Self::AlbumArtist => "album_artist",
+            Self::Duration => "duration",
+            Self::TrackNumber => "track_number",
+            Self::Mbid => "mbid",
+            Self::Context => "context",
+            Self::StreamId => "stream_id",
+            Self::ChosenByUser => "chosen_by_user",
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|column| *column == self).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for ScrobbleColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default_name())
+    }
+}
+
+// A field value read from a file, before conversion to its Scrobble type
+enum Cell {
+    Text(String),
+    Number(u64),
+    Bool(bool),
+}
+
+/// Reads & writes scrobble history as CSV or JSON lines.
+///
+/// Each [`ScrobbleColumn`] maps to one CSV column or JSON object key. By default columns are named after the
+/// [`Scrobble`] fields and timestamps are UNIX timestamps:
+///
+/// ```text
+/// artist,track,album,timestamp,album_artist,duration,track_number,mbid,context,stream_id,chosen_by_user
+/// foo floyd,old bananas,old bananas,1600000000,,241,1,,,,1
+/// ```
+///
+/// ```json
+/// {"artist":"foo floyd","track":"old bananas","album":"old bananas","timestamp":1600000000,"duration":241}
+/// ```
+///
+/// CSV files need a header row naming their columns, and follow RFC 4180 (values containing commas, quotes or line
+/// breaks are quoted, with quotes doubled). Each line of a JSON-lines file is one JSON object; empty values are
+/// omitted when writing. Only `artist` & `track` are required; other columns may be missing from a file, or empty,
+/// and columns or keys not in the layout are ignored. Durations are in seconds, and `chosen_by_user` is written as
+/// `1`/`0` in CSV and `true`/`false` in JSON.
+///
+/// Malformed rows don't abort reading a file: they are listed in the returned [`ImportReport`]'s `rejected` lines.
+///
+/// # Usage
+/// ```ignore
+/// let mut layout = ScrobbleLayout::new();
+/// layout
+///     .with_column_name(ScrobbleColumn::Track, "title")
+///     .with_timestamp_format(TimestampFormat::Rfc3339);
+///
+/// let report = layout.read_csv("history.csv")?;
+/// layout.write_json_lines(std::io::stdout(), &report.batch)?;
+/// ```
+///
+/// [`ScrobbleColumn`]: enum.ScrobbleColumn.html
+/// [`Scrobble`]: struct.Scrobble.html
+/// [`ImportReport`]: struct.ImportReport.html
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ScrobbleLayout {
+    names: Vec<String>,
+    timestamp_format: TimestampFormat,
+}
+
+impl Default for ScrobbleLayout {
+    fn default() -> Self {
+        Self {
+            names: ScrobbleColumn::ALL.iter().map(|column| column.default_name().to_owned()).collect(),
+            timestamp_format: TimestampFormat::default(),
+        }
+    }
+}
+
+impl ScrobbleLayout {
+    /// Creates a layout with the default column names, reading & writing UNIX timestamps
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the CSV column name / JSON key used for a column
+    pub fn with_column_name(&mut self, column: ScrobbleColumn, name: &str) -> &mut Self {
+        name.clone_into(&mut self.names[column.index()]);
+        self
+    }
+
+    /// Sets the format timestamps are read & written in. Defaults to UNIX timestamps.
+    pub fn with_timestamp_format(&mut self, timestamp_format: TimestampFormat) -> &mut Self {
+        self.timestamp_format = timestamp_format;
+        self
+    }
+
+    /// Returns the CSV column name / JSON key used for a column
+    #[must_use]
+    pub fn column_name(&self, column: ScrobbleColumn) -> &str {
+        &self.names[column.index()]
+    }
+
+    /// Reads & parses the CSV file at the given path
+    ///
+    /// # Errors
+    /// Returns a `ScrobblerError::Io` error if the file can't be read, otherwise fails in the same cases as
+    /// `ScrobbleLayout::parse_csv`.
+    pub fn read_csv<P: AsRef<Path>>(&self, path: P) -> Result<ImportReport> {
+        self.parse_csv(&fs::read_to_string(path)?)
+    }
+
+    /// Parses CSV scrobble history
+    ///
+    /// # Errors
+    /// Returns a `ScrobblerError::Import` error if the file is empty, or its header row can't be read or has no artist
+    /// or track column.
+    pub fn parse_csv(&self, contents: &str) -> Result<ImportReport> {
+        let mut records = csv_records(contents.trim_start_matches('\u{feff}'));
+        let header = match records.next() {
+            Some((_, Ok(header))) => header,
+            Some((_, Err(reason))) => return Err(ScrobblerError::Import(format!("Invalid CSV header: {reason}"))),
+            None => return Err(ScrobblerError::Import("CSV file is empty".to_owned())),
+        };
+
+        // Position of each column in the header row
+        let positions: Vec<Option<usize>> = ScrobbleColumn::ALL
+            .iter()
+            .map(|column| header.iter().position(|name| name.trim() == self.column_name(*column)))
+            .collect();
+        for required in [ScrobbleColumn::Artist, ScrobbleColumn::Track] {
+            if positions[required.index()].is_none() {
+                return Err(ScrobblerError::Import(format!(
+                    "CSV header has no \"{}\" column",
+                    self.column_name(required)
+                )));
+            }
+        }
+
+        let mut report = ImportReport::new();
+        let mut scrobbles = Vec::new();
+        for (line, record) in records {
+            let scrobble = record.and_then(|record| {
+                if record.len() != header.len() {
+                    return Err(RejectReason::FieldCount {
+                        expected: header.len(),
+                        found: record.len(),
+                    });
+                }
+                self.scrobble_from(|column| {
+                    positions[column.index()]
+                        .map(|position| record[position].clone())
+                        .filter(|value| !value.is_empty())
+                        .map(Cell::Text)
+                })
+            });
+
+            match scrobble {
+                Ok(scrobble) => {
+                    scrobbles.push(scrobble);
+                    report.lines.push(line);
+                }
+                Err(reason) => report.reject(line, reason),
+            }
+        }
+
+        report.batch = ScrobbleBatch::from(scrobbles);
+        Ok(report)
+    }
+
+    /// Writes scrobbles as CSV, with a header row
+    ///
+    /// # Errors
+    /// Returns an error if writing to `writer` fails.
+    pub fn write_csv<W: Write>(&self, mut writer: W, batch: &ScrobbleBatch) -> Result<()> {
+        let header: Vec<&str> = self.names.iter().map(String::as_str).collect();
+        writeln!(writer, "{}", csv_row(&header))?;
+
+        for scrobble in batch {
+            let values: Vec<String> = ScrobbleColumn::ALL
+                .iter()
+                .map(|column| match self.cell(scrobble, *column) {
+                    Some(Cell::Text(text)) => text,
+                    Some(Cell::Number(number)) => number.to_string(),
+                    Some(Cell::Bool(flag)) => if flag { "1" } else { "0" }.to_owned(),
+                    None => String::new(),
+                })
+                .collect();
+            let values: Vec<&str> = values.iter().map(String::as_str).collect();
+            writeln!(writer, "{}", csv_row(&values))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads & parses the JSON-lines file at the given path
+    ///
+    /// # Errors
+    /// Returns a `ScrobblerError::Io` error if the file can't be read.
+    pub fn read_json_lines<P: AsRef<Path>>(&self, path: P) -> Result<ImportReport> {
+        Ok(self.parse_json_lines(&fs::read_to_string(path)?))
+    }
+
+    /// Parses JSON-lines scrobble history. Blank lines are skipped.
+    #[must_use]
+    pub fn parse_json_lines(&self, contents: &str) -> ImportReport {
+        let mut report = ImportReport::new();
+        let mut scrobbles = Vec::new();
+
+        for (i, line) in contents.trim_start_matches('\u{feff}').lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let scrobble = match json::from_str::<json::Map<String, json::Value>>(line) {
+                Ok(object) => self.scrobble_from(|column| match object.get(self.column_name(column))? {
+                    json::Value::String(text) if !text.is_empty() => Some(Cell::Text(text.clone())),
+                    json::Value::Number(number) => Some(number.as_u64().map_or_else(
+                        || Cell::Text(number.to_string()),
+                        Cell::Number,
+                    )),
+                    json::Value::Bool(flag) => Some(Cell::Bool(*flag)),
+                    _ => None,
+                }),
+                Err(err) => Err(RejectReason::Malformed(err.to_string())),
+            };
+
+            match scrobble {
+                Ok(scrobble) => {
+                    scrobbles.push(scrobble);
+                    report.lines.push(i + 1);
+                }
+                Err(reason) => report.reject(i + 1, reason),
+            }
+        }
+
+        report.batch = ScrobbleBatch::from(scrobbles);
+        report
+    }
+
+    /// Writes scrobbles as JSON lines, one object per scrobble
+    ///
+    /// # Errors
+    /// Returns an error if writing to `writer` fails.
+    pub fn write_json_lines<W: Write>(&self, mut writer: W, batch: &ScrobbleBatch) -> Result<()> {
+        for scrobble in batch {
+            let mut object = json::Map::new();
+            for column in ScrobbleColumn::ALL {
+                let value = match self.cell(scrobble, column) {
+                    Some(Cell::Text(text)) => json::Value::from(text),
+                    Some(Cell::Number(number)) => json::Value::from(number),
+                    Some(Cell::Bool(flag)) => json::Value::from(flag),
+                    None => continue,
+                };
+                object.insert(self.column_name(column).to_owned(), value);
+            }
+
+            serde_json::to_writer(&mut writer, &object)?;
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    fn scrobble_from<F>(&self, get: F) -> std::result::Result<Scrobble, RejectReason>
+    where
+        F: Fn(ScrobbleColumn) -> Option<Cell>,
+    {
+        let text = |column: ScrobbleColumn| match get(column) {
+            Some(Cell::Text(text)) => Ok(Some(text)),
+            Some(Cell::Number(number)) => Ok(Some(number.to_string())),
+            Some(Cell::Bool(_)) => Err(invalid(column, "boolean")),
+            None => Ok(None),
+        };
+        let number = |column: ScrobbleColumn| match get(column) {
+            Some(Cell::Number(number)) => Ok(Some(number)),
+            Some(Cell::Text(text)) => text.trim().parse().map(Some).map_err(|_| invalid(column, &text)),
+            Some(Cell::Bool(flag)) => Err(invalid(column, &flag.to_string())),
+            None => Ok(None),
+        };
+
+        let artist = text(ScrobbleColumn::Artist)?.ok_or(RejectReason::MissingField("artist"))?;
+        let track = text(ScrobbleColumn::Track)?.ok_or(RejectReason::MissingField("track"))?;
+        let album = text(ScrobbleColumn::Album)?.unwrap_or_default();
+        let mut scrobble = Scrobble::new(&artist, &track, &album);
+
+        let timestamp = match get(ScrobbleColumn::Timestamp) {
+            Some(Cell::Number(number)) if self.timestamp_format == TimestampFormat::UnixSeconds => Some(number),
+            Some(Cell::Text(text)) => Some(
+                self.timestamp_format
+                    .parse(&text)
+                    .ok_or_else(|| invalid(ScrobbleColumn::Timestamp, &text))?,
+            ),
+            Some(Cell::Number(number)) => return Err(invalid(ScrobbleColumn::Timestamp, &number.to_string())),
+            Some(Cell::Bool(flag)) => return Err(invalid(ScrobbleColumn::Timestamp, &flag.to_string())),
+            None => None,
+        };
+        if let Some(timestamp) = timestamp {
+            scrobble.with_timestamp(timestamp);
+        }
+        if let Some(album_artist) = text(ScrobbleColumn::AlbumArtist)? {
+            scrobble.with_album_artist(&album_artist);
+        }
+        if let Some(duration) = number(ScrobbleColumn::Duration)? {
+            scrobble.with_duration(duration);
+        }
+        if let Some(track_number) = number(ScrobbleColumn::TrackNumber)? {
+            let track_number = u32::try_from(track_number)
+                .map_err(|_| invalid(ScrobbleColumn::TrackNumber, &track_number.to_string()))?;
+            scrobble.with_track_number(track_number);
+        }
+        if let Some(mbid) = text(ScrobbleColumn::Mbid)? {
+            scrobble.with_mbid(&mbid);
+        }
+        if let Some(context) = text(ScrobbleColumn::Context)? {
+            scrobble.with_context(&context);
+        }
+        if let Some(stream_id) = text(ScrobbleColumn::StreamId)? {
+            scrobble.with_stream_id(&stream_id);
+        }
+        let chosen_by_user = match get(ScrobbleColumn::ChosenByUser) {
+            Some(Cell::Bool(flag)) => Some(flag),
+            Some(Cell::Number(number @ (0 | 1))) => Some(number == 1),
+            Some(Cell::Text(text)) => match text.trim() {
+                "1" | "true" => Some(true),
+                "0" | "false" => Some(false),
+                _ => return Err(invalid(ScrobbleColumn::ChosenByUser, &text)),
+            },
+            Some(Cell::Number(number)) => {
+                return Err(invalid(ScrobbleColumn::ChosenByUser, &number.to_string()))
+            }
+            None => None,
+        };
+        if let Some(chosen_by_user) = chosen_by_user {
+            scrobble.with_chosen_by_user(chosen_by_user);
+        }
+
+        Ok(scrobble)
+    }
+
+    fn cell(&self, scrobble: &Scrobble, column: ScrobbleColumn) -> Option<Cell> {
+        let text = |value: &str| (!value.is_empty()).then(|| Cell::Text(value.to_owned()));
+
+        match column {
+            ScrobbleColumn::Artist => Some(Cell::Text(scrobble.artist().to_owned())),
+            ScrobbleColumn::Track => Some(Cell::Text(scrobble.track().to_owned())),
+            ScrobbleColumn::Album => text(scrobble.album()),
+            ScrobbleColumn::Timestamp => scrobble.timestamp().map(|timestamp| match self.timestamp_format {
+                TimestampFormat::UnixSeconds => Cell::Number(timestamp),
+                TimestampFormat::Rfc3339 => Cell::Text(self.timestamp_format.format(timestamp)),
+            }),
+            ScrobbleColumn::AlbumArtist => scrobble.album_artist().and_then(text),
+            ScrobbleColumn::Duration => scrobble.duration().map(Cell::Number),
+            ScrobbleColumn::TrackNumber => scrobble.track_number().map(|number| Cell::Number(u64::from(number))),
+            ScrobbleColumn::Mbid => scrobble.mbid().and_then(text),
+            ScrobbleColumn::Context => scrobble.context().and_then(text),
+            ScrobbleColumn::StreamId => scrobble.stream_id().and_then(text),
+            ScrobbleColumn::ChosenByUser => scrobble.chosen_by_user().map(Cell::Bool),
+        }
+    }
+}
+
+// Rejected fields are named by their default column name
+fn invalid(column: ScrobbleColumn, value: &str) -> RejectReason {
+    RejectReason::InvalidField {
+        field: column.default_name(),
+        value: value.to_owned(),
+    }
+}
+
+// Splits CSV text into records, each with the line number it starts on. Quoted values may contain line breaks.
+fn csv_records(contents: &str) -> impl Iterator<Item = (usize, std::result::Result<Vec<String>, RejectReason>)> + '_ {
+    let mut chars = contents.chars().peekable();
+    let mut line = 1;
+
+    std::iter::from_fn(move || loop {
+        chars.peek()?;
+        let start = line;
+        let mut record = Vec::new();
+        let mut value = String::new();
+        let mut quoted = false;
+        // Set after a quoted value's closing quote, which must be followed by a delimiter
+        let mut closed = false;
+        let mut error = None;
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted => {
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                        value.push('"');
+                    } else {
+                        quoted = false;
+                        closed = true;
+                    }
+                }
+                '\n' if quoted => {
+                    line += 1;
+                    value.push('\n');
+                }
+                c if quoted => value.push(c),
+                ',' => {
+                    record.push(std::mem::take(&mut value));
+                    closed = false;
+                }
+                '\r' if chars.peek() == Some(&'\n') => {}
+                '\n' => {
+                    line += 1;
+                    break;
+                }
+                _ if closed => error = Some(RejectReason::Malformed("Unexpected text after quoted value".to_owned())),
+                '"' if value.is_empty() => quoted = true,
+                '"' => error = Some(RejectReason::Malformed("Unexpected quote in unquoted value".to_owned())),
+                c => value.push(c),
+            }
+        }
+        if quoted {
+            error = Some(RejectReason::Malformed("Unterminated quoted value".to_owned()));
+        }
+
+        // Skip blank lines
+        if record.is_empty() && value.trim().is_empty() && error.is_none() {
+            continue;
+        }
+        record.push(value);
+
+        return Some((start, error.map_or(Ok(record), Err)));
+    })
+}
+
+fn csv_row(values: &[&str]) -> String {
+    values
+        .iter()
+        .map(|value| {
+            if value.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                (*value).to_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_scrobble() -> Scrobble {
+        let mut scrobble = Scrobble::new("foo floyd, and the \"fruit flies\"", "old bananas", "old bananas");
+        scrobble
+            .with_timestamp(1_600_000_000)
+            .with_album_artist("foo floyd")
+            .with_duration(241)
+            .with_track_number(1)
+            .with_mbid("0a1b2c3d-0000-1111-2222-333344445555")
+            .with_context("ctx")
+            .with_stream_id("stream")
+            .with_chosen_by_user(true);
+
+        scrobble
+    }
+
+    #[test]
+    fn check_csv_round_trip() {
+        let batch = ScrobbleBatch::from(vec![full_scrobble(), Scrobble::new("foo floyd", "new\nbananas", "")]);
+        let layout = ScrobbleLayout::new();
+
+        let mut csv = Vec::new();
+        layout.write_csv(&mut csv, &batch).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            csv,
+            "artist,track,album,timestamp,album_artist,duration,track_number,mbid,context,stream_id,chosen_by_user\n\
+             \"foo floyd, and the \"\"fruit flies\"\"\",old bananas,old bananas,1600000000,foo floyd,241,1,\
+             0a1b2c3d-0000-1111-2222-333344445555,ctx,stream,1\n\
+             foo floyd,\"new\nbananas\",,,,,,,,,\n"
+        );
+
+        let report = layout.parse_csv(&csv).unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.lines, vec![2, 3]);
+        assert!(report.batch.iter().eq(batch.iter()));
+    }
+
+    #[test]
+    fn check_csv_custom_columns() {
+        let mut layout = ScrobbleLayout::new();
+        layout
+            .with_column_name(ScrobbleColumn::Artist, "Artist")
+            .with_column_name(ScrobbleColumn::Track, "Title")
+            .with_column_name(ScrobbleColumn::Timestamp, "Played At")
+            .with_timestamp_format(TimestampFormat::Rfc3339);
+
+        let csv = "Title,Artist,Played At,Rating\r\n\
+                   old bananas,foo floyd,2020-09-13T14:26:40+02:00,5\r\n\
+                   \r\n\
+                   new bananas,foo floyd,yesterday,4\r\n\
+                   ,foo floyd,2020-09-13T12:30:00Z,3\r\n\
+                   old bananas,foo floyd\r\n\
+                   \"old\" bananas,foo floyd,2020-09-13T12:30:00Z,3\r\n\
+                   old \"bananas\",foo floyd,2020-09-13T12:30:00Z,3\r\n";
+
+        let report = layout.parse_csv(csv).unwrap();
+        assert_eq!(report.batch.len(), 1);
+        let scrobble = report.batch.iter().next().unwrap();
+        assert_eq!(scrobble.track(), "old bananas");
+        assert_eq!(scrobble.timestamp(), Some(1_600_000_000));
+
+        let reasons: Vec<_> = report.rejected.iter().map(|rejected| (rejected.line, &rejected.reason)).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (4, &RejectReason::InvalidField { field: "timestamp", value: "yesterday".to_owned() }),
+                (5, &RejectReason::MissingField("track")),
+                (6, &RejectReason::FieldCount { expected: 4, found: 2 }),
+                (7, &RejectReason::Malformed("Unexpected text after quoted value".to_owned())),
+                (8, &RejectReason::Malformed("Unexpected quote in unquoted value".to_owned())),
+            ]
+        );
+
+        let mut csv = Vec::new();
+        layout.write_csv(&mut csv, &report.batch).unwrap();
+        assert!(String::from_utf8(csv).unwrap().starts_with(
+            "Artist,Title,album,Played At,album_artist,duration,track_number,mbid,context,stream_id,chosen_by_user\n\
+             foo floyd,old bananas,,2020-09-13T12:26:40Z,"
+        ));
+
+        assert!(matches!(layout.parse_csv("artist,track\n"), Err(ScrobblerError::Import(_))));
+    }
+
+    #[test]
+    fn check_json_lines_round_trip() {
+        let batch = ScrobbleBatch::from(vec![full_scrobble(), Scrobble::new("foo floyd", "new bananas", "")]);
+        let layout = ScrobbleLayout::new();
+
+        let mut lines = Vec::new();
+        layout.write_json_lines(&mut lines, &batch).unwrap();
+        let lines = String::from_utf8(lines).unwrap();
+        assert!(lines.ends_with("\n{\"artist\":\"foo floyd\",\"track\":\"new bananas\"}\n"));
+
+        let report = layout.parse_json_lines(&lines);
+        assert!(report.is_complete());
+        assert!(report.batch.iter().eq(batch.iter()));
+
+        let report = layout.parse_json_lines(
+            "{\"artist\": \"foo floyd\", \"track\": \"old bananas\", \"duration\": \"241\", \"extra\": [1]}\n\
+             not json\n\
+             \n\
+             {\"artist\": \"foo floyd\", \"track\": \"old bananas\", \"chosen_by_user\": \"maybe\"}\n\
+             {\"artist\": \"foo floyd\", \"track\": \"old bananas\", \"timestamp\": -1}\n",
+        );
+        assert_eq!(report.batch.iter().next().unwrap().duration(), Some(241));
+        let lines: Vec<_> = report.rejected.iter().map(|rejected| rejected.line).collect();
+        assert_eq!(lines, vec![2, 4, 5]);
+        assert!(matches!(report.rejected[0].reason, RejectReason::Malformed(_)));
+    }
+}
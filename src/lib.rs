@@ -38,23 +38,71 @@
 //! }
 //! ```
 //! 
-//! *Note:* This crate does not implement any of the logic to comply with Last.fm's scrobbling rules. Typical
-//! ("real-time") implementations will likely want to adhere to these rules, outlined in Last.fm's 
-//! [API Documentation](https://www.last.fm/api/scrobbling#scrobble-requests). Other implementations may choose to
-//! ignore these guidelines. This crate provides the flexibility to develop any type of Scrobbling application.
+//! *Note:* `Scrobbler` itself does not enforce Last.fm's scrobbling rules, outlined in Last.fm's
+//! [API Documentation](https://www.last.fm/api/scrobbling#scrobble-requests). Typical ("real-time") implementations
+//! will likely want to adhere to these rules, and can use `PlayTracker` (or `PlaySession`, which also sends now
+//! playing requests) to decide when a track qualifies to be scrobbled from player events. Other implementations may
+//! choose to ignore these guidelines. This crate provides the flexibility to develop any type of Scrobbling
+//! application.
 //! 
 #[macro_use]
 extern crate wrapped_vec;
 
+#[cfg(feature = "async")]
+mod async_client;
+#[cfg(feature = "async")]
+mod async_scrobbler;
+mod audioscrobbler;
 mod auth;
 mod client;
+mod clock;
+mod correction;
+mod dedup;
+mod endpoints;
 mod error;
+mod history;
+mod import;
+mod interchange;
+mod listenbrainz;
+mod lookup;
 mod models;
+mod multi;
+mod queue;
+mod retry;
 mod scrobbler;
+mod scrobbler_log;
+mod service;
+mod session;
+mod spotify;
+mod timestamp;
+mod tracker;
+mod transport;
 
+#[cfg(feature = "async")]
+pub use crate::async_scrobbler::AsyncScrobbler;
+pub use crate::audioscrobbler::{LegacyScrobbler, HANDSHAKE_URL};
+pub use crate::clock::{Clock, Sleeper, SystemClock, ThreadSleeper};
+pub use crate::correction::{CorrectedScrobble, FieldChange, ScrobbleField};
+pub use crate::dedup::{DeduplicatedBatchResponse, DuplicateFilter};
+pub use crate::endpoints::ApiEndpoints;
+pub use crate::history::{RecentTracks, RecentTracksQuery};
+pub use crate::import::{ImportReport, RejectReason, RejectedLine, RejectedRecord};
+pub use crate::interchange::{ScrobbleColumn, ScrobbleLayout};
+pub use crate::listenbrainz::{ListenBrainz, MAX_LISTENS_PER_REQUEST};
+pub use crate::lookup::LookupOptions;
 pub use crate::models::metadata::{Scrobble, ScrobbleBatch};
-pub use crate::scrobbler::Scrobbler;
+pub use crate::multi::{MultiScrobbler, ServiceResults};
+pub use crate::queue::{DropReason, QueueDrainReport, ScrobbleQueue};
+pub use crate::retry::{RetryEvent, RetryPolicy};
+pub use crate::scrobbler::{Scrobbler, ScrobblerBuilder};
+pub use crate::scrobbler_log::ScrobblerLog;
 pub use crate::error::ScrobblerError;
+pub use crate::service::ScrobbleService;
+pub use crate::session::{FileSessionStore, Session, SessionStore};
+pub use crate::spotify::{SpotifyFileSummary, SpotifyHistory, SpotifyRecord};
+pub use crate::timestamp::TimestampFormat;
+pub use crate::tracker::{PlaySession, PlayTracker, TrackStart};
+pub use crate::transport::{HttpMethod, HttpRequest, HttpResponse, RecordingTransport, Transport, UreqTransport};
 
 
 /// Last.fm API Response Types
@@ -62,11 +110,15 @@ pub use crate::error::ScrobblerError;
 /// Types used to represent responses from the Last.fm API
 pub mod responses {
     pub use crate::models::responses::{
-        BatchScrobbleResponse, NowPlayingResponse, ScrobbleResponse, SessionResponse,
+        AlbumInfo, ArtistCorrection, ArtistInfo, BatchScrobbleResponse, ChunkedScrobbleResponse, LoveResponse,
+        NowPlayingResponse, RecentTracksPage, ScrobbleResponse, SessionResponse, TrackCorrection, TrackInfo,
     };
 
     /// Data types used to represent values in API Response types
     pub mod values {
-        pub use crate::models::responses::{CorrectableString, ScrobbleList};
+        pub use crate::models::responses::{
+            AlbumTrack, ArtistStats, CorrectableString, FailedChunk, IgnoreReason, IgnoredMessage, Image, NamedValue,
+            Pagination, RecentTrack, ScrobbleCounts, ScrobbleList, Tag, TrackAlbum,
+        };
     }
 }
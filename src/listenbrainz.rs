@@ -0,0 +1,318 @@
+// ListenBrainz listen submission API client
+use std::collections::HashMap;
+use std::fmt;
+use std::time::UNIX_EPOCH;
+
+use serde_json::{json, Map, Value};
+
+use crate::error::ScrobblerError;
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::transport::{HttpMethod, HttpRequest, Transport, UreqTransport};
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+const API_URL: &str = "https://api.listenbrainz.org";
+const SUBMIT_LISTENS_PATH: &str = "/1/submit-listens";
+
+/// Maximum number of listens ListenBrainz accepts in a single import request
+pub const MAX_LISTENS_PER_REQUEST: usize = 1000;
+
+#[derive(Clone, Copy)]
+enum ListenType {
+    PlayingNow,
+    Single,
+    Import,
+}
+
+impl fmt::Display for ListenType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match *self {
+            Self::PlayingNow => "playing_now",
+            Self::Single => "single",
+            Self::Import => "import",
+        };
+        write!(f, "{str}")
+    }
+}
+
+/// A ListenBrainz client. Submits song play information ("listens") to ListenBrainz.
+///
+/// Takes the same [`Scrobble`] values as [`Scrobbler`]: artist, track & album are sent as the listen's track
+/// metadata, and the optional album artist, duration, track number and MBID (as the recording MBID) as additional
+/// info. Both clients implement [`ScrobbleService`], so player code can submit to either.
+///
+/// Requests are authenticated with the user's ListenBrainz token, found on their
+/// [ListenBrainz settings page](https://listenbrainz.org/settings/).
+///
+/// # Usage
+/// ```ignore
+/// let listenbrainz = ListenBrainz::new("user-token");
+///
+/// let song = Scrobble::new("Example Artist", "Example Song", "Example Album");
+/// listenbrainz.playing_now(&song)?;
+/// listenbrainz.submit_single(&song)?;
+/// ```
+///
+/// # ListenBrainz API Documentation
+/// [submit-listens API Documentation](https://listenbrainz.readthedocs.io/en/latest/users/api/core.html)
+///
+/// [`Scrobble`]: struct.Scrobble.html
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`ScrobbleService`]: trait.ScrobbleService.html
+pub struct ListenBrainz {
+    token: String,
+    api_url: String,
+    transport: Box<dyn Transport>,
+}
+
+impl ListenBrainz {
+    /// Creates a new ListenBrainz client authenticated with the given user token
+    #[must_use]
+    pub fn new(token: &str) -> Self {
+        Self::with_transport(token, UreqTransport::new())
+    }
+
+    /// Creates a new ListenBrainz client sending its requests through the given [`Transport`]. See
+    /// [`Scrobbler::with_transport`].
+    ///
+    /// [`Transport`]: trait.Transport.html
+    /// [`Scrobbler::with_transport`]: struct.Scrobbler.html#method.with_transport
+    pub fn with_transport<T: Transport + 'static>(token: &str, transport: T) -> Self {
+        Self {
+            token: token.to_owned(),
+            api_url: API_URL.to_owned(),
+            transport: Box::new(transport),
+        }
+    }
+
+    /// Sets the root URL of the API, for self-hosted ListenBrainz servers. Defaults to
+    /// `https://api.listenbrainz.org`.
+    pub fn set_api_url(&mut self, api_url: &str) {
+        api_url.trim_end_matches('/').clone_into(&mut self.api_url);
+    }
+
+    /// Root URL of the API requests are sent to
+    #[must_use]
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    /// Registers the given [`Scrobble`]/track as the user's currently playing track (a `playing_now` listen). The
+    /// scrobble's timestamp is ignored.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or ListenBrainz rejects the listen.
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    pub fn playing_now(&self, scrobble: &Scrobble) -> Result<()> {
+        self.submit_listens(ListenType::PlayingNow, &[scrobble])
+    }
+
+    /// Submits a listen of the given [`Scrobble`]/track (a `single` listen). The current time is used if the
+    /// scrobble has no timestamp.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or ListenBrainz rejects the listen.
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    pub fn submit_single(&self, scrobble: &Scrobble) -> Result<()> {
+        self.submit_listens(ListenType::Single, &[scrobble])
+    }
+
+    /// Submits listens of a collection of tracks in one request (an `import`).
+    ///
+    /// # Errors
+    /// Returns [`ScrobblerError::InvalidBatch`] if the batch is empty or holds more than
+    /// [`MAX_LISTENS_PER_REQUEST`] scrobbles. [`ScrobbleService::scrobble_batch`] splits larger batches into
+    /// several requests.
+    ///
+    /// [`ScrobblerError::InvalidBatch`]: enum.ScrobblerError.html#variant.InvalidBatch
+    /// [`MAX_LISTENS_PER_REQUEST`]: constant.MAX_LISTENS_PER_REQUEST.html
+    /// [`ScrobbleService::scrobble_batch`]: trait.ScrobbleService.html#tymethod.scrobble_batch
+    pub fn import(&self, batch: &ScrobbleBatch) -> Result<()> {
+        let scrobbles: Vec<&Scrobble> = batch.iter().collect();
+        if scrobbles.len() > MAX_LISTENS_PER_REQUEST {
+            return Err(ScrobblerError::InvalidBatch(format!(
+                "Listen import too large (must be {MAX_LISTENS_PER_REQUEST} or fewer listens)"
+            )));
+        }
+
+        self.submit_listens(ListenType::Import, &scrobbles)
+    }
+
+    // Imports a batch of any size, one request per `MAX_LISTENS_PER_REQUEST` listens, stopping at the first failure
+    pub(crate) fn import_chunked(&self, batch: &ScrobbleBatch) -> Result<()> {
+        let scrobbles: Vec<&Scrobble> = batch.iter().collect();
+        if scrobbles.is_empty() {
+            return Err(ScrobblerError::InvalidBatch("Listen import is empty".to_owned()));
+        }
+
+        for chunk in scrobbles.chunks(MAX_LISTENS_PER_REQUEST) {
+            self.submit_listens(ListenType::Import, chunk)?;
+        }
+
+        Ok(())
+    }
+
+    fn submit_listens(&self, listen_type: ListenType, scrobbles: &[&Scrobble]) -> Result<()> {
+        if scrobbles.is_empty() {
+            return Err(ScrobblerError::InvalidBatch("Listen import is empty".to_owned()));
+        }
+
+        let now = UNIX_EPOCH.elapsed()?.as_secs();
+        let payload: Vec<Value> = scrobbles
+            .iter()
+            .map(|scrobble| match listen_type {
+                ListenType::PlayingNow => listen_payload(scrobble, None),
+                ListenType::Single | ListenType::Import => {
+                    listen_payload(scrobble, Some(scrobble.timestamp().unwrap_or(now)))
+                }
+            })
+            .collect();
+
+        let body = json!({
+            "listen_type": listen_type.to_string(),
+            "payload": payload,
+        });
+
+        let url = format!("{}{}", self.api_url, SUBMIT_LISTENS_PATH);
+        let mut request = HttpRequest::new(HttpMethod::Post, &url, HashMap::new());
+        request
+            .headers
+            .insert("Authorization".to_owned(), format!("Token {}", self.token));
+        request.body = Some(body.to_string());
+
+        let resp = self.transport.send(&request)?;
+        if resp.status >= 400 {
+            return Err(ScrobblerError::HttpStatus {
+                status: resp.status,
+                body: resp.body,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// Maps a scrobble to a listen's JSON payload, see
+// https://listenbrainz.readthedocs.io/en/latest/users/json.html#payload-json-details
+fn listen_payload(scrobble: &Scrobble, listened_at: Option<u64>) -> Value {
+    let mut additional_info = Map::new();
+    additional_info.insert("submission_client".to_owned(), json!(env!("CARGO_PKG_NAME")));
+    additional_info.insert("submission_client_version".to_owned(), json!(env!("CARGO_PKG_VERSION")));
+    if let Some(duration) = scrobble.duration() {
+        additional_info.insert("duration_ms".to_owned(), json!(duration * 1000));
+    }
+    if let Some(track_number) = scrobble.track_number() {
+        additional_info.insert("tracknumber".to_owned(), json!(track_number));
+    }
+    if let Some(mbid) = scrobble.mbid() {
+        additional_info.insert("recording_mbid".to_owned(), json!(mbid));
+    }
+    if let Some(album_artist) = scrobble.album_artist() {
+        additional_info.insert("release_artist_name".to_owned(), json!(album_artist));
+    }
+
+    let mut track_metadata = Map::new();
+    track_metadata.insert("artist_name".to_owned(), json!(scrobble.artist()));
+    track_metadata.insert("track_name".to_owned(), json!(scrobble.track()));
+    if !scrobble.album().is_empty() {
+        track_metadata.insert("release_name".to_owned(), json!(scrobble.album()));
+    }
+    track_metadata.insert("additional_info".to_owned(), Value::Object(additional_info));
+
+    let mut listen = Map::new();
+    if let Some(listened_at) = listened_at {
+        listen.insert("listened_at".to_owned(), json!(listened_at));
+    }
+    listen.insert("track_metadata".to_owned(), Value::Object(track_metadata));
+
+    Value::Object(listen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::RecordingTransport;
+
+    fn request_body(transport: &RecordingTransport) -> Value {
+        let request = transport.last_request().unwrap();
+        serde_json::from_str(request.body.as_deref().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn check_listenbrainz_submit() {
+        let transport = RecordingTransport::new();
+        let listenbrainz = ListenBrainz::with_transport("user-token", transport.clone());
+
+        let mut scrobble = Scrobble::new("foo floyd and the fruit flies", "old bananas", "old bananas");
+        scrobble
+            .with_timestamp(1337)
+            .with_duration(215)
+            .with_track_number(3)
+            .with_album_artist("foo floyd")
+            .with_mbid("7a2d44d9-8f6b-4a4a-a6b3-0c6a2f1b6f0c");
+
+        transport.push_response(200, r#"{"status": "ok"}"#);
+        listenbrainz.submit_single(&scrobble).unwrap();
+
+        let request = transport.last_request().unwrap();
+        assert_eq!(request.url, "https://api.listenbrainz.org/1/submit-listens");
+        assert_eq!(request.headers["Authorization"], "Token user-token");
+
+        let body = request_body(&transport);
+        assert_eq!(body["listen_type"], "single");
+        let listen = &body["payload"][0];
+        assert_eq!(listen["listened_at"], 1337);
+        assert_eq!(listen["track_metadata"]["artist_name"], "foo floyd and the fruit flies");
+        assert_eq!(listen["track_metadata"]["track_name"], "old bananas");
+        assert_eq!(listen["track_metadata"]["release_name"], "old bananas");
+
+        let info = &listen["track_metadata"]["additional_info"];
+        assert_eq!(info["duration_ms"], 215_000);
+        assert_eq!(info["tracknumber"], 3);
+        assert_eq!(info["recording_mbid"], "7a2d44d9-8f6b-4a4a-a6b3-0c6a2f1b6f0c");
+        assert_eq!(info["release_artist_name"], "foo floyd");
+        assert_eq!(info["submission_client"], "rustfm-scrobble");
+
+        transport.push_response(200, r#"{"status": "ok"}"#);
+        listenbrainz.playing_now(&scrobble).unwrap();
+        let body = request_body(&transport);
+        assert_eq!(body["listen_type"], "playing_now");
+        assert!(body["payload"][0].get("listened_at").is_none());
+
+        transport.push_response(401, r#"{"code": 401, "error": "Invalid authorization token."}"#);
+        let err = listenbrainz.submit_single(&scrobble).unwrap_err();
+        assert_eq!(err.http_status(), Some(401));
+    }
+
+    #[test]
+    fn check_listenbrainz_import() {
+        let transport = RecordingTransport::new();
+        let mut listenbrainz = ListenBrainz::with_transport("user-token", transport.clone());
+        listenbrainz.set_api_url("https://listenbrainz.example.com/");
+        assert_eq!(listenbrainz.api_url(), "https://listenbrainz.example.com");
+
+        let err = listenbrainz.import(&ScrobbleBatch::new()).unwrap_err();
+        assert!(matches!(err, ScrobblerError::InvalidBatch(_)));
+
+        let tracks = vec![("Artist", "Track", ""); 1001];
+        let batch = ScrobbleBatch::from(tracks);
+        let err = listenbrainz.import(&batch).unwrap_err();
+        assert!(matches!(err, ScrobblerError::InvalidBatch(_)));
+        assert!(transport.requests().is_empty());
+
+        transport.push_response(200, r#"{"status": "ok"}"#);
+        transport.push_response(200, r#"{"status": "ok"}"#);
+        listenbrainz.import_chunked(&batch).unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].url, "https://listenbrainz.example.com/1/submit-listens");
+
+        let body = request_body(&transport);
+        assert_eq!(body["listen_type"], "import");
+        assert_eq!(body["payload"].as_array().unwrap().len(), 1);
+        assert!(body["payload"][0]["track_metadata"].get("release_name").is_none());
+    }
+}
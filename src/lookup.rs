@@ -0,0 +1,61 @@
+// Options shared by the track/artist/album metadata lookups
+use std::collections::HashMap;
+
+/// Options for the metadata lookups [`Scrobbler::track_info`], [`Scrobbler::artist_info`] and
+/// [`Scrobbler::album_info`].
+///
+/// By default names are looked up exactly as given, and no user-specific statistics are returned.
+///
+/// # Usage
+/// ```ignore
+/// let mut options = LookupOptions::new();
+/// options.with_autocorrect(true).with_username("username");
+///
+/// let info = scrobbler.track_info("Bjork", "Joga", &options)?;
+/// println!("{} plays, loved: {:?}", info.user_playcount.unwrap_or(0), info.user_loved);
+/// ```
+///
+/// [`Scrobbler::track_info`]: struct.Scrobbler.html#method.track_info
+/// [`Scrobbler::artist_info`]: struct.Scrobbler.html#method.artist_info
+/// [`Scrobbler::album_info`]: struct.Scrobbler.html#method.album_info
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct LookupOptions {
+    autocorrect: bool,
+    username: Option<String>,
+}
+
+impl LookupOptions {
+    /// Creates options looking names up exactly as given, without user-specific statistics
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets Last.fm correct misspelt artist/track/album names, returning the metadata of the corrected item
+    pub fn with_autocorrect(&mut self, autocorrect: bool) -> &mut Self {
+        self.autocorrect = autocorrect;
+        self
+    }
+
+    /// Includes the given user's play count (and, for tracks, loved status) in the response
+    pub fn with_username(&mut self, username: &str) -> &mut Self {
+        self.username = Some(username.to_owned());
+        self
+    }
+
+    pub(crate) fn params(&self, lookup: &[(&str, &str)]) -> HashMap<String, String> {
+        let mut params: HashMap<String, String> = lookup
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect();
+
+        if self.autocorrect {
+            params.insert("autocorrect".to_string(), "1".to_string());
+        }
+        if let Some(ref username) = self.username {
+            params.insert("username".to_string(), username.clone());
+        }
+
+        params
+    }
+}
@@ -1,10 +1,28 @@
 pub mod responses {
 
     use std::fmt;
+    use std::ops::Range;
 
     use serde::Deserialize;
     use serde_json as json;
 
+    use crate::correction::{CorrectedScrobble, ScrobbleField};
+    use crate::error::ScrobblerError;
+    use crate::models::metadata::{Scrobble, ScrobbleBatch};
+
+    /// Error body returned by the Last.fm API, e.g. `{"error": 9, "message": "Invalid session key"}`
+    #[derive(Deserialize, Debug)]
+    pub struct ApiErrorResponse {
+        pub error: u32,
+        #[serde(default)]
+        pub message: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct AuthTokenResponse {
+        pub token: String,
+    }
+
     #[derive(Deserialize, Debug)]
     pub struct AuthResponse {
         pub session: SessionResponse,
@@ -45,6 +63,18 @@ pub mod responses {
         pub track: CorrectableString,
     }
 
+    impl NowPlayingResponse {
+        /// Returns a copy of the submitted [`Scrobble`] with Last.fm's metadata corrections applied, and the fields
+        /// that changed. See [`ScrobbleResponse::apply_corrections`].
+        /// 
+        /// [`Scrobble`]: ../struct.Scrobble.html
+        /// [`ScrobbleResponse::apply_corrections`]: struct.ScrobbleResponse.html#method.apply_corrections
+        #[must_use]
+        pub fn apply_corrections(&self, scrobble: &Scrobble) -> CorrectedScrobble {
+            apply_corrections(scrobble, &self.artist, &self.track, &self.album, &self.album_artist)
+        }
+    }
+
     #[derive(Deserialize)]
     pub struct ScrobbleResponseWrapper {
         pub scrobbles: SingleScrobble,
@@ -71,6 +101,191 @@ pub mod responses {
         pub album_artist: CorrectableString,
         pub track: CorrectableString,
         pub timestamp: String,
+        #[serde(rename = "ignoredMessage", default)]
+        pub ignored_message: Option<IgnoredMessage>,
+    }
+
+    impl ScrobbleResponse {
+        /// Returns true if Last.fm ignored (did not record) this scrobble
+        #[must_use]
+        pub fn is_ignored(&self) -> bool {
+            self.ignore_reason().is_some()
+        }
+
+        /// Returns the reason Last.fm ignored this scrobble, or `None` if it was accepted
+        #[must_use]
+        pub fn ignore_reason(&self) -> Option<IgnoreReason> {
+            self.ignored_message.as_ref().and_then(IgnoredMessage::reason)
+        }
+
+        /// Returns a copy of the submitted [`Scrobble`] with Last.fm's metadata corrections applied, and the fields
+        /// that changed. Only fields Last.fm marked as `corrected` are changed; the scrobble's other values are kept.
+        /// 
+        /// # Usage
+        /// ```ignore
+        /// let response = scrobbler.scrobble(&track)?;
+        /// let corrected = response.apply_corrections(&track);
+        /// if corrected.is_changed() {
+        ///     // Offer to update the local tags to corrected.scrobble
+        /// }
+        /// ```
+        /// 
+        /// [`Scrobble`]: ../struct.Scrobble.html
+        #[must_use]
+        pub fn apply_corrections(&self, scrobble: &Scrobble) -> CorrectedScrobble {
+            apply_corrections(scrobble, &self.artist, &self.track, &self.album, &self.album_artist)
+        }
+    }
+
+    fn apply_corrections(
+        scrobble: &Scrobble,
+        artist: &CorrectableString,
+        track: &CorrectableString,
+        album: &CorrectableString,
+        album_artist: &CorrectableString,
+    ) -> CorrectedScrobble {
+        let mut corrected = CorrectedScrobble::new(scrobble);
+        let fields = [
+            (ScrobbleField::Artist, artist),
+            (ScrobbleField::Track, track),
+            (ScrobbleField::Album, album),
+            (ScrobbleField::AlbumArtist, album_artist),
+        ];
+
+        for (field, value) in fields {
+            if value.corrected {
+                corrected.set(field, &value.text);
+            }
+        }
+
+        corrected
+    }
+
+    /// Message attached to each scrobble in a Scrobble response, explaining why Last.fm ignored the scrobble.
+    /// 
+    /// A `code` of `0` means the scrobble was accepted.
+    /// 
+    /// [Scrobble Request API Documentation](https://www.last.fm/api/show/track.scrobble)
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct IgnoredMessage {
+        #[serde(deserialize_with = "deserialize_count")]
+        pub code: u32,
+        #[serde(rename = "#text", default)]
+        pub text: String,
+    }
+
+    impl IgnoredMessage {
+        /// Returns the typed reason for the `code`, or `None` if the scrobble was accepted
+        #[must_use]
+        pub fn reason(&self) -> Option<IgnoreReason> {
+            IgnoreReason::from_code(self.code)
+        }
+    }
+
+    /// Reason Last.fm ignored a scrobble, parsed from the `ignoredMessage` code of a Scrobble response.
+    /// 
+    /// [Scrobble Request API Documentation](https://www.last.fm/api/show/track.scrobble)
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum IgnoreReason {
+        /// The artist was ignored (code 1), e.g. it is on Last.fm's list of filtered artist names
+        ArtistIgnored,
+        /// The track was ignored (code 2)
+        TrackIgnored,
+        /// The timestamp is too far in the past (code 3)
+        TimestampTooOld,
+        /// The timestamp is too far in the future (code 4)
+        TimestampTooNew,
+        /// The user's daily scrobble limit was exceeded (code 5)
+        DailyLimitExceeded,
+        /// An ignore code not documented by Last.fm
+        Other(u32),
+    }
+
+    impl IgnoreReason {
+        /// Maps an `ignoredMessage` code to its reason. Code `0` means the scrobble was accepted, so gives `None`.
+        #[must_use]
+        pub fn from_code(code: u32) -> Option<Self> {
+            match code {
+                0 => None,
+                1 => Some(Self::ArtistIgnored),
+                2 => Some(Self::TrackIgnored),
+                3 => Some(Self::TimestampTooOld),
+                4 => Some(Self::TimestampTooNew),
+                5 => Some(Self::DailyLimitExceeded),
+                code => Some(Self::Other(code)),
+            }
+        }
+
+        /// The numeric `ignoredMessage` code of the reason
+        #[must_use]
+        pub fn code(self) -> u32 {
+            match self {
+                Self::ArtistIgnored => 1,
+                Self::TrackIgnored => 2,
+                Self::TimestampTooOld => 3,
+                Self::TimestampTooNew => 4,
+                Self::DailyLimitExceeded => 5,
+                Self::Other(code) => code,
+            }
+        }
+
+        /// Returns true if Last.fm will ignore the scrobble again when it is resubmitted. The daily scrobble limit
+        /// resets, and undocumented codes are not assumed to be permanent.
+        #[must_use]
+        pub fn is_permanent(self) -> bool {
+            !matches!(self, Self::DailyLimitExceeded | Self::Other(_))
+        }
+    }
+
+    impl fmt::Display for IgnoreReason {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::ArtistIgnored => write!(f, "Artist was ignored"),
+                Self::TrackIgnored => write!(f, "Track was ignored"),
+                Self::TimestampTooOld => write!(f, "Timestamp was too old"),
+                Self::TimestampTooNew => write!(f, "Timestamp was too new"),
+                Self::DailyLimitExceeded => write!(f, "Daily scrobble limit exceeded"),
+                Self::Other(code) => write!(f, "Ignored with code {code}"),
+            }
+        }
+    }
+
+    /// The `@attr` summary of a Scrobble response: how many of the submitted scrobbles Last.fm accepted & ignored
+    #[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct ScrobbleCounts {
+        #[serde(deserialize_with = "deserialize_count")]
+        pub accepted: u32,
+        #[serde(deserialize_with = "deserialize_count")]
+        pub ignored: u32,
+    }
+
+    // Numeric values are sent as either JSON strings or numbers depending on the endpoint
+    fn deserialize_count<'de, D>(de: D) -> Result<u32, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let deser_result: json::Value = serde::Deserialize::deserialize(de)?;
+        match deser_result {
+            json::Value::String(ref s) => s.parse().map_err(serde::de::Error::custom),
+            json::Value::Number(ref n) => n
+                .as_u64()
+                .and_then(|n| u32::try_from(n).ok())
+                .ok_or_else(|| serde::de::Error::custom("Unexpected value")),
+            _ => Err(serde::de::Error::custom("Unexpected value")),
+        }
+    }
+
+    /// Response to a Love or Unlove request
+    /// 
+    /// Last.fm returns no data for these requests, so this records the track whose loved status was set.
+    /// 
+    /// [track.love API Method Documentation](https://www.last.fm/api/show/track.love)
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct LoveResponse {
+        pub artist: String,
+        pub track: String,
+        /// True if the track was loved, false if it was unloved
+        pub loved: bool,
     }
 
     /// Response to a Batch Scrobble request
@@ -82,6 +297,100 @@ pub mod responses {
     #[derive(Debug)]
     pub struct BatchScrobbleResponse {
         pub scrobbles: ScrobbleList,
+        /// Number of scrobbles Last.fm accepted & ignored
+        pub counts: ScrobbleCounts,
+    }
+
+    impl BatchScrobbleResponse {
+        /// Returns true if Last.fm accepted every scrobble in the batch. A batch request can succeed while some or all
+        /// of its scrobbles were ignored; see each scrobble's [`ScrobbleResponse::ignore_reason`].
+        /// 
+        /// [`ScrobbleResponse::ignore_reason`]: struct.ScrobbleResponse.html#method.ignore_reason
+        #[must_use]
+        pub fn all_accepted(&self) -> bool {
+            self.counts.ignored == 0
+        }
+
+        /// Pairs each scrobble response with the scrobble submitted at the same index of the batch, returning the
+        /// batch index & a corrected copy of each scrobble. See [`ScrobbleResponse::apply_corrections`].
+        /// 
+        /// Every scrobble is returned; filter on [`CorrectedScrobble::is_changed`] to find those Last.fm corrected.
+        /// 
+        /// [`ScrobbleResponse::apply_corrections`]: struct.ScrobbleResponse.html#method.apply_corrections
+        /// [`CorrectedScrobble::is_changed`]: ../struct.CorrectedScrobble.html#method.is_changed
+        #[must_use]
+        pub fn corrections(&self, batch: &ScrobbleBatch) -> Vec<(usize, CorrectedScrobble)> {
+            self.scrobbles
+                .iter()
+                .zip(batch.iter())
+                .map(|(response, scrobble)| response.apply_corrections(scrobble))
+                .enumerate()
+                .collect()
+        }
+    }
+
+    /// Response to a chunked Batch Scrobble submission
+    /// 
+    /// Returned by [`Scrobbler::scrobble_batch_chunked`], which splits a [`ScrobbleBatch`] of any size into
+    /// API-sized requests. All indices refer to positions in the originally submitted batch, so clients can tell
+    /// exactly which scrobbles were recorded, which were ignored by Last.fm, and which need to be sent again.
+    /// 
+    /// [`Scrobbler::scrobble_batch_chunked`]: ../struct.Scrobbler.html#method.scrobble_batch_chunked
+    /// [`ScrobbleBatch`]: ../struct.ScrobbleBatch.html
+    #[derive(Debug, Default)]
+    pub struct ChunkedScrobbleResponse {
+        /// Responses for each scrobble in successfully submitted chunks, paired with the scrobble's batch index
+        pub scrobbles: Vec<(usize, ScrobbleResponse)>,
+        /// Batch indices of scrobbles accepted by Last.fm
+        pub accepted: Vec<usize>,
+        /// Batch indices of scrobbles Last.fm received but ignored
+        pub ignored: Vec<usize>,
+        /// Chunks which could not be submitted, in submission order
+        pub failed_chunks: Vec<FailedChunk>,
+    }
+
+    impl ChunkedScrobbleResponse {
+        /// Returns true if every chunk was submitted successfully (individual scrobbles may still have been ignored)
+        #[must_use]
+        pub fn is_success(&self) -> bool {
+            self.failed_chunks.is_empty()
+        }
+
+        /// Returns the batch indices of all scrobbles in failed chunks, which may be re-submitted
+        #[must_use]
+        pub fn failed_indices(&self) -> Vec<usize> {
+            self.failed_chunks
+                .iter()
+                .flat_map(|chunk| chunk.indices.clone())
+                .collect()
+        }
+
+        /// Pairs each scrobble response with the scrobble at the same index of the submitted batch, returning the
+        /// batch index & a corrected copy of each scrobble. Scrobbles in failed chunks are not included. See
+        /// [`BatchScrobbleResponse::corrections`].
+        /// 
+        /// [`BatchScrobbleResponse::corrections`]: struct.BatchScrobbleResponse.html#method.corrections
+        #[must_use]
+        pub fn corrections(&self, batch: &ScrobbleBatch) -> Vec<(usize, CorrectedScrobble)> {
+            let submitted: Vec<&Scrobble> = batch.iter().collect();
+            self.scrobbles
+                .iter()
+                .filter_map(|(index, response)| {
+                    let scrobble = submitted.get(*index)?;
+                    Some((*index, response.apply_corrections(scrobble)))
+                })
+                .collect()
+        }
+    }
+
+    /// A chunk of a [`ChunkedScrobbleResponse`] submission that failed, with the error that caused the failure.
+    /// 
+    /// [`ChunkedScrobbleResponse`]: struct.ChunkedScrobbleResponse.html
+    #[derive(Debug)]
+    pub struct FailedChunk {
+        /// Range of batch indices the chunk contained
+        pub indices: Range<usize>,
+        pub error: ScrobblerError,
     }
 
     #[derive(Deserialize, Debug)]
@@ -94,6 +403,8 @@ pub mod responses {
         #[serde(deserialize_with = "BatchScrobbles::deserialize_response_scrobbles")]
         #[serde(rename = "scrobble")]
         pub scrobbles: ScrobbleList,
+        #[serde(rename = "@attr", default)]
+        pub counts: Option<ScrobbleCounts>,
     }
 
     impl BatchScrobbles {
@@ -105,11 +416,11 @@ pub mod responses {
             let deser_result: json::Value = serde::Deserialize::deserialize(de)?;
             let scrobbles = match deser_result {
                 obj@json::Value::Object(_) => {
-                    let scrobble: ScrobbleResponse = serde_json::from_value(obj).expect("Parsing scrobble failed");
+                    let scrobble: ScrobbleResponse = serde_json::from_value(obj).map_err(serde::de::Error::custom)?;
                     ScrobbleList::from(vec!(scrobble))
                 }
                 arr@json::Value::Array(_) => {
-                    let scrobbles: ScrobbleList = serde_json::from_value(arr).expect("Parsing scrobble list failed");
+                    let scrobbles: ScrobbleList = serde_json::from_value(arr).map_err(serde::de::Error::custom)?;
                     scrobbles
                 },
                 _ => ScrobbleList::from(vec!())
@@ -118,11 +429,614 @@ pub mod responses {
         }
     }
 
+    #[derive(Deserialize, Debug)]
+    pub struct RecentTracksResponseWrapper {
+        pub recenttracks: RecentTracksPage,
+    }
+
+    /// A page of a user's listening history, returned by [`Scrobbler::recent_tracks_page`].
+    /// 
+    /// If the user is currently listening to a track, the first page starts with that track, marked as
+    /// [`RecentTrack::now_playing`]. Such a track is extra to the requested page size.
+    /// 
+    /// [user.getRecentTracks API Method Documentation](https://www.last.fm/api/show/user.getRecentTracks)
+    /// 
+    /// [`Scrobbler::recent_tracks_page`]: ../struct.Scrobbler.html#method.recent_tracks_page
+    /// [`RecentTrack::now_playing`]: values/struct.RecentTrack.html#structfield.now_playing
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct RecentTracksPage {
+        #[serde(rename = "track", default, deserialize_with = "deserialize_one_or_many")]
+        pub tracks: Vec<RecentTrack>,
+        #[serde(rename = "@attr")]
+        pub pagination: Pagination,
+    }
+
+    /// The `@attr` paging summary of a paginated API response
+    #[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct Pagination {
+        /// The (1-based) number of this page
+        #[serde(deserialize_with = "deserialize_count")]
+        pub page: u32,
+        #[serde(rename = "perPage", deserialize_with = "deserialize_count")]
+        pub per_page: u32,
+        #[serde(rename = "totalPages", deserialize_with = "deserialize_count")]
+        pub total_pages: u32,
+        /// Total number of items across all pages
+        #[serde(deserialize_with = "deserialize_count")]
+        pub total: u32,
+    }
+
+    /// A single track from a user's listening history
+    /// 
+    /// [user.getRecentTracks API Method Documentation](https://www.last.fm/api/show/user.getRecentTracks)
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct RecentTrack {
+        pub name: String,
+        #[serde(default, deserialize_with = "deserialize_mbid")]
+        pub mbid: Option<String>,
+        pub artist: NamedValue,
+        #[serde(default)]
+        pub album: NamedValue,
+        #[serde(default)]
+        pub url: String,
+        #[serde(rename = "image", default)]
+        pub images: Vec<Image>,
+        /// When the track was scrobbled, as a UNIX timestamp. `None` for a track that is playing now.
+        #[serde(rename = "date", default, deserialize_with = "RecentTrack::deserialize_date")]
+        pub timestamp: Option<u64>,
+        /// True if the user is listening to this track right now; it has not been scrobbled yet
+        #[serde(rename = "@attr", default, deserialize_with = "RecentTrack::deserialize_now_playing")]
+        pub now_playing: bool,
+        /// Whether the user has loved the track. Only returned for `extended` requests.
+        #[serde(default, deserialize_with = "deserialize_flag")]
+        pub loved: Option<bool>,
+    }
+
+    impl RecentTrack {
+        /// Returns a [`Scrobble`] of this track, with its timestamp and MusicBrainz ID
+        /// 
+        /// [`Scrobble`]: ../../struct.Scrobble.html
+        #[must_use]
+        pub fn to_scrobble(&self) -> Scrobble {
+            let mut scrobble = Scrobble::new(&self.artist.name, &self.name, &self.album.name);
+            if let Some(timestamp) = self.timestamp {
+                scrobble.with_timestamp(timestamp);
+            }
+            if let Some(ref mbid) = self.mbid {
+                scrobble.with_mbid(mbid);
+            }
+
+            scrobble
+        }
+
+        fn deserialize_date<'de, D>(de: D) -> Result<Option<u64>, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            #[derive(Deserialize)]
+            struct Date {
+                uts: String,
+            }
+
+            let date: Option<Date> = serde::Deserialize::deserialize(de)?;
+            date.map(|date| date.uts.parse().map_err(serde::de::Error::custom))
+                .transpose()
+        }
+
+        fn deserialize_now_playing<'de, D>(de: D) -> Result<bool, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let attr: json::Value = serde::Deserialize::deserialize(de)?;
+            Ok(attr.get("nowplaying").and_then(json::Value::as_str) == Some("true"))
+        }
+    }
+
+    /// A name with an optional MusicBrainz ID, such as the artist or album of a [`RecentTrack`]
+    /// 
+    /// [`RecentTrack`]: struct.RecentTrack.html
+    #[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+    pub struct NamedValue {
+        // Extended responses give the artist's name as `name` rather than `#text`
+        #[serde(rename = "#text", alias = "name", default)]
+        pub name: String,
+        #[serde(default, deserialize_with = "deserialize_mbid")]
+        pub mbid: Option<String>,
+    }
+
+    impl fmt::Display for NamedValue {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.name)
+        }
+    }
+
+    /// A link to an image (album art, artist photo...) in one of the sizes Last.fm provides
+    #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct Image {
+        /// Size name, e.g. `small`, `medium`, `large` or `extralarge`
+        #[serde(default)]
+        pub size: String,
+        #[serde(rename = "#text", default)]
+        pub url: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct TrackInfoResponseWrapper {
+        pub track: TrackInfo,
+    }
+
+    /// Response to a `track.getInfo` request: metadata & listening statistics for a track.
+    /// 
+    /// Can be turned into a [`Scrobble`] with [`TrackInfo::to_scrobble`], or used to fill in the missing metadata of
+    /// an existing one with [`TrackInfo::enrich`].
+    /// 
+    /// [track.getInfo API Method Documentation](https://www.last.fm/api/show/track.getInfo)
+    /// 
+    /// [`Scrobble`]: ../struct.Scrobble.html
+    /// [`TrackInfo::to_scrobble`]: struct.TrackInfo.html#method.to_scrobble
+    /// [`TrackInfo::enrich`]: struct.TrackInfo.html#method.enrich
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct TrackInfo {
+        pub name: String,
+        #[serde(default, deserialize_with = "deserialize_mbid")]
+        pub mbid: Option<String>,
+        #[serde(default)]
+        pub url: String,
+        /// Length of the track in seconds, if known
+        #[serde(default, deserialize_with = "TrackInfo::deserialize_duration")]
+        pub duration: Option<u64>,
+        #[serde(default, deserialize_with = "deserialize_total")]
+        pub listeners: u64,
+        #[serde(default, deserialize_with = "deserialize_total")]
+        pub playcount: u64,
+        pub artist: NamedValue,
+        #[serde(default)]
+        pub album: Option<TrackAlbum>,
+        /// Number of times the requested user has played the track. Only returned when a username is given.
+        #[serde(rename = "userplaycount", default, deserialize_with = "deserialize_optional_count")]
+        pub user_playcount: Option<u32>,
+        /// Whether the requested user has loved the track. Only returned when a username is given.
+        #[serde(rename = "userloved", default, deserialize_with = "deserialize_flag")]
+        pub user_loved: Option<bool>,
+        #[serde(rename = "toptags", default, deserialize_with = "deserialize_tags")]
+        pub tags: Vec<Tag>,
+    }
+
+    impl TrackInfo {
+        /// Returns a [`Scrobble`] of this track, with its album, album artist, duration & MusicBrainz ID where known
+        /// 
+        /// [`Scrobble`]: ../struct.Scrobble.html
+        #[must_use]
+        pub fn to_scrobble(&self) -> Scrobble {
+            self.enrich(&Scrobble::new(&self.artist.name, &self.name, ""))
+        }
+
+        /// Returns a copy of the given [`Scrobble`] with its missing album, album artist, duration & MusicBrainz ID
+        /// filled in from this track. Values already set on the scrobble are kept.
+        /// 
+        /// [`Scrobble`]: ../struct.Scrobble.html
+        #[must_use]
+        pub fn enrich(&self, scrobble: &Scrobble) -> Scrobble {
+            let mut enriched = scrobble.clone();
+
+            if let Some(ref album) = self.album {
+                if enriched.album().is_empty() {
+                    enriched.with_album(&album.title);
+                }
+                if enriched.album_artist().is_none() && !album.artist.is_empty() {
+                    enriched.with_album_artist(&album.artist);
+                }
+            }
+            if let (None, Some(duration)) = (enriched.duration(), self.duration) {
+                enriched.with_duration(duration);
+            }
+            if let (None, Some(mbid)) = (enriched.mbid(), &self.mbid) {
+                enriched.with_mbid(mbid);
+            }
+
+            enriched
+        }
+
+        // Track durations are given in milliseconds, with "0" for unknown
+        fn deserialize_duration<'de, D>(de: D) -> Result<Option<u64>, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let duration = deserialize_optional_total(de)?;
+            Ok(duration.map(|ms| ms / 1000).filter(|secs| *secs > 0))
+        }
+    }
+
+    /// The album a [`TrackInfo`] track appears on
+    /// 
+    /// [`TrackInfo`]: ../struct.TrackInfo.html
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct TrackAlbum {
+        /// The album artist
+        #[serde(default)]
+        pub artist: String,
+        #[serde(default)]
+        pub title: String,
+        #[serde(default, deserialize_with = "deserialize_mbid")]
+        pub mbid: Option<String>,
+        #[serde(default)]
+        pub url: String,
+        #[serde(rename = "image", default)]
+        pub images: Vec<Image>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct ArtistInfoResponseWrapper {
+        pub artist: ArtistInfo,
+    }
+
+    /// Response to an `artist.getInfo` request: metadata & listening statistics for an artist.
+    /// 
+    /// [artist.getInfo API Method Documentation](https://www.last.fm/api/show/artist.getInfo)
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct ArtistInfo {
+        pub name: String,
+        #[serde(default, deserialize_with = "deserialize_mbid")]
+        pub mbid: Option<String>,
+        #[serde(default)]
+        pub url: String,
+        #[serde(rename = "image", default)]
+        pub images: Vec<Image>,
+        #[serde(default)]
+        pub stats: ArtistStats,
+        #[serde(default, deserialize_with = "deserialize_tags")]
+        pub tags: Vec<Tag>,
+    }
+
+    /// Listening statistics of an [`ArtistInfo`] artist
+    /// 
+    /// [`ArtistInfo`]: ../struct.ArtistInfo.html
+    #[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct ArtistStats {
+        #[serde(default, deserialize_with = "deserialize_total")]
+        pub listeners: u64,
+        #[serde(default, deserialize_with = "deserialize_total")]
+        pub playcount: u64,
+        /// Number of times the requested user has played the artist. Only returned when a username is given.
+        #[serde(rename = "userplaycount", default, deserialize_with = "deserialize_optional_count")]
+        pub user_playcount: Option<u32>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct AlbumInfoResponseWrapper {
+        pub album: AlbumInfo,
+    }
+
+    /// Response to an `album.getInfo` request: metadata, tracklist & listening statistics for an album.
+    /// 
+    /// The tracklist can be turned into [`Scrobble`]s with [`AlbumInfo::to_scrobble_batch`], or used to fill in the
+    /// missing metadata of an existing scrobble with [`AlbumInfo::enrich`].
+    /// 
+    /// [album.getInfo API Method Documentation](https://www.last.fm/api/show/album.getInfo)
+    /// 
+    /// [`Scrobble`]: ../struct.Scrobble.html
+    /// [`AlbumInfo::to_scrobble_batch`]: struct.AlbumInfo.html#method.to_scrobble_batch
+    /// [`AlbumInfo::enrich`]: struct.AlbumInfo.html#method.enrich
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct AlbumInfo {
+        pub name: String,
+        /// The album artist
+        pub artist: String,
+        #[serde(default, deserialize_with = "deserialize_mbid")]
+        pub mbid: Option<String>,
+        #[serde(default)]
+        pub url: String,
+        #[serde(rename = "image", default)]
+        pub images: Vec<Image>,
+        #[serde(default, deserialize_with = "deserialize_total")]
+        pub listeners: u64,
+        #[serde(default, deserialize_with = "deserialize_total")]
+        pub playcount: u64,
+        /// Number of times the requested user has played the album. Only returned when a username is given.
+        #[serde(rename = "userplaycount", default, deserialize_with = "deserialize_optional_count")]
+        pub user_playcount: Option<u32>,
+        #[serde(default, deserialize_with = "AlbumInfo::deserialize_tracks")]
+        pub tracks: Vec<AlbumTrack>,
+        #[serde(default, deserialize_with = "deserialize_tags")]
+        pub tags: Vec<Tag>,
+    }
+
+    impl AlbumInfo {
+        /// Returns a [`ScrobbleBatch`] of the album's tracklist, with album artist, track numbers & durations where
+        /// known. The scrobbles have no timestamps.
+        /// 
+        /// [`ScrobbleBatch`]: ../struct.ScrobbleBatch.html
+        #[must_use]
+        pub fn to_scrobble_batch(&self) -> ScrobbleBatch {
+            let scrobbles: Vec<Scrobble> = self
+                .tracks
+                .iter()
+                .map(|track| self.enrich(&Scrobble::new(&track.artist.name, &track.name, "")))
+                .collect();
+
+            ScrobbleBatch::from(scrobbles)
+        }
+
+        /// Returns a copy of the given [`Scrobble`] with its missing album & album artist filled in from this album.
+        /// If the scrobble's track is on the album's tracklist (compared case-insensitively), a missing track number &
+        /// duration are filled in too. Values already set on the scrobble are kept.
+        /// 
+        /// [`Scrobble`]: ../struct.Scrobble.html
+        #[must_use]
+        pub fn enrich(&self, scrobble: &Scrobble) -> Scrobble {
+            let mut enriched = scrobble.clone();
+
+            if enriched.album().is_empty() {
+                enriched.with_album(&self.name);
+            }
+            if enriched.album_artist().is_none() && !self.artist.is_empty() {
+                enriched.with_album_artist(&self.artist);
+            }
+
+            let track_name = scrobble.track().to_lowercase();
+            if let Some(track) = self.tracks.iter().find(|track| track.name.to_lowercase() == track_name) {
+                if let (None, Some(rank)) = (enriched.track_number(), track.rank) {
+                    enriched.with_track_number(rank);
+                }
+                if let (None, Some(duration)) = (enriched.duration(), track.duration) {
+                    enriched.with_duration(duration);
+                }
+            }
+
+            enriched
+        }
+
+        fn deserialize_tracks<'de, D>(de: D) -> Result<Vec<AlbumTrack>, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let deser_result: json::Value = serde::Deserialize::deserialize(de)?;
+            nested_list(deser_result, "track").map_err(serde::de::Error::custom)
+        }
+    }
+
+    /// A track on an [`AlbumInfo`] album's tracklist
+    /// 
+    /// [`AlbumInfo`]: ../struct.AlbumInfo.html
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct AlbumTrack {
+        pub name: String,
+        #[serde(default)]
+        pub url: String,
+        /// Length of the track in seconds, if known
+        #[serde(default, deserialize_with = "deserialize_optional_total")]
+        pub duration: Option<u64>,
+        pub artist: NamedValue,
+        /// The track's position on the album
+        #[serde(rename = "@attr", default, deserialize_with = "AlbumTrack::deserialize_rank")]
+        pub rank: Option<u32>,
+    }
+
+    impl AlbumTrack {
+        fn deserialize_rank<'de, D>(de: D) -> Result<Option<u32>, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let attr: json::Value = serde::Deserialize::deserialize(de)?;
+            Ok(attr.get("rank").and_then(value_as_u64).and_then(|rank| u32::try_from(rank).ok()))
+        }
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct TrackCorrectionResponseWrapper {
+        #[serde(deserialize_with = "deserialize_corrections")]
+        pub corrections: Vec<TrackCorrection>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct ArtistCorrectionResponseWrapper {
+        #[serde(deserialize_with = "deserialize_corrections")]
+        pub corrections: Vec<ArtistCorrection>,
+    }
+
+    /// Response to a `track.getCorrection` request: the canonical artist & track name Last.fm would correct the
+    /// requested names to.
+    /// 
+    /// [track.getCorrection API Method Documentation](https://www.last.fm/api/show/track.getCorrection)
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(from = "RawTrackCorrection")]
+    pub struct TrackCorrection {
+        pub artist: NamedValue,
+        pub track: String,
+        /// True if the requested artist name was corrected
+        pub artist_corrected: bool,
+        /// True if the requested track name was corrected
+        pub track_corrected: bool,
+    }
+
+    impl TrackCorrection {
+        /// Returns a copy of the given [`Scrobble`] with the corrected artist & track name, and the fields that
+        /// changed.
+        /// 
+        /// [`Scrobble`]: ../struct.Scrobble.html
+        #[must_use]
+        pub fn apply(&self, scrobble: &Scrobble) -> CorrectedScrobble {
+            let mut corrected = CorrectedScrobble::new(scrobble);
+            if self.artist_corrected {
+                corrected.set(ScrobbleField::Artist, &self.artist.name);
+            }
+            if self.track_corrected {
+                corrected.set(ScrobbleField::Track, &self.track);
+            }
+
+            corrected
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct RawTrackCorrection {
+        track: RawCorrectedTrack,
+        #[serde(rename = "@attr", default)]
+        attr: CorrectionFlags,
+    }
+
+    #[derive(Deserialize)]
+    struct RawCorrectedTrack {
+        name: String,
+        artist: NamedValue,
+    }
+
+    #[derive(Deserialize, Default)]
+    struct CorrectionFlags {
+        #[serde(rename = "artistcorrected", default, deserialize_with = "deserialize_flag")]
+        artist: Option<bool>,
+        #[serde(rename = "trackcorrected", default, deserialize_with = "deserialize_flag")]
+        track: Option<bool>,
+    }
+
+    impl From<RawTrackCorrection> for TrackCorrection {
+        fn from(raw: RawTrackCorrection) -> Self {
+            Self {
+                artist: raw.track.artist,
+                track: raw.track.name,
+                artist_corrected: raw.attr.artist.unwrap_or_default(),
+                track_corrected: raw.attr.track.unwrap_or_default(),
+            }
+        }
+    }
+
+    /// Response to an `artist.getCorrection` request: the canonical name Last.fm would correct the requested artist
+    /// name to.
+    /// 
+    /// [artist.getCorrection API Method Documentation](https://www.last.fm/api/show/artist.getCorrection)
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct ArtistCorrection {
+        pub artist: NamedValue,
+    }
+
+    impl ArtistCorrection {
+        /// Returns a copy of the given [`Scrobble`] with the corrected artist name, and the fields that changed.
+        /// 
+        /// [`Scrobble`]: ../struct.Scrobble.html
+        #[must_use]
+        pub fn apply(&self, scrobble: &Scrobble) -> CorrectedScrobble {
+            let mut corrected = CorrectedScrobble::new(scrobble);
+            corrected.set(ScrobbleField::Artist, &self.artist.name);
+
+            corrected
+        }
+    }
+
+    // Sent as `"corrections": {"correction": ...}`, or a blank string if there is no correction
+    fn deserialize_corrections<'de, D, T>(de: D) -> Result<Vec<T>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+        T: serde::de::DeserializeOwned,
+    {
+        let deser_result: json::Value = serde::Deserialize::deserialize(de)?;
+        nested_list(deser_result, "correction").map_err(serde::de::Error::custom)
+    }
+
+    /// A tag (genre, mood...) applied to a track, album or artist by Last.fm users
+    #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct Tag {
+        pub name: String,
+        #[serde(default)]
+        pub url: String,
+    }
+
+    fn deserialize_tags<'de, D>(de: D) -> Result<Vec<Tag>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let deser_result: json::Value = serde::Deserialize::deserialize(de)?;
+        nested_list(deser_result, "tag").map_err(serde::de::Error::custom)
+    }
+
+    // Lists are wrapped in an object, e.g. `"tags": {"tag": [...]}`, which is an empty string if there are no items
+    fn nested_list<T: serde::de::DeserializeOwned>(value: json::Value, key: &str) -> json::Result<Vec<T>> {
+        match value {
+            json::Value::Object(mut obj) => match obj.remove(key) {
+                Some(arr @ json::Value::Array(_)) => json::from_value(arr),
+                Some(item @ json::Value::Object(_)) => json::from_value(item).map(|item| vec![item]),
+                _ => Ok(Vec::new()),
+            },
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn value_as_u64(value: &json::Value) -> Option<u64> {
+        match value {
+            json::Value::String(s) => s.parse().ok(),
+            json::Value::Number(n) => n.as_u64(),
+            _ => None,
+        }
+    }
+
+    // Like `deserialize_count`, for totals which may not fit in a u32
+    fn deserialize_total<'de, D>(de: D) -> Result<u64, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let deser_result: json::Value = serde::Deserialize::deserialize(de)?;
+        value_as_u64(&deser_result).ok_or_else(|| serde::de::Error::custom("Unexpected value"))
+    }
+
+    fn deserialize_optional_total<'de, D>(de: D) -> Result<Option<u64>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let deser_result: json::Value = serde::Deserialize::deserialize(de)?;
+        Ok(value_as_u64(&deser_result))
+    }
+
+    fn deserialize_optional_count<'de, D>(de: D) -> Result<Option<u32>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let total = deserialize_optional_total(de)?;
+        Ok(total.and_then(|total| u32::try_from(total).ok()))
+    }
+
+    // Lists holding a single item are sometimes sent as the bare item
+    fn deserialize_one_or_many<'de, D, T>(de: D) -> Result<Vec<T>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+        T: serde::de::DeserializeOwned,
+    {
+        let deser_result: json::Value = serde::Deserialize::deserialize(de)?;
+        match deser_result {
+            arr @ json::Value::Array(_) => json::from_value(arr).map_err(serde::de::Error::custom),
+            obj @ json::Value::Object(_) => json::from_value(obj)
+                .map(|item| vec![item])
+                .map_err(serde::de::Error::custom),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    // Missing MusicBrainz IDs are sent as empty strings
+    fn deserialize_mbid<'de, D>(de: D) -> Result<Option<String>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let mbid: Option<String> = serde::Deserialize::deserialize(de)?;
+        Ok(mbid.filter(|mbid| !mbid.is_empty()))
+    }
+
+    // Boolean flags are sent as "0"/"1" strings
+    fn deserialize_flag<'de, D>(de: D) -> Result<Option<bool>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let deser_result: json::Value = serde::Deserialize::deserialize(de)?;
+        match deser_result {
+            json::Value::String(ref s) if s == "1" => Ok(Some(true)),
+            json::Value::String(ref s) if s == "0" => Ok(Some(false)),
+            json::Value::Null => Ok(None),
+            _ => Err(serde::de::Error::custom("Unexpected value")),
+        }
+    }
+
     /// Represents a string that can be marked as 'corrected' by the Last.fm API. 
     /// 
     /// All Scrobble/NowPlaying responses have their fields as `CorrectableString`'s. The API will sometimes change
     /// the artist/song name/album name data that you have submitted. For example - it is common for Bjork to be turned
-    /// into Björk by the API; the modified artist field would be marked `corrected = true`, `text = "Björk". 
+    /// into Björk by the API; the modified artist field would be marked `corrected = true`, `text = "Björk"`. 
     /// 
     /// Most clients can ignore these corrections, but the information is exposed for clients that require it.
     /// 
@@ -142,8 +1056,8 @@ pub mod responses {
         {
             let deser_result: json::Value = serde::Deserialize::deserialize(de)?;
             match deser_result {
-                json::Value::String(ref s) if &*s == "1" => Ok(true),
-                json::Value::String(ref s) if &*s == "0" => Ok(false),
+                json::Value::String(ref s) if s == "1" => Ok(true),
+                json::Value::String(ref s) if s == "0" => Ok(false),
                 _ => Err(serde::de::Error::custom("Unexpected value")),
             }
         }
@@ -163,23 +1077,67 @@ pub mod metadata {
     /// Repesents a single music track played at a point in time. In the Last.fm universe, this is known as a 
     /// "scrobble".
     /// 
-    /// Takes an artist, track and album name. Can hold a timestamp indicating when the track was listened to.
-    /// `Scrobble` objects are submitted via [`Scrobbler::now_playing`], [`Scrobbler::scrobble`] and batches of
-    /// Scrobbles are sent via [`Scrobbler::scrobble_batch`].
+    /// Takes an artist, track and album name. Can hold a timestamp indicating when the track was listened to, as well
+    /// as the optional track metadata accepted by the Last.fm API (album artist, duration, track number, MusicBrainz
+    /// ID...), which are set with the builder-style `with_*` methods. `Scrobble` objects are submitted via
+    /// [`Scrobbler::now_playing`], [`Scrobbler::scrobble`] and batches of Scrobbles are sent via
+    /// [`Scrobbler::scrobble_batch`].
+    /// 
+    /// # Serialization
+    /// With the `serde` cargo feature enabled, `Scrobble` implements serde's `Serialize`/`Deserialize`, and a
+    /// [`ScrobbleBatch`] is (de)serialized as a sequence of `Scrobble`s. The field layout is stable:
+    /// 
+    /// ```json
+    /// {
+    ///     "artist": "Example Artist",
+    ///     "track": "Example Track",
+    ///     "album": "Example Album",
+    ///     "timestamp": 1600000000,
+    ///     "album_artist": "Various Artists",
+    ///     "duration": 241,
+    ///     "track_number": 3,
+    ///     "mbid": "0a1b2c3d-0000-1111-2222-333344445555",
+    ///     "context": "ctx",
+    ///     "stream_id": "stream",
+    ///     "chosen_by_user": true
+    /// }
+    /// ```
+    /// 
+    /// `artist` and `track` are required. `album` defaults to an empty string, and every other field is optional and
+    /// omitted when unset. Timestamps are UNIX timestamps, and durations are in seconds.
     /// 
     /// [`Scrobbler::now_playing`]: struct.Scrobbler.html#method.now_playing
     /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
     /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    /// [`ScrobbleBatch`]: struct.ScrobbleBatch.html
     #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, WrappedVec)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     #[CollectionName = "ScrobbleBatch"]
     #[CollectionDoc = "A batch of Scrobbles to be submitted to Last.fm together."]
     #[CollectionDerives = "Clone, Debug"]
     pub struct Scrobble {
         artist: String,
         track: String,
+        #[cfg_attr(feature = "serde", serde(default))]
         album: String,
 
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
         timestamp: Option<u64>,
+
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        album_artist: Option<String>,
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        duration: Option<u64>,
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        track_number: Option<u32>,
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        mbid: Option<String>,
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        context: Option<String>,
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        stream_id: Option<String>,
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        chosen_by_user: Option<bool>,
     }
 
     impl Scrobble {
@@ -194,12 +1152,20 @@ pub mod metadata {
         /// ```
         /// 
         /// [`Scrobbler`]: struct.Scrobbler.html
+        #[must_use]
         pub fn new(artist: &str, track: &str, album: &str) -> Self {
             Self {
                 artist: artist.to_owned(),
                 track: track.to_owned(),
                 album: album.to_owned(),
                 timestamp: None,
+                album_artist: None,
+                duration: None,
+                track_number: None,
+                mbid: None,
+                context: None,
+                stream_id: None,
+                chosen_by_user: None,
             }
         }
 
@@ -224,9 +1190,78 @@ pub mod metadata {
             self
         }
 
+        /// Sets the artist name of a Scrobble, replacing the one it was created with
+        pub fn with_artist(&mut self, artist: &str) -> &mut Self {
+            artist.clone_into(&mut self.artist);
+            self
+        }
+
+        /// Sets the track name of a Scrobble, replacing the one it was created with
+        pub fn with_track(&mut self, track: &str) -> &mut Self {
+            track.clone_into(&mut self.track);
+            self
+        }
+
+        /// Sets the album name of a Scrobble, replacing the one it was created with
+        pub fn with_album(&mut self, album: &str) -> &mut Self {
+            album.clone_into(&mut self.album);
+            self
+        }
+
+        /// Sets the album artist of a Scrobble, when it differs from the track artist (e.g. on compilation albums).
+        ///
+        /// # Example
+        /// ```ignore
+        /// let mut scrobble = Scrobble::new("Example Artist", "Example Track", "Now That's What I Call Music");
+        /// scrobble.with_album_artist("Various Artists");
+        /// ```
+        pub fn with_album_artist(&mut self, album_artist: &str) -> &mut Self {
+            self.album_artist = Some(album_artist.to_owned());
+            self
+        }
+
+        /// Sets the length of the track, in seconds
+        pub fn with_duration(&mut self, duration: u64) -> &mut Self {
+            self.duration = Some(duration);
+            self
+        }
+
+        /// Sets the track's number (position) on its album
+        pub fn with_track_number(&mut self, track_number: u32) -> &mut Self {
+            self.track_number = Some(track_number);
+            self
+        }
+
+        /// Sets the [MusicBrainz](https://musicbrainz.org/) Track ID of the track
+        pub fn with_mbid(&mut self, mbid: &str) -> &mut Self {
+            self.mbid = Some(mbid.to_owned());
+            self
+        }
+
+        /// Sets the sub-client version (not public, only enabled for certain API keys)
+        pub fn with_context(&mut self, context: &str) -> &mut Self {
+            self.context = Some(context.to_owned());
+            self
+        }
+
+        /// Sets the stream ID, for tracks played from a Last.fm radio stream
+        pub fn with_stream_id(&mut self, stream_id: &str) -> &mut Self {
+            self.stream_id = Some(stream_id.to_owned());
+            self
+        }
+
+        /// Sets whether the user chose this song themselves (`true`), or it was chosen by someone else, such as a
+        /// radio station or recommendation service (`false`). Last.fm assumes `true` when this is not set.
+        pub fn with_chosen_by_user(&mut self, chosen_by_user: bool) -> &mut Self {
+            self.chosen_by_user = Some(chosen_by_user);
+            self
+        }
+
         /// Converts the Scrobble metadata (track name, artist & album name) into a `HashMap`. Map keys are 
         /// `"track"`, `"artist"` and `"album"`. If a timestamp is set, it will be present in the map under key 
-        /// `"timestamp"`.
+        /// `"timestamp"`. Any optional metadata that has been set is included under the Last.fm API parameter names:
+        /// `"albumArtist"`, `"duration"`, `"trackNumber"`, `"mbid"`, `"context"`, `"streamId"` and
+        /// `"chosenByUser"`.
         /// 
         /// # Example
         /// ```ignore
@@ -234,6 +1269,7 @@ pub mod metadata {
         /// let scrobble_map = scrobble.as_map();
         /// assert_eq!(scrobble_map.get("artist"), "Example Artist");
         /// ```
+        #[must_use]
         pub fn as_map(&self) -> HashMap<String, String> {
             let mut params = HashMap::new();
             params.insert("track".to_string(), self.track.clone());
@@ -244,23 +1280,103 @@ pub mod metadata {
                 params.insert("timestamp".to_string(), timestamp.to_string());
             }
 
+            if let Some(ref album_artist) = self.album_artist {
+                params.insert("albumArtist".to_string(), album_artist.clone());
+            }
+
+            if let Some(duration) = self.duration {
+                params.insert("duration".to_string(), duration.to_string());
+            }
+
+            if let Some(track_number) = self.track_number {
+                params.insert("trackNumber".to_string(), track_number.to_string());
+            }
+
+            if let Some(ref mbid) = self.mbid {
+                params.insert("mbid".to_string(), mbid.clone());
+            }
+
+            if let Some(ref context) = self.context {
+                params.insert("context".to_string(), context.clone());
+            }
+
+            if let Some(ref stream_id) = self.stream_id {
+                params.insert("streamId".to_string(), stream_id.clone());
+            }
+
+            if let Some(chosen_by_user) = self.chosen_by_user {
+                let value = if chosen_by_user { "1" } else { "0" };
+                params.insert("chosenByUser".to_string(), value.to_string());
+            }
+
             params
         }
 
         /// Returns the `Scrobble`'s artist name
+        #[must_use]
         pub fn artist(&self) -> &str {
             &self.artist
         }
 
         /// Returns the `Scrobble`'s track name
+        #[must_use]
         pub fn track(&self) -> &str {
             &self.track
         }
 
         /// Returns the `Scrobble`'s album name
+        #[must_use]
         pub fn album(&self) -> &str {
             &self.album
         }
+
+        /// Returns the `Scrobble`'s timestamp, if set
+        #[must_use]
+        pub fn timestamp(&self) -> Option<u64> {
+            self.timestamp
+        }
+
+        /// Returns the `Scrobble`'s album artist, if set
+        #[must_use]
+        pub fn album_artist(&self) -> Option<&str> {
+            self.album_artist.as_deref()
+        }
+
+        /// Returns the `Scrobble`'s track length in seconds, if set
+        #[must_use]
+        pub fn duration(&self) -> Option<u64> {
+            self.duration
+        }
+
+        /// Returns the `Scrobble`'s track number, if set
+        #[must_use]
+        pub fn track_number(&self) -> Option<u32> {
+            self.track_number
+        }
+
+        /// Returns the `Scrobble`'s MusicBrainz Track ID, if set
+        #[must_use]
+        pub fn mbid(&self) -> Option<&str> {
+            self.mbid.as_deref()
+        }
+
+        /// Returns the `Scrobble`'s sub-client context, if set
+        #[must_use]
+        pub fn context(&self) -> Option<&str> {
+            self.context.as_deref()
+        }
+
+        /// Returns the `Scrobble`'s stream ID, if set
+        #[must_use]
+        pub fn stream_id(&self) -> Option<&str> {
+            self.stream_id.as_deref()
+        }
+
+        /// Returns whether the user chose to play the `Scrobble`'s track, if set
+        #[must_use]
+        pub fn chosen_by_user(&self) -> Option<bool> {
+            self.chosen_by_user
+        }
     
     }
 
@@ -310,6 +1426,21 @@ pub mod metadata {
         }
     }
 
+    #[cfg(feature = "serde")]
+    impl serde::Serialize for ScrobbleBatch {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.iter())
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de> serde::Deserialize<'de> for ScrobbleBatch {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let scrobbles: Vec<Scrobble> = serde::Deserialize::deserialize(deserializer)?;
+            Ok(ScrobbleBatch::from(scrobbles))
+        }
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -340,6 +1471,95 @@ pub mod metadata {
             assert_eq!(params["artist"], "foo floyd and the fruit flies");
             assert_eq!(params["track"], "old bananas");
             assert_eq!(params["album"], "old bananas");
+            assert!(!params.contains_key("albumArtist"));
+            assert!(!params.contains_key("chosenByUser"));
+        }
+
+        #[test]
+        fn make_scrobble_with_optional_params() {
+            let mut scrobble = Scrobble::new(
+                "foo floyd and the fruit flies",
+                "old bananas",
+                "old bananas",
+            );
+            scrobble
+                .with_album_artist("foo floyd")
+                .with_duration(215)
+                .with_track_number(3)
+                .with_mbid("0a1b2c3d-0000-1111-2222-333344445555")
+                .with_context("ctx")
+                .with_stream_id("stream")
+                .with_chosen_by_user(false);
+
+            assert_eq!(scrobble.album_artist(), Some("foo floyd"));
+            assert_eq!(scrobble.duration(), Some(215));
+            assert_eq!(scrobble.track_number(), Some(3));
+
+            let params = scrobble.as_map();
+            assert_eq!(params["albumArtist"], "foo floyd");
+            assert_eq!(params["duration"], "215");
+            assert_eq!(params["trackNumber"], "3");
+            assert_eq!(params["mbid"], "0a1b2c3d-0000-1111-2222-333344445555");
+            assert_eq!(params["context"], "ctx");
+            assert_eq!(params["streamId"], "stream");
+            assert_eq!(params["chosenByUser"], "0");
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn make_scrobble_serde_round_trip() {
+            let mut scrobble = Scrobble::new("foo floyd and the fruit flies", "old bananas", "old bananas");
+            scrobble
+                .with_timestamp(1_600_000_000)
+                .with_album_artist("foo floyd")
+                .with_duration(215)
+                .with_track_number(3)
+                .with_mbid("0a1b2c3d-0000-1111-2222-333344445555")
+                .with_context("ctx")
+                .with_stream_id("stream")
+                .with_chosen_by_user(false);
+
+            let json = serde_json::to_string(&scrobble).unwrap();
+            assert_eq!(
+                json,
+                concat!(
+                    r#"{"artist":"foo floyd and the fruit flies","track":"old bananas","album":"old bananas","#,
+                    r#""timestamp":1600000000,"album_artist":"foo floyd","duration":215,"track_number":3,"#,
+                    r#""mbid":"0a1b2c3d-0000-1111-2222-333344445555","context":"ctx","stream_id":"stream","#,
+                    r#""chosen_by_user":false}"#,
+                )
+            );
+            assert_eq!(serde_json::from_str::<Scrobble>(&json).unwrap(), scrobble);
+
+            // Unset optional fields are omitted
+            let scrobble = Scrobble::new("foo floyd", "old bananas", "");
+            let json = serde_json::to_string(&scrobble).unwrap();
+            assert_eq!(json, r#"{"artist":"foo floyd","track":"old bananas","album":""}"#);
+
+            let parsed: Scrobble = serde_json::from_str(r#"{"artist":"foo floyd","track":"old bananas"}"#).unwrap();
+            assert_eq!(parsed, scrobble);
+            assert!(serde_json::from_str::<Scrobble>(r#"{"artist":"foo floyd"}"#).is_err());
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn make_scrobble_batch_serde_round_trip() {
+            let batch = ScrobbleBatch::from(vec![
+                ("foo floyd", "old bananas", "old bananas"),
+                ("foo floyd", "new bananas", ""),
+            ]);
+
+            let json = serde_json::to_string(&batch).unwrap();
+            assert_eq!(
+                json,
+                concat!(
+                    r#"[{"artist":"foo floyd","track":"old bananas","album":"old bananas"},"#,
+                    r#"{"artist":"foo floyd","track":"new bananas","album":""}]"#,
+                )
+            );
+
+            let parsed: ScrobbleBatch = serde_json::from_str(&json).unwrap();
+            assert!(parsed.iter().eq(batch.iter()));
         }
     }
 }
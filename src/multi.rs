@@ -0,0 +1,323 @@
+// Fan-out of scrobbling calls to multiple services
+use std::collections::HashMap;
+use std::time::{SystemTimeError, UNIX_EPOCH};
+
+use crate::error::ScrobblerError;
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::models::responses::{BatchScrobbleResponse, NowPlayingResponse, ScrobbleResponse};
+use crate::queue::{QueueDrainReport, ScrobbleQueue};
+use crate::scrobbler::Scrobbler;
+
+/// Results of a [`MultiScrobbler`] call, keyed by service name
+///
+/// [`MultiScrobbler`]: struct.MultiScrobbler.html
+pub type ServiceResults<T> = HashMap<String, Result<T, ScrobblerError>>;
+
+/// Sends now playing and scrobble requests to several scrobbling services at once.
+///
+/// Holds a set of named, authenticated [`Scrobbler`]s (for example one for Last.fm and one for Libre.fm, see
+/// [`Scrobbler::builder`]) and sends every call to all of them. Each call returns a result per service, so a failure
+/// on one service doesn't hide successes on the others and can be handled (e.g. queued) for that service alone.
+///
+/// # Usage
+/// ```ignore
+/// let mut multi = MultiScrobbler::new();
+/// multi.add("last.fm", last_fm_scrobbler);
+/// multi.add("libre.fm", libre_fm_scrobbler);
+///
+/// for (service, result) in multi.scrobble(&song) {
+///     if let Err(err) = result {
+///         eprintln!("Failed to scrobble to {}: {}", service, err);
+///     }
+/// }
+/// ```
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`Scrobbler::builder`]: struct.Scrobbler.html#method.builder
+#[derive(Default)]
+pub struct MultiScrobbler {
+    scrobblers: Vec<(String, Scrobbler)>,
+}
+
+impl MultiScrobbler {
+    /// Creates a `MultiScrobbler` with no services
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an authenticated [`Scrobbler`] under the given service name, returning the `Scrobbler` it replaces if
+    /// the name was already in use.
+    ///
+    /// [`Scrobbler`]: struct.Scrobbler.html
+    pub fn add(&mut self, name: &str, scrobbler: Scrobbler) -> Option<Scrobbler> {
+        if let Some(existing) = self.get_mut(name) {
+            return Some(std::mem::replace(existing, scrobbler));
+        }
+
+        self.scrobblers.push((name.to_owned(), scrobbler));
+        None
+    }
+
+    /// Removes the service with the given name, returning its `Scrobbler`
+    pub fn remove(&mut self, name: &str) -> Option<Scrobbler> {
+        let index = self.scrobblers.iter().position(|(n, _)| n == name)?;
+        Some(self.scrobblers.remove(index).1)
+    }
+
+    /// Gets the `Scrobbler` of the service with the given name
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Scrobbler> {
+        self.scrobblers.iter().find(|(n, _)| n == name).map(|(_, s)| s)
+    }
+
+    /// Gets a mutable reference to the `Scrobbler` of the service with the given name
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Scrobbler> {
+        self.scrobblers.iter_mut().find(|(n, _)| n == name).map(|(_, s)| s)
+    }
+
+    /// Names of the services, in the order they were added
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.scrobblers.iter().map(|(n, _)| n.as_str())
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.scrobblers.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.scrobblers.is_empty()
+    }
+
+    /// Registers the given [`Scrobble`]/track as "now playing" on every service. See [`Scrobbler::now_playing`].
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`Scrobbler::now_playing`]: struct.Scrobbler.html#method.now_playing
+    #[must_use]
+    pub fn now_playing(&self, scrobble: &Scrobble) -> ServiceResults<NowPlayingResponse> {
+        self.for_each(|scrobbler| scrobbler.now_playing(scrobble))
+    }
+
+    /// Registers a scrobble (play) of the given [`Scrobble`]/track on every service. See [`Scrobbler::scrobble`].
+    ///
+    /// If the scrobble has no timestamp, the current time is used for all services.
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    #[must_use]
+    pub fn scrobble(&self, scrobble: &Scrobble) -> ServiceResults<ScrobbleResponse> {
+        match timestamped(scrobble) {
+            Ok(scrobble) => self.for_each(|scrobbler| scrobbler.scrobble(&scrobble)),
+            Err(err) => self.fail_all(&err),
+        }
+    }
+
+    /// Registers a scrobble (play) of a collection of tracks on every service. See [`Scrobbler::scrobble_batch`].
+    ///
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    #[must_use]
+    pub fn scrobble_batch(&self, batch: &ScrobbleBatch) -> ServiceResults<BatchScrobbleResponse> {
+        self.for_each(|scrobbler| scrobbler.scrobble_batch(batch))
+    }
+
+    /// Registers a scrobble (play) of the given [`Scrobble`]/track on every service, adding it to the service's own
+    /// [`ScrobbleQueue`] if the submission to that service fails. See [`Scrobbler::scrobble_or_queue`].
+    ///
+    /// `queues` maps service names to their queues. Services without a queue behave as in
+    /// [`MultiScrobbler::scrobble`].
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+    /// [`Scrobbler::scrobble_or_queue`]: struct.Scrobbler.html#method.scrobble_or_queue
+    /// [`MultiScrobbler::scrobble`]: struct.MultiScrobbler.html#method.scrobble
+    pub fn scrobble_or_queue(
+        &self,
+        scrobble: &Scrobble,
+        queues: &mut HashMap<String, ScrobbleQueue>,
+    ) -> ServiceResults<ScrobbleResponse> {
+        let scrobble = match timestamped(scrobble) {
+            Ok(scrobble) => scrobble,
+            Err(err) => return self.fail_all(&err),
+        };
+
+        self.scrobblers
+            .iter()
+            .map(|(name, scrobbler)| {
+                let result = match queues.get_mut(name) {
+                    Some(queue) => scrobbler.scrobble_or_queue(&scrobble, queue),
+                    None => scrobbler.scrobble(&scrobble),
+                };
+                (name.clone(), result)
+            })
+            .collect()
+    }
+
+    /// Submits the scrobbles held in each service's [`ScrobbleQueue`] to that service. See
+    /// [`Scrobbler::drain_queue`]. Only services with a queue in `queues` are included in the results.
+    ///
+    /// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+    /// [`Scrobbler::drain_queue`]: struct.Scrobbler.html#method.drain_queue
+    pub fn drain_queues(&self, queues: &mut HashMap<String, ScrobbleQueue>) -> ServiceResults<QueueDrainReport> {
+        self.scrobblers
+            .iter()
+            .filter_map(|(name, scrobbler)| {
+                let queue = queues.get_mut(name)?;
+                Some((name.clone(), scrobbler.drain_queue(queue)))
+            })
+            .collect()
+    }
+
+    fn for_each<T, F>(&self, f: F) -> ServiceResults<T>
+    where
+        F: Fn(&Scrobbler) -> Result<T, ScrobblerError>,
+    {
+        self.scrobblers
+            .iter()
+            .map(|(name, scrobbler)| (name.clone(), f(scrobbler)))
+            .collect()
+    }
+
+    // Reports a failure to timestamp a scrobble for every service, each with its own copy of the typed error
+    fn fail_all<T>(&self, err: &SystemTimeError) -> ServiceResults<T> {
+        self.for_each(|_| Err(err.clone().into()))
+    }
+}
+
+// Fixes the timestamp of a scrobble before fanning out, so all services record the same play time
+fn timestamped(scrobble: &Scrobble) -> Result<Scrobble, SystemTimeError> {
+    let mut scrobble = scrobble.clone();
+    if scrobble.timestamp().is_none() {
+        scrobble.with_timestamp(UNIX_EPOCH.elapsed()?.as_secs());
+    }
+
+    Ok(scrobble)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::RecordingTransport;
+
+    const SCROBBLE_RESPONSE: &str = r#"
+        {
+            "scrobbles": [{
+                "artist": [ "0", "foo floyd and the fruit flies" ],
+                "album": [ "1", "old bananas" ],
+                "albumArtist": [ "0", "foo floyd"],
+                "track": [ "1", "old bananas"],
+                "timestamp": "2019-10-04 13:23:40"
+            }]
+        }
+    "#;
+
+    const BATCH_RESPONSE: &str = r##"
+        {
+            "scrobbles": {
+                "scrobble": [{
+                    "artist": { "corrected": "0", "#text": "foo floyd and the fruit flies" },
+                    "album": { "corrected": "0", "#text": "old bananas" },
+                    "albumArtist": { "corrected": "0", "#text": "" },
+                    "track": { "corrected": "0", "#text": "old bananas" },
+                    "timestamp": "1337"
+                }]
+            }
+        }
+    "##;
+
+    fn make_service(name: &str, multi: &mut MultiScrobbler) -> RecordingTransport {
+        let transport = RecordingTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport.clone());
+        scrobbler.authenticate_with_session_key(&format!("{name}-key"));
+        assert!(multi.add(name, scrobbler).is_none());
+
+        transport
+    }
+
+    #[test]
+    fn check_multi_scrobbler_services() {
+        let mut multi = MultiScrobbler::new();
+        assert!(multi.is_empty());
+
+        make_service("last.fm", &mut multi);
+        make_service("libre.fm", &mut multi);
+        assert_eq!(multi.names().collect::<Vec<_>>(), vec!["last.fm", "libre.fm"]);
+        assert_eq!(multi.get("libre.fm").unwrap().session_key(), Some("libre.fm-key"));
+
+        let replaced = multi.add("last.fm", Scrobbler::new("api_key", "api_secret"));
+        assert_eq!(replaced.unwrap().session_key(), Some("last.fm-key"));
+        assert_eq!(multi.len(), 2);
+
+        assert!(multi.remove("last.fm").is_some());
+        assert!(multi.remove("last.fm").is_none());
+        assert!(multi.get_mut("libre.fm").is_some());
+        assert_eq!(multi.len(), 1);
+    }
+
+    #[test]
+    fn check_multi_scrobbler_fan_out() {
+        let mut multi = MultiScrobbler::new();
+        let last_fm = make_service("last.fm", &mut multi);
+        let libre_fm = make_service("libre.fm", &mut multi);
+
+        last_fm.push_response(200, SCROBBLE_RESPONSE);
+        libre_fm.push_response(503, "Service Unavailable");
+
+        let scrobble = Scrobble::new("foo floyd and the fruit flies", "old bananas", "old bananas");
+        let results = multi.scrobble(&scrobble);
+        assert_eq!(results.len(), 2);
+        assert!(results["last.fm"].is_ok());
+        assert_eq!(results["libre.fm"].as_ref().unwrap_err().http_status(), Some(503));
+
+        // Both services are sent the same timestamp & their own session key
+        let (last_fm_req, libre_fm_req) = (last_fm.last_request().unwrap(), libre_fm.last_request().unwrap());
+        assert_eq!(last_fm_req.params["timestamp"], libre_fm_req.params["timestamp"]);
+        assert_eq!(last_fm_req.params["sk"], "last.fm-key");
+        assert_eq!(libre_fm_req.params["sk"], "libre.fm-key");
+
+        let results = multi.scrobble_batch(&ScrobbleBatch::new());
+        assert!(results.values().all(|r| matches!(r, Err(ScrobblerError::InvalidBatch(_)))));
+    }
+
+    #[test]
+    fn check_multi_scrobbler_timestamp_failure() {
+        let mut multi = MultiScrobbler::new();
+        make_service("last.fm", &mut multi);
+        make_service("libre.fm", &mut multi);
+
+        let err = UNIX_EPOCH.duration_since(UNIX_EPOCH + std::time::Duration::from_secs(1)).unwrap_err();
+        let results: ServiceResults<ScrobbleResponse> = multi.fail_all(&err);
+        assert_eq!(results.len(), 2);
+        assert!(results.values().all(|r| matches!(r, Err(ScrobblerError::Time(_)))));
+    }
+
+    #[test]
+    fn check_multi_scrobbler_queues() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut multi = MultiScrobbler::new();
+        let last_fm = make_service("last.fm", &mut multi);
+        let libre_fm = make_service("libre.fm", &mut multi);
+
+        let mut queues = HashMap::new();
+        queues.insert("last.fm".to_owned(), ScrobbleQueue::open(dir.path().join("last.fm")).unwrap());
+        queues.insert("libre.fm".to_owned(), ScrobbleQueue::open(dir.path().join("libre.fm")).unwrap());
+
+        last_fm.push_response(200, SCROBBLE_RESPONSE);
+        libre_fm.push_result(Err(ScrobblerError::Transport("offline".to_owned())));
+
+        let scrobble = Scrobble::new("foo floyd and the fruit flies", "old bananas", "old bananas");
+        let results = multi.scrobble_or_queue(&scrobble, &mut queues);
+        assert!(results["last.fm"].is_ok());
+        assert!(results["libre.fm"].is_err());
+        assert!(queues["last.fm"].is_empty());
+        assert_eq!(queues["libre.fm"].len(), 1);
+
+        queues.remove("last.fm");
+        libre_fm.push_response(200, BATCH_RESPONSE);
+        let reports = multi.drain_queues(&mut queues);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports["libre.fm"].as_ref().unwrap().submitted.len(), 1);
+        assert!(queues["libre.fm"].is_empty());
+    }
+}
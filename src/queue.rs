@@ -0,0 +1,385 @@
+// Persistent offline scrobble queue
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ScrobblerError;
+use crate::models::metadata::Scrobble;
+use crate::models::responses::IgnoreReason;
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+/// Last.fm does not accept scrobbles with timestamps more than 14 days in the past
+pub const MAX_SCROBBLE_AGE_SECS: u64 = 14 * 24 * 60 * 60;
+
+/// A durable, file-backed queue of [`Scrobble`]s waiting to be submitted to Last.fm.
+///
+/// Scrobbles that could not be submitted (typically because the device is offline) are added to the queue, and later
+/// re-submitted in timestamp order with [`Scrobbler::drain_queue`]. See also [`Scrobbler::scrobble_or_queue`].
+///
+/// The queue is stored as an append-only journal: every addition and removal is appended to the file as a single
+/// line and flushed to disk before returning, so queued scrobbles survive crashes & restarts. Entries are only removed
+/// once Last.fm has confirmed receiving them. The journal is compacted when the queue is opened or emptied.
+///
+/// # Usage
+/// ```ignore
+/// let mut queue = ScrobbleQueue::open("/var/lib/my-player/scrobbles.journal")?;
+/// let _ = scrobbler.scrobble_or_queue(&track, &mut queue);
+/// ...
+/// // Once back online
+/// let report = scrobbler.drain_queue(&mut queue)?;
+/// ```
+///
+/// [`Scrobble`]: struct.Scrobble.html
+/// [`Scrobbler::drain_queue`]: struct.Scrobbler.html#method.drain_queue
+/// [`Scrobbler::scrobble_or_queue`]: struct.Scrobbler.html#method.scrobble_or_queue
+#[derive(Debug)]
+pub struct ScrobbleQueue {
+    path: PathBuf,
+    journal: File,
+    entries: BTreeMap<u64, Scrobble>,
+    next_id: u64,
+}
+
+impl ScrobbleQueue {
+    /// Opens the queue stored at `path`, creating an empty queue if the file does not exist.
+    ///
+    /// # Errors
+    /// Returns an error if the journal exists but can't be read, or can't be compacted.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        let (entries, next_id) = Self::replay(&path)?;
+
+        let journal = Self::write_journal(&path, &entries)?;
+
+        Ok(Self {
+            path,
+            journal,
+            entries,
+            next_id,
+        })
+    }
+
+    /// Adds a scrobble to the queue, returning its queue ID.
+    ///
+    /// Scrobbles without a timestamp are given the current time, so they are recorded at the time they were played
+    /// rather than the time they are eventually submitted.
+    ///
+    /// # Errors
+    /// Returns an error if the scrobble can't be appended to the journal.
+    pub fn push(&mut self, scrobble: &Scrobble) -> Result<u64> {
+        let mut scrobble = scrobble.clone();
+        if scrobble.timestamp().is_none() {
+            scrobble.with_timestamp(UNIX_EPOCH.elapsed()?.as_secs());
+        }
+
+        let id = self.next_id;
+        self.append(&JournalEntry::Add {
+            id,
+            scrobble: Box::new(QueuedScrobble::from(&scrobble)),
+        })?;
+
+        self.entries.insert(id, scrobble);
+        self.next_id += 1;
+        Ok(id)
+    }
+
+    /// Removes the scrobbles with the given queue IDs. Unknown IDs are ignored.
+    ///
+    /// # Errors
+    /// Returns an error if the removal can't be appended to the journal.
+    pub fn remove(&mut self, ids: &[u64]) -> Result<()> {
+        for id in ids {
+            if self.entries.contains_key(id) {
+                self.append(&JournalEntry::Remove { id: *id })?;
+                self.entries.remove(id);
+            }
+        }
+
+        if self.entries.is_empty() {
+            self.compact()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the queued scrobbles, paired with their queue IDs, in timestamp order
+    #[must_use]
+    pub fn pending(&self) -> Vec<(u64, &Scrobble)> {
+        let mut pending: Vec<(u64, &Scrobble)> = self.entries.iter().map(|(id, scrobble)| (*id, scrobble)).collect();
+        pending.sort_by_key(|(id, scrobble)| (scrobble.timestamp(), *id));
+        pending
+    }
+
+    /// Returns the number of queued scrobbles
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no scrobbles are queued
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the path of the queue's journal file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rewrites the journal so it only contains the currently queued scrobbles
+    ///
+    /// # Errors
+    /// Returns an error if the journal can't be rewritten.
+    pub fn compact(&mut self) -> Result<()> {
+        self.journal = Self::write_journal(&self.path, &self.entries)?;
+        Ok(())
+    }
+
+    fn append(&mut self, entry: &JournalEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.journal.write_all(&line)?;
+        self.journal.sync_data()?;
+        Ok(())
+    }
+
+    fn replay(path: &Path) -> Result<(BTreeMap<u64, Scrobble>, u64)> {
+        let mut entries = BTreeMap::new();
+        let mut next_id = 0;
+
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok((entries, next_id)),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut lines = BufReader::new(file).lines().peekable();
+        while let Some(line) = lines.next() {
+            let line = line?;
+            let entry: JournalEntry = match serde_json::from_str(&line) {
+                Ok(entry) => entry,
+                // A crash while appending can leave a partially written final line; that entry was never confirmed
+                // as queued, so it is safe to drop.
+                Err(_) if lines.peek().is_none() => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            match entry {
+                JournalEntry::Add { id, scrobble } => {
+                    entries.insert(id, Scrobble::from(*scrobble));
+                    next_id = next_id.max(id + 1);
+                }
+                JournalEntry::Remove { id } => {
+                    entries.remove(&id);
+                    next_id = next_id.max(id + 1);
+                }
+            }
+        }
+
+        Ok((entries, next_id))
+    }
+
+    // Atomically replaces the journal with one holding only `entries`, returning it opened for appending
+    fn write_journal(path: &Path, entries: &BTreeMap<u64, Scrobble>) -> Result<File> {
+        let mut file_name = path.file_name().unwrap_or_default().to_owned();
+        file_name.push(".tmp");
+        let temp_path = path.with_file_name(file_name);
+
+        let mut temp = File::create(&temp_path)?;
+        for (id, scrobble) in entries {
+            let mut line = serde_json::to_vec(&JournalEntry::Add {
+                id: *id,
+                scrobble: Box::new(QueuedScrobble::from(scrobble)),
+            })?;
+            line.push(b'\n');
+            temp.write_all(&line)?;
+        }
+        temp.sync_all()?;
+        drop(temp);
+
+        fs::rename(&temp_path, path)?;
+
+        Ok(OpenOptions::new().append(true).open(path)?)
+    }
+}
+
+/// Report of a [`Scrobbler::drain_queue`] run.
+///
+/// [`Scrobbler::drain_queue`]: struct.Scrobbler.html#method.drain_queue
+#[derive(Debug, Default)]
+pub struct QueueDrainReport {
+    /// Scrobbles accepted by Last.fm and removed from the queue
+    pub submitted: Vec<Scrobble>,
+    /// Scrobbles removed from the queue without being recorded, with the reason they were dropped
+    pub dropped: Vec<(Scrobble, DropReason)>,
+    /// Errors from submissions that failed; the affected scrobbles remain queued
+    pub errors: Vec<ScrobblerError>,
+    /// Number of scrobbles still queued
+    pub remaining: usize,
+}
+
+/// Reason a queued scrobble was removed from a [`ScrobbleQueue`] without being recorded by Last.fm
+///
+/// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DropReason {
+    /// The scrobble is older than Last.fm's 14-day acceptance window, so it can never be accepted
+    Expired { age_secs: u64 },
+    /// Last.fm received the scrobble but ignored it, with the reason and message of its `ignoredMessage`
+    Ignored { reason: IgnoreReason, message: String },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum JournalEntry {
+    Add { id: u64, scrobble: Box<QueuedScrobble> },
+    Remove { id: u64 },
+}
+
+#[derive(Serialize, Deserialize)]
+struct QueuedScrobble {
+    artist: String,
+    track: String,
+    album: String,
+    timestamp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    album_artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    track_number: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mbid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    context: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stream_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chosen_by_user: Option<bool>,
+}
+
+impl From<&Scrobble> for QueuedScrobble {
+    fn from(scrobble: &Scrobble) -> Self {
+        Self {
+            artist: scrobble.artist().to_owned(),
+            track: scrobble.track().to_owned(),
+            album: scrobble.album().to_owned(),
+            timestamp: scrobble.timestamp(),
+            album_artist: scrobble.album_artist().map(str::to_owned),
+            duration: scrobble.duration(),
+            track_number: scrobble.track_number(),
+            mbid: scrobble.mbid().map(str::to_owned),
+            context: scrobble.context().map(str::to_owned),
+            stream_id: scrobble.stream_id().map(str::to_owned),
+            chosen_by_user: scrobble.chosen_by_user(),
+        }
+    }
+}
+
+impl From<QueuedScrobble> for Scrobble {
+    fn from(queued: QueuedScrobble) -> Self {
+        let mut scrobble = Scrobble::new(&queued.artist, &queued.track, &queued.album);
+        if let Some(timestamp) = queued.timestamp {
+            scrobble.with_timestamp(timestamp);
+        }
+        if let Some(ref album_artist) = queued.album_artist {
+            scrobble.with_album_artist(album_artist);
+        }
+        if let Some(duration) = queued.duration {
+            scrobble.with_duration(duration);
+        }
+        if let Some(track_number) = queued.track_number {
+            scrobble.with_track_number(track_number);
+        }
+        if let Some(ref mbid) = queued.mbid {
+            scrobble.with_mbid(mbid);
+        }
+        if let Some(ref context) = queued.context {
+            scrobble.with_context(context);
+        }
+        if let Some(ref stream_id) = queued.stream_id {
+            scrobble.with_stream_id(stream_id);
+        }
+        if let Some(chosen_by_user) = queued.chosen_by_user {
+            scrobble.with_chosen_by_user(chosen_by_user);
+        }
+        scrobble
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_scrobble(track: &str, timestamp: u64) -> Scrobble {
+        let mut scrobble = Scrobble::new("foo floyd and the fruit flies", track, "old bananas");
+        scrobble.with_timestamp(timestamp);
+        scrobble
+    }
+
+    #[test]
+    fn check_queue_persists_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.journal");
+
+        let mut queue = ScrobbleQueue::open(&path).unwrap();
+        assert!(queue.is_empty());
+
+        let mut detailed = make_scrobble("later", 200);
+        detailed.with_album_artist("foo floyd").with_duration(180).with_chosen_by_user(true);
+        let later = queue.push(&detailed).unwrap();
+        let earlier = queue.push(&make_scrobble("earlier", 100)).unwrap();
+        let removed = queue.push(&make_scrobble("removed", 150)).unwrap();
+        queue.remove(&[removed]).unwrap();
+        drop(queue);
+
+        let queue = ScrobbleQueue::open(&path).unwrap();
+        assert_eq!(queue.len(), 2);
+        let pending = queue.pending();
+        assert_eq!(pending[0], (earlier, &make_scrobble("earlier", 100)));
+        assert_eq!(pending[1], (later, &detailed));
+    }
+
+    #[test]
+    fn check_queue_assigns_timestamp_and_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.journal");
+
+        let mut queue = ScrobbleQueue::open(&path).unwrap();
+        let first = queue.push(&Scrobble::new("Artist", "Track", "Album")).unwrap();
+        assert!(queue.pending()[0].1.timestamp().is_some());
+        queue.remove(&[first]).unwrap();
+        assert!(queue.is_empty());
+        drop(queue);
+
+        let mut queue = ScrobbleQueue::open(&path).unwrap();
+        let second = queue.push(&make_scrobble("Track", 100)).unwrap();
+        let third = queue.push(&make_scrobble("Track", 100)).unwrap();
+        assert_ne!(second, third);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+    }
+
+    #[test]
+    fn check_queue_ignores_torn_final_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.journal");
+
+        let mut queue = ScrobbleQueue::open(&path).unwrap();
+        queue.push(&make_scrobble("Track", 100)).unwrap();
+        drop(queue);
+
+        let mut journal = OpenOptions::new().append(true).open(&path).unwrap();
+        journal.write_all(br#"{"add":{"id":1,"scrob"#).unwrap();
+        drop(journal);
+
+        let queue = ScrobbleQueue::open(&path).unwrap();
+        assert_eq!(queue.len(), 1);
+    }
+}
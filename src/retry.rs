@@ -0,0 +1,324 @@
+// Retrying of API requests that fail with transient errors
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::clock::{Clock, Sleeper, SystemClock, ThreadSleeper};
+use crate::error::ScrobblerError;
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+// Last.fm API errors reporting a temporary problem on Last.fm's side
+const SERVICE_OFFLINE: u32 = 11;
+const TEMPORARILY_UNAVAILABLE: u32 = 16;
+const RATE_LIMIT_EXCEEDED: u32 = 29;
+
+// HTTP statuses sent when a request was refused without being processed
+const TOO_MANY_REQUESTS: u16 = 429;
+const SERVICE_UNAVAILABLE: u16 = 503;
+
+/// How a [`Scrobbler`] retries API requests that fail with a transient error, set with
+/// [`ScrobblerBuilder::retry_policy`].
+///
+/// Retried failures are Last.fm API errors 11 (service offline), 16 (temporarily unavailable) and 29 (rate limit
+/// exceeded), and HTTP 429 & 503 statuses, which refuse the request without processing it. Other 5xx statuses and
+/// transport failures (lost connections, timeouts...) are only retried for read requests, since a write such as a
+/// scrobble may have been processed before the server or connection failed. Other errors are returned immediately.
+///
+/// The n<sup>th</sup> retry waits `base_delay * 2^(n-1)`, shortened by a random fraction of up to `jitter` so clients
+/// that failed together don't retry together. No retry is made once `max_attempts` requests have been sent, or if its
+/// delay would end after `max_elapsed` has passed since the first request; the last error is returned.
+///
+/// # Usage
+/// ```ignore
+/// let mut policy = RetryPolicy::new();
+/// policy.with_max_attempts(5).with_base_delay(Duration::from_millis(500));
+///
+/// let scrobbler = Scrobbler::builder(api_key, api_secret)
+///     .retry_policy(policy)
+///     .on_retry(|event| eprintln!("Retrying {} in {:?}: {}", event.method, event.delay, event.error))
+///     .build();
+/// ```
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`ScrobblerBuilder::retry_policy`]: struct.ScrobblerBuilder.html#method.retry_policy
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    jitter: f64,
+    max_elapsed: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_secs(1),
+            jitter: 0.5,
+            max_elapsed: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy making up to 3 attempts, with a 1 second base delay, a jitter of 0.5 and at most 30 seconds
+    /// of retrying
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of requests sent, including the first one
+    pub fn with_max_attempts(&mut self, max_attempts: u32) -> &mut Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry, doubled for each later retry
+    pub fn with_base_delay(&mut self, base_delay: Duration) -> &mut Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Sets the largest fraction (between 0 and 1) each delay is randomly shortened by. 0 disables jitter.
+    pub fn with_jitter(&mut self, jitter: f64) -> &mut Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the longest time after the first request that a retry may be made
+    pub fn with_max_elapsed(&mut self, max_elapsed: Duration) -> &mut Self {
+        self.max_elapsed = max_elapsed;
+        self
+    }
+
+    #[must_use]
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    #[must_use]
+    pub fn base_delay(&self) -> Duration {
+        self.base_delay
+    }
+
+    #[must_use]
+    pub fn jitter(&self) -> f64 {
+        self.jitter
+    }
+
+    #[must_use]
+    pub fn max_elapsed(&self) -> Duration {
+        self.max_elapsed
+    }
+
+    /// Returns the delay before the given (1-based) retry, before jitter is applied
+    #[must_use]
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(retry.saturating_sub(1));
+        self.base_delay.saturating_mul(factor)
+    }
+}
+
+/// A retry about to be made by a [`Scrobbler`] following a [`RetryPolicy`], passed to the callback set with
+/// [`ScrobblerBuilder::on_retry`]
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`RetryPolicy`]: struct.RetryPolicy.html
+/// [`ScrobblerBuilder::on_retry`]: struct.ScrobblerBuilder.html#method.on_retry
+#[derive(Debug)]
+pub struct RetryEvent<'a> {
+    /// The API method of the failed request, e.g. `track.scrobble`
+    pub method: &'a str,
+    /// Number of the attempt that failed, starting at 1
+    pub attempt: u32,
+    /// The error the attempt failed with
+    pub error: &'a ScrobblerError,
+    /// How long the client waits before the next attempt
+    pub delay: Duration,
+}
+
+pub(crate) type RetryCallback = Box<dyn Fn(&RetryEvent<'_>) + Send + Sync>;
+
+// Sends requests following a retry policy. Without a policy, requests are sent once.
+pub(crate) struct Retrier {
+    pub(crate) policy: Option<RetryPolicy>,
+    pub(crate) clock: Box<dyn Clock + Send + Sync>,
+    pub(crate) sleeper: Box<dyn Sleeper + Send + Sync>,
+    pub(crate) on_retry: Option<RetryCallback>,
+}
+
+impl Default for Retrier {
+    fn default() -> Self {
+        Self {
+            policy: None,
+            clock: Box::new(SystemClock),
+            sleeper: Box::new(ThreadSleeper),
+            on_retry: None,
+        }
+    }
+}
+
+impl Retrier {
+    // Runs the request until it succeeds, fails with an error that can't be retried, or the policy gives up.
+    // `idempotent` requests can be safely repeated after server and transport failures.
+    pub(crate) fn run<T, F>(&self, method: &str, idempotent: bool, mut request: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        let Some(policy) = self.policy else {
+            return request();
+        };
+
+        let started = self.clock.now();
+        let mut attempt = 1;
+        loop {
+            let error = match request() {
+                Err(error) if attempt < policy.max_attempts && is_retryable(&error, idempotent) => error,
+                result => return result,
+            };
+
+            let delay = jittered(policy.delay(attempt), policy.jitter);
+            let elapsed = self.clock.now().duration_since(started).unwrap_or_default();
+            if elapsed.saturating_add(delay) > policy.max_elapsed {
+                return Err(error);
+            }
+
+            if let Some(ref on_retry) = self.on_retry {
+                on_retry(&RetryEvent {
+                    method,
+                    attempt,
+                    error: &error,
+                    delay,
+                });
+            }
+            self.sleeper.sleep(delay);
+            attempt += 1;
+        }
+    }
+}
+
+fn is_retryable(error: &ScrobblerError, idempotent: bool) -> bool {
+    match *error {
+        ScrobblerError::Api { code, .. } => {
+            matches!(code, SERVICE_OFFLINE | TEMPORARILY_UNAVAILABLE | RATE_LIMIT_EXCEEDED)
+        }
+        ScrobblerError::HttpStatus { status, .. } => {
+            matches!(status, TOO_MANY_REQUESTS | SERVICE_UNAVAILABLE) || (idempotent && (500..600).contains(&status))
+        }
+        ScrobblerError::Transport(_) => idempotent,
+        _ => false,
+    }
+}
+
+// Shortens the delay by a random fraction of up to `jitter`
+fn jittered(delay: Duration, jitter: f64) -> Duration {
+    if jitter <= 0.0 {
+        return delay;
+    }
+
+    // RandomState is seeded randomly per instance, which is random enough to spread out retries
+    let random = RandomState::new().build_hasher().finish() >> 32;
+    let fraction = f64::from(u32::try_from(random).unwrap_or(u32::MAX)) / f64::from(u32::MAX);
+
+    delay.mul_f64(1.0 - jitter * fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::time::UNIX_EPOCH;
+
+    // A retrier on a fake clock, advanced by its sleeper, with the delays slept
+    fn make_retrier(policy: RetryPolicy) -> (Retrier, Arc<Mutex<Vec<Duration>>>) {
+        let slept = Arc::new(Mutex::new(Vec::new()));
+        let (clock_slept, sleeper_slept) = (Arc::clone(&slept), Arc::clone(&slept));
+
+        let retrier = Retrier {
+            policy: Some(policy),
+            clock: Box::new(move || UNIX_EPOCH + clock_slept.lock().unwrap().iter().sum::<Duration>()),
+            sleeper: Box::new(move |delay| sleeper_slept.lock().unwrap().push(delay)),
+            on_retry: None,
+        };
+        (retrier, slept)
+    }
+
+    fn api_error(code: u32) -> ScrobblerError {
+        ScrobblerError::Api {
+            code,
+            message: "error".to_owned(),
+        }
+    }
+
+    #[test]
+    fn check_retry_backoff() {
+        let mut policy = RetryPolicy::new();
+        policy.with_max_attempts(4).with_jitter(0.0);
+        assert_eq!(policy.delay(3), Duration::from_secs(4));
+
+        let (retrier, slept) = make_retrier(policy);
+        let mut attempts = 0;
+        let result: Result<()> = retrier.run("track.scrobble", false, || {
+            attempts += 1;
+            Err(api_error(16))
+        });
+        assert_eq!(result.unwrap_err().api_error_code(), Some(16));
+        assert_eq!(attempts, 4);
+        assert_eq!(*slept.lock().unwrap(), vec![1, 2, 4].into_iter().map(Duration::from_secs).collect::<Vec<_>>());
+
+        // Retries stop once the next one would be too late
+        policy.with_max_elapsed(Duration::from_secs(5));
+        let (retrier, slept) = make_retrier(policy);
+        let mut attempts = 0;
+        let result = retrier.run("track.scrobble", false, || {
+            attempts += 1;
+            if attempts < 4 {
+                Err(ScrobblerError::HttpStatus { status: 503, body: String::new() })
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert!(result.is_err());
+        assert_eq!(slept.lock().unwrap().len(), 2);
+
+        let mut policy = RetryPolicy::new();
+        policy.with_base_delay(Duration::from_secs(10)).with_jitter(0.5);
+        let (retrier, slept) = make_retrier(policy);
+        assert_eq!(retrier.run("user.getRecentTracks", true, || Ok(1)).unwrap(), 1);
+        assert!(slept.lock().unwrap().is_empty());
+
+        let mut attempts = 0;
+        let result = retrier.run("user.getRecentTracks", true, || {
+            attempts += 1;
+            if attempts == 1 {
+                Err(ScrobblerError::Transport("Connection reset".to_owned()))
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result.unwrap(), 2);
+        let delay = slept.lock().unwrap()[0];
+        assert!(delay >= Duration::from_secs(5) && delay <= Duration::from_secs(10));
+    }
+
+    #[test]
+    fn check_retryable_errors() {
+        for code in [11, 16, 29] {
+            assert!(is_retryable(&api_error(code), false));
+        }
+        assert!(!is_retryable(&api_error(9), true));
+        assert!(is_retryable(&ScrobblerError::HttpStatus { status: 503, body: String::new() }, false));
+        assert!(is_retryable(&ScrobblerError::HttpStatus { status: 429, body: String::new() }, false));
+        assert!(is_retryable(&ScrobblerError::HttpStatus { status: 502, body: String::new() }, true));
+        assert!(!is_retryable(&ScrobblerError::HttpStatus { status: 404, body: String::new() }, true));
+
+        // Writes may have been processed before a server or transport failure
+        assert!(!is_retryable(&ScrobblerError::HttpStatus { status: 502, body: String::new() }, false));
+        let transport_error = ScrobblerError::Transport("Connection reset".to_owned());
+        assert!(is_retryable(&transport_error, true));
+        assert!(!is_retryable(&transport_error, false));
+        assert!(!is_retryable(&ScrobblerError::Decode("bad json".to_owned()), true));
+    }
+}
@@ -1,16 +1,32 @@
 use crate::client::LastFm;
+use crate::clock::{Clock, Sleeper};
+use crate::dedup::{DeduplicatedBatchResponse, DuplicateFilter};
+use crate::endpoints::ApiEndpoints;
 use crate::error::ScrobblerError;
+use crate::history::{RecentTracks, RecentTracksQuery};
+use crate::lookup::LookupOptions;
 use crate::models::metadata::{Scrobble, ScrobbleBatch};
 use crate::models::responses::{
-    BatchScrobbleResponse, NowPlayingResponse, ScrobbleResponse, SessionResponse,
+    AlbumInfo, ArtistCorrection, ArtistInfo, BatchScrobbleResponse, ChunkedScrobbleResponse, FailedChunk,
+    LoveResponse, NowPlayingResponse, RecentTracksPage, ScrobbleResponse, SessionResponse, TrackCorrection, TrackInfo,
 };
+use crate::queue::{DropReason, QueueDrainReport, ScrobbleQueue, MAX_SCROBBLE_AGE_SECS};
+use crate::retry::{Retrier, RetryEvent, RetryPolicy};
+use crate::session::{Session, SessionStore};
+use crate::transport::{Transport, UreqTransport};
 
 use std::collections::HashMap;
 use std::result;
-use std::time::UNIX_EPOCH;
+use std::time::{Duration, UNIX_EPOCH};
 
 type Result<T> = result::Result<T, ScrobblerError>;
 
+// Maximum number of scrobbles the Last.fm API accepts in a single batch request
+const MAX_BATCH_SIZE: usize = 50;
+
+// Last.fm API error returned by auth.getSession while the user has not yet approved the token
+const TOKEN_NOT_AUTHORIZED: u32 = 14;
+
 /// A Last.fm Scrobbler client. Submits song play information to Last.fm.
 /// 
 /// This is a client for the Scrobble and Now Playing endpoints on the Last.fm API. It handles API client and user 
@@ -33,6 +49,7 @@ type Result<T> = result::Result<T, ScrobblerError>;
 /// ```
 pub struct Scrobbler {
     client: LastFm,
+    session: Option<Session>,
 }
 
 impl Scrobbler {
@@ -57,10 +74,78 @@ impl Scrobbler {
     /// [API Authentication documentation](https://www.last.fm/api/authentication)
     /// 
     /// [API Account Registration form](https://www.last.fm/api/account/create)
+    #[must_use]
     pub fn new(api_key: &str, api_secret: &str) -> Self {
         let client = LastFm::new(api_key, api_secret);
 
-        Self { client }
+        Self {
+            client,
+            session: None,
+        }
+    }
+
+    /// Creates a new Scrobbler instance that sends its API requests through the given [`Transport`].
+    ///
+    /// [`Scrobbler::new`] uses the default, `ureq` based, [`UreqTransport`]. A custom transport lets applications
+    /// route requests through their own HTTP stack, or test code that scrobbles without network access by using a
+    /// [`RecordingTransport`].
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let transport = RecordingTransport::new();
+    /// transport.push_response(200, r#"{"nowplaying": { ... }}"#);
+    ///
+    /// let mut scrobbler = Scrobbler::with_transport(api_key, api_secret, transport.clone());
+    /// scrobbler.authenticate_with_session_key("session-key");
+    /// scrobbler.now_playing(&song)?;
+    ///
+    /// assert_eq!(transport.requests().len(), 1);
+    /// ```
+    ///
+    /// [`Transport`]: trait.Transport.html
+    /// [`Scrobbler::new`]: struct.Scrobbler.html#method.new
+    /// [`UreqTransport`]: struct.UreqTransport.html
+    /// [`RecordingTransport`]: struct.RecordingTransport.html
+    pub fn with_transport<T: Transport + 'static>(api_key: &str, api_secret: &str, transport: T) -> Self {
+        Self::builder(api_key, api_secret).transport(transport).build()
+    }
+
+    /// Returns a [`ScrobblerBuilder`] for configuring a Scrobbler with the given Last.fm API Key and API Secret.
+    ///
+    /// The builder sets the scrobbling service to talk to (Last.fm by default, see [`ApiEndpoints`]) and the HTTP
+    /// [`Transport`] used to reach it. API credentials must be obtained from the chosen service.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let mut scrobbler = Scrobbler::builder(api_key, api_secret)
+    ///     .endpoints(ApiEndpoints::libre_fm())
+    ///     .build();
+    ///
+    /// let token = scrobbler.get_auth_token()?;
+    /// // Directs the user to libre.fm to approve the token
+    /// println!("{}", scrobbler.authorization_url(&token));
+    /// ```
+    ///
+    /// [`ScrobblerBuilder`]: struct.ScrobblerBuilder.html
+    /// [`ApiEndpoints`]: struct.ApiEndpoints.html
+    /// [`Transport`]: trait.Transport.html
+    #[must_use]
+    pub fn builder(api_key: &str, api_secret: &str) -> ScrobblerBuilder {
+        ScrobblerBuilder {
+            api_key: api_key.to_owned(),
+            api_secret: api_secret.to_owned(),
+            endpoints: ApiEndpoints::default(),
+            transport: None,
+            retrier: Retrier::default(),
+        }
+    }
+
+    /// Gets the [`ApiEndpoints`] of the service the client talks to.
+    ///
+    /// [`ApiEndpoints`]: struct.ApiEndpoints.html
+    #[must_use]
+    pub fn endpoints(&self) -> &ApiEndpoints {
+        self.client.endpoints()
     }
 
     /// Authenticates a Last.fm user with the given username and password. 
@@ -79,6 +164,10 @@ impl Scrobbler {
     /// ...
     /// ```
     /// 
+    /// # Errors
+    /// Returns an error if the request fails, or a `ScrobblerError::Api` error if Last.fm rejects the username and
+    /// password.
+    /// 
     /// # Last.fm API Documentation
     /// [Last.fm Mobile Auth Flow Documentation](https://www.last.fm/api/mobileauth)
     pub fn authenticate_with_password(
@@ -87,7 +176,8 @@ impl Scrobbler {
         password: &str,
     ) -> Result<SessionResponse> {
         self.client.set_user_credentials(username, password);
-        Ok(self.client.authenticate_with_password()?)
+        let result = self.client.authenticate_with_password();
+        self.update_session(result)
     }
 
     /// Authenticates a Last.fm user with an authentication token. This method supports both the 'Web' and 'Desktop'
@@ -101,13 +191,130 @@ impl Scrobbler {
     /// let response = scrobbler.authenticate_with_token(auth_token);
     /// ```
     /// 
+    /// # Errors
+    /// Returns an error if the request fails, or a `ScrobblerError::Api` error if the token is invalid, has expired or
+    /// has not been authorized by the user.
+    /// 
     /// # Last.fm API Documentation
     /// [Last.fm Web Auth Flow Documentation](https://www.last.fm/api/webauth)
     /// 
     /// [Last.fm Desktop Auth Flow Documentation](https://www.last.fm/api/desktopauth)
     pub fn authenticate_with_token(&mut self, token: &str) -> Result<SessionResponse> {
         self.client.set_user_token(token);
-        Ok(self.client.authenticate_with_token()?)
+        let result = self.client.authenticate_with_token();
+        self.update_session(result)
+    }
+
+    /// Fetches a new, unauthorized authentication token from the Last.fm API.
+    /// 
+    /// This is the first step of the 'Desktop' auth flow. The user must approve the token in their browser (see
+    /// [`Scrobbler::authorization_url`]) before it can be exchanged for a session via
+    /// [`authenticate_with_token`] or [`authenticate_with_token_polling`]. Tokens are valid for 60 minutes.
+    /// 
+    /// # Usage
+    /// ```ignore
+    /// let scrobbler = Scrobbler::new(...);
+    /// let token = scrobbler.get_auth_token()?;
+    /// println!("Please approve access at {}", scrobbler.authorization_url(&token));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns an error if the request fails or Last.fm returns an error response (for example for an invalid API key).
+    /// 
+    /// # Last.fm API Documentation
+    /// [auth.getToken API Method Documentation](https://www.last.fm/api/show/auth.getToken)
+    /// 
+    /// [Last.fm Desktop Auth Flow Documentation](https://www.last.fm/api/desktopauth)
+    /// 
+    /// [`Scrobbler::authorization_url`]: struct.Scrobbler.html#method.authorization_url
+    /// [`authenticate_with_token`]: struct.Scrobbler.html#method.authenticate_with_token
+    /// [`authenticate_with_token_polling`]: struct.Scrobbler.html#method.authenticate_with_token_polling
+    pub fn get_auth_token(&self) -> Result<String> {
+        self.client.get_token()
+    }
+
+    /// Builds the Last.fm URL the user must visit to approve the given authentication token ('Desktop' auth flow).
+    /// 
+    /// Tokens are fetched with [`Scrobbler::get_auth_token`].
+    /// 
+    /// [`Scrobbler::get_auth_token`]: struct.Scrobbler.html#method.get_auth_token
+    #[must_use]
+    pub fn authorization_url(&self, token: &str) -> String {
+        self.client.auth_url(token)
+    }
+
+    /// Builds the Last.fm URL the user must visit to grant access to your application ('Web' auth flow).
+    /// 
+    /// Once the user approves access, Last.fm redirects them to `callback_url` with a `token` query parameter,
+    /// which can be passed to [`authenticate_with_token`].
+    /// 
+    /// # Last.fm API Documentation
+    /// [Last.fm Web Auth Flow Documentation](https://www.last.fm/api/webauth)
+    /// 
+    /// [`authenticate_with_token`]: struct.Scrobbler.html#method.authenticate_with_token
+    #[must_use]
+    pub fn authorization_url_with_callback(&self, callback_url: &str) -> String {
+        self.client.auth_url_with_callback(callback_url)
+    }
+
+    /// Authenticates a Last.fm user with an authentication token, retrying until the user approves the token.
+    /// 
+    /// Completes the 'Desktop' auth flow: after fetching a token with [`Scrobbler::get_auth_token`] and sending the
+    /// user to [`Scrobbler::authorization_url`], call this method to wait for their approval. `auth.getSession` is
+    /// tried every `poll_interval` while the token has not been authorized yet. Waiting uses the [`Clock`] and
+    /// [`Sleeper`] set with [`ScrobblerBuilder::retry_clock`] and [`ScrobblerBuilder::retry_sleeper`].
+    /// 
+    /// # Usage
+    /// ```ignore
+    /// let mut scrobbler = Scrobbler::new(...);
+    /// let token = scrobbler.get_auth_token()?;
+    /// open_in_browser(&scrobbler.authorization_url(&token));
+    /// let response = scrobbler.authenticate_with_token_polling(
+    ///     &token,
+    ///     Duration::from_secs(5),
+    ///     Duration::from_secs(600),
+    /// )?;
+    /// ```
+    /// 
+    /// # Errors
+    /// Errors other than the token not being authorized yet (including the token expiring) are returned immediately. A
+    /// `ScrobblerError::NotAuthenticated` error is returned if the token is not approved within `timeout`.
+    /// 
+    /// # Last.fm API Documentation
+    /// [Last.fm Desktop Auth Flow Documentation](https://www.last.fm/api/desktopauth)
+    /// 
+    /// [`Scrobbler::get_auth_token`]: struct.Scrobbler.html#method.get_auth_token
+    /// [`Scrobbler::authorization_url`]: struct.Scrobbler.html#method.authorization_url
+    /// [`Clock`]: trait.Clock.html
+    /// [`Sleeper`]: trait.Sleeper.html
+    /// [`ScrobblerBuilder::retry_clock`]: struct.ScrobblerBuilder.html#method.retry_clock
+    /// [`ScrobblerBuilder::retry_sleeper`]: struct.ScrobblerBuilder.html#method.retry_sleeper
+    pub fn authenticate_with_token_polling(
+        &mut self,
+        token: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<SessionResponse> {
+        let started = self.client.clock().now();
+        self.client.set_user_token(token);
+
+        loop {
+            match self.client.authenticate_with_token() {
+                Err(ScrobblerError::Api { code: TOKEN_NOT_AUTHORIZED, .. }) => {}
+                result => return self.update_session(result),
+            }
+
+            let elapsed = self.client.clock().now().duration_since(started).unwrap_or_default();
+            if elapsed + poll_interval > timeout {
+                // The unapproved token is dropped along with the session, so no later request uses it
+                self.client.clear_user_credentials();
+                return self.update_session(Err(ScrobblerError::NotAuthenticated(
+                    "Timed out waiting for the authentication token to be authorized".to_owned(),
+                )));
+            }
+
+            self.client.sleeper().sleep(poll_interval);
+        }
     }
 
     /// Authenticates a Last.fm user with a session key. 
@@ -139,7 +346,75 @@ impl Scrobbler {
     /// [`authenticate_with_token`]: struct.Scrobbler.html#method.authenticate_with_token
     /// [`Scrobbler::session_key`]: struct.Scrobbler.html#method.session_key
     pub fn authenticate_with_session_key(&mut self, session_key: &str) {
-        self.client.authenticate_with_session_key(session_key)
+        self.authenticate_with_session(&Session::from_key(session_key));
+    }
+
+    /// Authenticates a Last.fm user with a previously saved [`Session`].
+    /// 
+    /// Behaves like [`authenticate_with_session_key`], but also restores the username and subscriber status that
+    /// were reported when the session was first created. The current session can be exported via
+    /// [`Scrobbler::session`].
+    /// 
+    /// # Usage
+    /// ```ignore
+    /// let session: Session = serde_json::from_str(&saved_session_json)?;
+    /// let mut scrobbler = Scrobbler::new(...);
+    /// scrobbler.authenticate_with_session(&session);
+    /// ```
+    /// 
+    /// [`Session`]: struct.Session.html
+    /// [`authenticate_with_session_key`]: struct.Scrobbler.html#method.authenticate_with_session_key
+    /// [`Scrobbler::session`]: struct.Scrobbler.html#method.session
+    pub fn authenticate_with_session(&mut self, session: &Session) {
+        self.client.authenticate_with_session_key(&session.key);
+        self.session = Some(session.clone());
+    }
+
+    /// Authenticates with the [`Session`] held in the given [`SessionStore`], if there is one.
+    /// 
+    /// Returns `Ok(true)` if a stored session was found and used, `Ok(false)` if the store holds no session (in which
+    /// case the `Scrobbler` is left untouched and another authentication method should be used).
+    /// 
+    /// # Errors
+    /// Returns the store's error if the session can't be loaded.
+    /// 
+    /// [`Session`]: struct.Session.html
+    /// [`SessionStore`]: trait.SessionStore.html
+    pub fn restore_session<S: SessionStore + ?Sized>(&mut self, store: &S) -> Result<bool> {
+        match store.load()? {
+            Some(session) => {
+                self.authenticate_with_session(&session);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Saves the current [`Session`] to the given [`SessionStore`], so it can later be restored with
+    /// [`Scrobbler::restore_session`].
+    /// 
+    /// # Errors
+    /// Returns a `ScrobblerError::NotAuthenticated` error if the `Scrobbler` is not authenticated, or the store's error
+    /// if the session can't be saved.
+    /// 
+    /// [`Session`]: struct.Session.html
+    /// [`SessionStore`]: trait.SessionStore.html
+    /// [`Scrobbler::restore_session`]: struct.Scrobbler.html#method.restore_session
+    pub fn save_session<S: SessionStore + ?Sized>(&self, store: &S) -> Result<()> {
+        let session = self.session.as_ref().ok_or_else(|| {
+            ScrobblerError::NotAuthenticated("No session to save".to_owned())
+        })?;
+
+        store.save(session)
+    }
+
+    // Keeps `session` in sync with the client's session key, so a failed authentication leaves neither set
+    fn update_session(&mut self, result: Result<SessionResponse>) -> Result<SessionResponse> {
+        self.session = result.as_ref().ok().map(Session::from);
+        if self.session.is_none() {
+            self.client.clear_session_key();
+        }
+        result
     }
 
     /// Registers the given [`Scrobble`]/track as the currently authenticated user's "now playing" track.
@@ -166,6 +441,10 @@ impl Scrobbler {
     /// On success a [`NowPlayingResponse`] is returned. This can often be ignored (as in the example code), but it
     /// contains information that may be of use to some clients. 
     /// 
+    /// # Errors
+    /// Returns an error if the `Scrobbler` is not authenticated, the request fails, or Last.fm returns an error
+    /// response.
+    /// 
     /// # Last.fm API Documentation
     /// [track.updateNowPlaying API Method Documentation](https://www.last.fm/api/show/track.updateNowPlaying)
     /// 
@@ -177,7 +456,7 @@ impl Scrobbler {
     pub fn now_playing(&self, scrobble: &Scrobble) -> Result<NowPlayingResponse> {
         let params = scrobble.as_map();
 
-        Ok(self.client.send_now_playing(&params)?)
+        self.client.send_now_playing(&params)
     }
 
     /// Registers a scrobble (play) of the given [`Scrobble`]/track.
@@ -199,6 +478,10 @@ impl Scrobbler {
     /// On success a [`ScrobbleResponse`] is returned. This can often be ignored (as in the example code), but it
     /// contains information that may be of use to some clients. 
     /// 
+    /// # Errors
+    /// Returns an error if the `Scrobbler` is not authenticated, the request fails, or Last.fm returns an error
+    /// response.
+    /// 
     /// # Last.fm API Documentation
     /// [track.scrobble API Method Documention](https://www.last.fm/api/show/track.scrobble)
     /// [Scrobble Request Documentation](https://www.last.fm/api/scrobbling#scrobble-requests)
@@ -208,14 +491,9 @@ impl Scrobbler {
     /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
     /// [`ScrobbleResponse`]: responses/struct.ScrobbleResponse.html
     pub fn scrobble(&self, scrobble: &Scrobble) -> Result<ScrobbleResponse> {
-        let mut params = scrobble.as_map();
-        let current_time = UNIX_EPOCH.elapsed()?;
+        let params = Self::scrobble_params(scrobble)?;
 
-        params
-            .entry("timestamp".to_string())
-            .or_insert_with(|| format!("{}", current_time.as_secs()));
-
-        Ok(self.client.send_scrobble(&params)?)
+        self.client.send_scrobble(&params)
     }
 
     /// Registers a scrobble (play) of a collection of tracks. 
@@ -243,6 +521,10 @@ impl Scrobbler {
     /// On success, returns a [`ScrobbleBatchResponse`]. This can be ignored by most clients, but contains some data
     /// that may be of interest.
     /// 
+    /// # Errors
+    /// Returns a `ScrobblerError::InvalidBatch` error, without sending a request, if the batch is empty or holds more
+    /// than 50 scrobbles. Otherwise fails in the same cases as [`Scrobbler::scrobble`].
+    /// 
     /// # Last.fm API Documentation
     /// [track.scrobble API Method Documention](https://www.last.fm/api/show/track.scrobble)
     /// 
@@ -250,121 +532,1166 @@ impl Scrobbler {
     /// 
     /// [`ScrobbleBatch`]: struct.ScrobbleBatch.html
     /// [`Scrobble`]: struct.Scrobble.html
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
     /// [`ScrobbleBatchResponse`]: responses/struct.ScrobbleBatchResponse.html
     pub fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<BatchScrobbleResponse> {
-        let mut params = HashMap::new();
+        let params = Self::validated_batch_params(batch)?;
 
-        let batch_count = batch.len();
-        if batch_count > 50 {
-            return Err(ScrobblerError::new(
-                "Scrobble batch too large (must be 50 or fewer scrobbles)".to_owned(),
-            ));
-        } else if batch_count == 0 {
-            return Err(ScrobblerError::new("Scrobble batch is empty".to_owned()));
-        }
+        self.client.send_batch_scrobbles(&params)
+    }
+
+    /// Registers a scrobble (play) of a collection of tracks of any size, splitting it into API-sized requests.
+    /// 
+    /// The Last.fm API accepts at most 50 scrobbles per request. This method splits the [`ScrobbleBatch`] into
+    /// chunks of up to 50 [`Scrobble`]s and submits them in order, as [`Scrobbler::scrobble_batch`] would. A chunk
+    /// that fails to submit does not stop the remaining chunks from being sent.
+    /// 
+    /// # Usage
+    /// ```ignore
+    /// let batch = ScrobbleBatch::from(tracks_played_offline);
+    /// let response = scrobbler.scrobble_batch_chunked(&batch);
+    /// for index in response.failed_indices() {
+    ///     // Retry later...
+    /// }
+    /// ```
+    /// 
+    /// # Response
+    /// Returns a [`ChunkedScrobbleResponse`], merging the results of every chunk. It lists the indices (positions in
+    /// the submitted batch) of scrobbles accepted and ignored by Last.fm, and the index ranges of chunks that failed
+    /// along with their errors, so only the failed scrobbles need to be re-sent. An empty batch sends no requests.
+    /// 
+    /// [`ScrobbleBatch`]: struct.ScrobbleBatch.html
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    /// [`ChunkedScrobbleResponse`]: responses/struct.ChunkedScrobbleResponse.html
+    #[must_use]
+    pub fn scrobble_batch_chunked(&self, batch: &ScrobbleBatch) -> ChunkedScrobbleResponse {
+        let scrobbles: Vec<&Scrobble> = batch.iter().collect();
+        let mut response = ChunkedScrobbleResponse::default();
 
-        for (i, scrobble) in batch.iter().enumerate() {
-            let mut scrobble_params = scrobble.as_map();
-            let current_time = UNIX_EPOCH.elapsed()?;
-            scrobble_params
-                .entry("timestamp".to_string())
-                .or_insert_with(|| format!("{}", current_time.as_secs()));
+        for (chunk_index, chunk) in scrobbles.chunks(MAX_BATCH_SIZE).enumerate() {
+            let offset = chunk_index * MAX_BATCH_SIZE;
+            let result = Self::batch_params(chunk.iter().copied())
+                .and_then(|params| self.client.send_batch_scrobbles(&params));
 
-            for (key, val) in &scrobble_params {
-                // batched parameters need array notation suffix ie.
-                // "artist[1] = "Artist 1", "artist[2]" = "Artist 2"
-                params.insert(format!("{}[{}]", key, i), val.clone());
+            match result {
+                Ok(chunk_response) => {
+                    for (i, scrobble) in chunk_response.scrobbles.into_iter().enumerate() {
+                        if scrobble.is_ignored() {
+                            response.ignored.push(offset + i);
+                        } else {
+                            response.accepted.push(offset + i);
+                        }
+                        response.scrobbles.push((offset + i, scrobble));
+                    }
+                }
+                Err(error) => response.failed_chunks.push(FailedChunk {
+                    indices: offset..offset + chunk.len(),
+                    error,
+                }),
             }
         }
 
-        Ok(self.client.send_batch_scrobbles(&params)?)
+        response
     }
 
-    /// Gets the session key the client is currently authenticated with. Returns `None` if not authenticated. Valid
-    /// session keys can be stored and used to authenticate with [`authenticate_with_session_key`].
+    // Submits a batch of any size, one request per `MAX_BATCH_SIZE` scrobbles, stopping at the first failure
+    pub(crate) fn scrobble_chunked(&self, batch: &ScrobbleBatch) -> Result<()> {
+        let scrobbles: Vec<&Scrobble> = batch.iter().collect();
+        if scrobbles.is_empty() {
+            return Err(ScrobblerError::InvalidBatch("Scrobble batch is empty".to_owned()));
+        }
+
+        for chunk in scrobbles.chunks(MAX_BATCH_SIZE) {
+            let params = Self::batch_params(chunk.iter().copied())?;
+            self.client.send_batch_scrobbles(&params)?;
+        }
+
+        Ok(())
+    }
+
+    /// Registers a scrobble (play) of the given [`Scrobble`]/track, adding it to a [`ScrobbleQueue`] if the
+    /// submission fails.
     /// 
-    /// See [`authenticate_with_session_key`] for more information on Last.fm API Session Keys
+    /// Behaves like [`Scrobbler::scrobble`], but if the scrobble cannot be submitted (for example when the device is
+    /// offline) it is stored in the queue, timestamped with the current time if no timestamp was set, so it can be
+    /// submitted later with [`Scrobbler::drain_queue`].
     /// 
-    /// [`authenticate_with_session_key`]: struct.Scrobbler.html#method.authenticate_with_session_key
-    pub fn session_key(&self) -> Option<&str> {
-        self.client.session_key()
+    /// # Errors
+    /// Returns the submission error once the scrobble has been queued, or the queue's error if the scrobble could not
+    /// be added to it.
+    /// 
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    /// [`Scrobbler::drain_queue`]: struct.Scrobbler.html#method.drain_queue
+    pub fn scrobble_or_queue(&self, scrobble: &Scrobble, queue: &mut ScrobbleQueue) -> Result<ScrobbleResponse> {
+        let mut scrobble = scrobble.clone();
+        if scrobble.timestamp().is_none() {
+            scrobble.with_timestamp(UNIX_EPOCH.elapsed()?.as_secs());
+        }
+
+        self.scrobble(&scrobble).or_else(|err| {
+            queue.push(&scrobble)?;
+            Err(err)
+        })
     }
-}
 
+    /// Submits the scrobbles held in a [`ScrobbleQueue`] to Last.fm, in timestamp order.
+    /// 
+    /// Queued scrobbles are sent in batches (see [`Scrobbler::scrobble_batch_chunked`]) and are only removed from
+    /// the queue once Last.fm has confirmed receiving them. Scrobbles older than Last.fm's 14-day acceptance window
+    /// can never be accepted, so they are removed without being sent. Scrobbles in batches that fail to submit, and
+    /// scrobbles Last.fm ignored for a temporary reason (such as the daily scrobble limit), stay queued for a later
+    /// attempt.
+    /// 
+    /// # Response
+    /// Returns a [`QueueDrainReport`] listing the scrobbles submitted, the scrobbles dropped from the queue (expired,
+    /// or permanently ignored by Last.fm) with the reason, and any submission errors.
+    /// 
+    /// # Errors
+    /// Returns an error only if the queue itself could not be updated; failed submissions are reported in the
+    /// [`QueueDrainReport`].
+    /// 
+    /// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+    /// [`Scrobbler::scrobble_batch_chunked`]: struct.Scrobbler.html#method.scrobble_batch_chunked
+    /// [`QueueDrainReport`]: struct.QueueDrainReport.html
+    pub fn drain_queue(&self, queue: &mut ScrobbleQueue) -> Result<QueueDrainReport> {
+        let now = UNIX_EPOCH.elapsed()?.as_secs();
+        self.drain_queue_at(queue, now)
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use mockito::mock;
-    use std::error::Error;
+    fn drain_queue_at(&self, queue: &mut ScrobbleQueue, now: u64) -> Result<QueueDrainReport> {
+        let mut report = QueueDrainReport::default();
 
-    #[test]
-    fn make_scrobbler_pass_auth() {
-        let _m = mock("POST", mockito::Matcher::Any).create();
+        let mut expired = Vec::new();
+        let mut to_submit = Vec::new();
+        for (id, scrobble) in queue.pending() {
+            let age_secs = now.saturating_sub(scrobble.timestamp().unwrap_or(now));
+            if age_secs > MAX_SCROBBLE_AGE_SECS {
+                expired.push(id);
+                report.dropped.push((scrobble.clone(), DropReason::Expired { age_secs }));
+            } else {
+                to_submit.push((id, scrobble.clone()));
+            }
+        }
+        queue.remove(&expired)?;
 
-        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
-        let resp = scrobbler.authenticate_with_password("user", "pass");
-        assert!(resp.is_err());
+        let batch: ScrobbleBatch = to_submit.iter().map(|(_, scrobble)| scrobble.clone()).collect();
+        let response = self.scrobble_batch_chunked(&batch);
 
-        let _m = mock("POST", mockito::Matcher::Any)
-            .with_body(
-                r#"
-                {   
-                    "session": {
-                        "key": "key",
-                        "subscriber": 1337,
-                        "name": "foo floyd"
-                    }
+        let mut confirmed = Vec::new();
+        for (index, scrobble_response) in response.scrobbles {
+            // A response listing more scrobbles than were sent can't be matched to the queue, so extras are skipped
+            let Some((id, scrobble)) = to_submit.get(index) else {
+                continue;
+            };
+
+            match scrobble_response.ignore_reason() {
+                // Left in the queue, so it is resubmitted by a later drain
+                Some(reason) if !reason.is_permanent() => continue,
+                Some(reason) => {
+                    let message = scrobble_response.ignored_message.map(|message| message.text).unwrap_or_default();
+                    let reason = DropReason::Ignored { reason, message };
+                    report.dropped.push((scrobble.clone(), reason));
                 }
-            "#,
-            )
-            .create();
+                None => report.submitted.push(scrobble.clone()),
+            }
+            confirmed.push(*id);
+        }
+        queue.remove(&confirmed)?;
 
-        let resp = scrobbler.authenticate_with_password("user", "pass");
-        assert!(resp.is_ok());
+        report.errors = response.failed_chunks.into_iter().map(|chunk| chunk.error).collect();
+        report.remaining = queue.len();
+
+        Ok(report)
     }
 
-    #[test]
-    fn make_scrobbler_token_auth() {
-        let _m = mock("POST", mockito::Matcher::Any).create();
+    /// Registers a scrobble (play) of the given [`Scrobble`]/track unless it duplicates a scrobble held by the
+    /// [`DuplicateFilter`].
+    /// 
+    /// Scrobbles without a timestamp are timestamped with the current time. Duplicates are dropped without sending
+    /// anything to Last.fm, and `Ok(None)` is returned. Otherwise the scrobble is submitted as by
+    /// [`Scrobbler::scrobble`], and added to the filter once Last.fm has accepted it.
+    /// 
+    /// # Usage
+    /// ```ignore
+    /// let mut filter = DuplicateFilter::new();
+    /// for song in replayed_songs {
+    ///     if scrobbler.scrobble_deduplicated(&song, &mut filter)?.is_none() {
+    ///         println!("Skipped duplicate {}", song.track());
+    ///     }
+    /// }
+    /// ```
+    /// 
+    /// # Errors
+    /// Fails in the same cases as [`Scrobbler::scrobble`]. Dropping a duplicate is not an error.
+    /// 
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`DuplicateFilter`]: struct.DuplicateFilter.html
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    pub fn scrobble_deduplicated(
+        &self,
+        scrobble: &Scrobble,
+        filter: &mut DuplicateFilter,
+    ) -> Result<Option<ScrobbleResponse>> {
+        let mut scrobble = scrobble.clone();
+        if scrobble.timestamp().is_none() {
+            scrobble.with_timestamp(UNIX_EPOCH.elapsed()?.as_secs());
+        }
+        if filter.is_duplicate(&scrobble) {
+            return Ok(None);
+        }
 
-        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
-        let resp = scrobbler.authenticate_with_token("some_token");
-        assert!(resp.is_err());
+        let response = self.scrobble(&scrobble)?;
+        if !response.is_ignored() {
+            filter.insert(&scrobble);
+        }
 
-        let _m = mock("POST", mockito::Matcher::Any)
-            .with_body(
-                r#"
-                {   
-                    "session": {
-                        "key": "key",
-                        "subscriber": 1337,
-                        "name": "foo floyd"
-                    }
+        Ok(Some(response))
+    }
+
+    /// Registers a scrobble (play) of a collection of tracks of any size, dropping the scrobbles that duplicate a
+    /// scrobble held by the [`DuplicateFilter`] or an earlier scrobble of the batch.
+    /// 
+    /// Scrobbles without a timestamp are timestamped with the current time. The remaining scrobbles are submitted as
+    /// by [`Scrobbler::scrobble_batch_chunked`], and those accepted by Last.fm are added to the filter.
+    /// 
+    /// # Response
+    /// Returns a [`DeduplicatedBatchResponse`], listing the batch indices of the duplicates & of the scrobbles that
+    /// were submitted, with the [`ChunkedScrobbleResponse`] of the submission.
+    /// 
+    /// # Errors
+    /// Returns an error only if the system clock is set before the UNIX epoch; failed chunks are reported in the
+    /// [`ChunkedScrobbleResponse`].
+    /// 
+    /// [`DuplicateFilter`]: struct.DuplicateFilter.html
+    /// [`Scrobbler::scrobble_batch_chunked`]: struct.Scrobbler.html#method.scrobble_batch_chunked
+    /// [`DeduplicatedBatchResponse`]: struct.DeduplicatedBatchResponse.html
+    /// [`ChunkedScrobbleResponse`]: responses/struct.ChunkedScrobbleResponse.html
+    pub fn scrobble_batch_deduplicated(
+        &self,
+        batch: &ScrobbleBatch,
+        filter: &mut DuplicateFilter,
+    ) -> Result<DeduplicatedBatchResponse> {
+        let now = UNIX_EPOCH.elapsed()?.as_secs();
+        let batch: ScrobbleBatch = batch
+            .iter()
+            .map(|scrobble| {
+                let mut scrobble = scrobble.clone();
+                if scrobble.timestamp().is_none() {
+                    scrobble.with_timestamp(now);
                 }
-            "#,
-            )
-            .create();
+                scrobble
+            })
+            .collect();
 
-        let resp = scrobbler.authenticate_with_token("some_token");
-        assert!(resp.is_ok());
+        let (submitted, duplicates) = filter.partition(&batch);
+        let scrobbles: Vec<&Scrobble> = batch.iter().collect();
+        let to_submit: ScrobbleBatch = submitted.iter().map(|&i| scrobbles[i].clone()).collect();
+
+        let response = self.scrobble_batch_chunked(&to_submit);
+        for &index in &response.accepted {
+            filter.insert(scrobbles[submitted[index]]);
+        }
+
+        Ok(DeduplicatedBatchResponse {
+            duplicates,
+            submitted,
+            response,
+        })
     }
 
-    #[test]
-    fn check_scrobbler_error() {
-        let err = ScrobblerError::new("test_error".into());
-        let fmt = format!("{}", err);
-        assert_eq!("test_error", fmt);
+    /// Adds the user's scrobbles already recorded by Last.fm to a [`DuplicateFilter`], so they aren't submitted
+    /// again.
+    /// 
+    /// Fetches the user's listening history with [`Scrobbler::recent_tracks`], newest first, until the filter's
+    /// capacity is reached or the history ends. Set the query's `from` timestamp to only fetch the recent history
+    /// that could overlap with the scrobbles about to be sent. Returns the number of scrobbles added.
+    /// 
+    /// # Errors
+    /// Returns the first error met while fetching the listening history. Scrobbles added before the error stay in the
+    /// filter.
+    /// 
+    /// [`DuplicateFilter`]: struct.DuplicateFilter.html
+    /// [`Scrobbler::recent_tracks`]: struct.Scrobbler.html#method.recent_tracks
+    pub fn seed_duplicate_filter(&self, filter: &mut DuplicateFilter, query: &RecentTracksQuery) -> Result<usize> {
+        let mut added = 0;
+        for track in self.recent_tracks(query) {
+            if added >= filter.capacity() {
+                break;
+            }
 
-        let desc = err.to_string();
-        assert_eq!("test_error", &desc);
+            let track = track?;
+            if !track.now_playing {
+                filter.insert(&track.to_scrobble());
+                added += 1;
+            }
+        }
 
-        assert!(err.source().is_none());
+        Ok(added)
     }
 
-    #[test]
-    fn check_scrobbler_now_playing() {
-        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+    /// Marks the given [`Scrobble`]/track as loved by the currently authenticated user.
+    /// 
+    /// Only the scrobble's artist and track are sent; a track can be loved without being scrobbled first.
+    /// 
+    /// # Usage
+    /// ```ignore
+    /// let scrobbler = Scrobbler::new(...);
+    /// // Scrobbler authentication ...
+    /// let track = Scrobble::new("Example Artist", "Example Track", "");
+    /// scrobbler.love(&track)?;
+    /// ```
+    /// 
+    /// # Response
+    /// On success a [`LoveResponse`] is returned, recording the artist & track that were loved.
+    /// 
+    /// # Errors
+    /// Returns an error if the `Scrobbler` is not authenticated, the request fails, or Last.fm returns an error
+    /// response.
+    /// 
+    /// # Last.fm API Documentation
+    /// [track.love API Method Documentation](https://www.last.fm/api/show/track.love)
+    /// 
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`LoveResponse`]: responses/struct.LoveResponse.html
+    pub fn love(&self, scrobble: &Scrobble) -> Result<LoveResponse> {
+        self.client.send_love(&Self::love_params(scrobble))?;
+        Ok(Self::love_response(scrobble, true))
+    }
+
+    /// Removes the loved status of the given [`Scrobble`]/track for the currently authenticated user. See
+    /// [`Scrobbler::love`].
+    /// 
+    /// # Errors
+    /// Returns an error if the `Scrobbler` is not authenticated, the request fails, or Last.fm returns an error
+    /// response.
+    /// 
+    /// # Last.fm API Documentation
+    /// [track.unlove API Method Documentation](https://www.last.fm/api/show/track.unlove)
+    /// 
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`Scrobbler::love`]: struct.Scrobbler.html#method.love
+    pub fn unlove(&self, scrobble: &Scrobble) -> Result<LoveResponse> {
+        self.client.send_unlove(&Self::love_params(scrobble))?;
+        Ok(Self::love_response(scrobble, false))
+    }
+
+    fn love_params(scrobble: &Scrobble) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        params.insert("artist".to_string(), scrobble.artist().to_owned());
+        params.insert("track".to_string(), scrobble.track().to_owned());
+
+        params
+    }
+
+    fn love_response(scrobble: &Scrobble, loved: bool) -> LoveResponse {
+        LoveResponse {
+            artist: scrobble.artist().to_owned(),
+            track: scrobble.track().to_owned(),
+            loved,
+        }
+    }
+
+    /// Gets a single page of a user's listening history. Pages are numbered from 1.
+    /// 
+    /// Only an API key is needed to read a user's history; the `Scrobbler` does not have to be authenticated. Use
+    /// [`Scrobbler::recent_tracks`] to iterate over every page.
+    /// 
+    /// # Usage
+    /// ```ignore
+    /// let scrobbler = Scrobbler::new(...);
+    /// let page = scrobbler.recent_tracks_page(&RecentTracksQuery::new("username"), 1)?;
+    /// println!("{} scrobbles", page.pagination.total);
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns an error if the request fails or Last.fm returns an error response, for example when the user does not
+    /// exist.
+    /// 
+    /// # Last.fm API Documentation
+    /// [user.getRecentTracks API Method Documentation](https://www.last.fm/api/show/user.getRecentTracks)
+    /// 
+    /// [`Scrobbler::recent_tracks`]: struct.Scrobbler.html#method.recent_tracks
+    pub fn recent_tracks_page(&self, query: &RecentTracksQuery, page: u32) -> Result<RecentTracksPage> {
+        self.client.get_recent_tracks(&query.params(page))
+    }
+
+    /// Iterates over a user's listening history, newest first, fetching pages lazily as the iterator advances.
+    /// 
+    /// A track the user is listening to right now is returned first, with [`RecentTrack::now_playing`] set; it has
+    /// no timestamp. Scrobbles made while iterating shift tracks between pages, so set an upper bound with
+    /// [`RecentTracksQuery::with_to`] when reading a long history.
+    /// 
+    /// # Usage
+    /// ```ignore
+    /// let mut query = RecentTracksQuery::new("username");
+    /// query.with_from(last_sync).with_limit(200);
+    /// 
+    /// for track in scrobbler.recent_tracks(&query) {
+    ///     let track = track?;
+    ///     if !track.now_playing {
+    ///         library.add(track.to_scrobble());
+    ///     }
+    /// }
+    /// ```
+    /// 
+    /// [`RecentTrack::now_playing`]: responses/values/struct.RecentTrack.html#structfield.now_playing
+    /// [`RecentTracksQuery::with_to`]: struct.RecentTracksQuery.html#method.with_to
+    #[must_use]
+    pub fn recent_tracks(&self, query: &RecentTracksQuery) -> RecentTracks<'_> {
+        RecentTracks::new(self, query.clone())
+    }
+
+    /// Looks up the metadata & listening statistics of a track. No authentication is needed.
+    /// 
+    /// The returned [`TrackInfo`] can fill in the missing metadata (album, album artist, duration, MusicBrainz ID) of
+    /// a [`Scrobble`] before it is submitted.
+    /// 
+    /// # Usage
+    /// ```ignore
+    /// let info = scrobbler.track_info(track.artist(), track.track(), &LookupOptions::new())?;
+    /// scrobbler.scrobble(&info.enrich(&track))?;
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns an error if the request fails or Last.fm returns an error response, for example when the track is not
+    /// found.
+    /// 
+    /// # Last.fm API Documentation
+    /// [track.getInfo API Method Documentation](https://www.last.fm/api/show/track.getInfo)
+    /// 
+    /// [`TrackInfo`]: responses/struct.TrackInfo.html
+    /// [`Scrobble`]: struct.Scrobble.html
+    pub fn track_info(&self, artist: &str, track: &str, options: &LookupOptions) -> Result<TrackInfo> {
+        self.client.get_track_info(&options.params(&[("artist", artist), ("track", track)]))
+    }
+
+    /// Looks up the metadata & listening statistics of an artist. No authentication is needed.
+    /// 
+    /// # Errors
+    /// Returns an error if the request fails or Last.fm returns an error response, for example when the artist is not
+    /// found.
+    /// 
+    /// # Last.fm API Documentation
+    /// [artist.getInfo API Method Documentation](https://www.last.fm/api/show/artist.getInfo)
+    pub fn artist_info(&self, artist: &str, options: &LookupOptions) -> Result<ArtistInfo> {
+        self.client.get_artist_info(&options.params(&[("artist", artist)]))
+    }
+
+    /// Looks up the metadata, tracklist & listening statistics of an album. No authentication is needed.
+    /// 
+    /// # Errors
+    /// Returns an error if the request fails or Last.fm returns an error response, for example when the album is not
+    /// found.
+    /// 
+    /// # Last.fm API Documentation
+    /// [album.getInfo API Method Documentation](https://www.last.fm/api/show/album.getInfo)
+    pub fn album_info(&self, artist: &str, album: &str, options: &LookupOptions) -> Result<AlbumInfo> {
+        self.client.get_album_info(&options.params(&[("artist", artist), ("album", album)]))
+    }
+
+    /// Checks whether Last.fm would correct the given artist & track name, without submitting anything. Returns
+    /// `None` if Last.fm has no correction for the names. No authentication is needed.
+    /// 
+    /// Use [`TrackCorrection::apply`] to apply the correction to a [`Scrobble`] and see which fields changed.
+    /// 
+    /// # Usage
+    /// ```ignore
+    /// if let Some(correction) = scrobbler.track_correction(track.artist(), track.track())? {
+    ///     let corrected = correction.apply(&track);
+    ///     if corrected.is_changed() {
+    ///         // Suggest corrected.scrobble to the user
+    ///     }
+    /// }
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns an error if the request fails or Last.fm returns an error response.
+    /// 
+    /// # Last.fm API Documentation
+    /// [track.getCorrection API Method Documentation](https://www.last.fm/api/show/track.getCorrection)
+    /// 
+    /// [`TrackCorrection::apply`]: responses/struct.TrackCorrection.html#method.apply
+    /// [`Scrobble`]: struct.Scrobble.html
+    pub fn track_correction(&self, artist: &str, track: &str) -> Result<Option<TrackCorrection>> {
+        let mut params = HashMap::new();
+        params.insert("artist".to_string(), artist.to_owned());
+        params.insert("track".to_string(), track.to_owned());
+
+        self.client.get_track_correction(&params)
+    }
+
+    /// Checks whether Last.fm would correct the given artist name. Returns `None` if Last.fm has no correction for
+    /// the name. See [`Scrobbler::track_correction`].
+    /// 
+    /// # Errors
+    /// Returns an error if the request fails or Last.fm returns an error response.
+    /// 
+    /// # Last.fm API Documentation
+    /// [artist.getCorrection API Method Documentation](https://www.last.fm/api/show/artist.getCorrection)
+    /// 
+    /// [`Scrobbler::track_correction`]: struct.Scrobbler.html#method.track_correction
+    pub fn artist_correction(&self, artist: &str) -> Result<Option<ArtistCorrection>> {
+        let mut params = HashMap::new();
+        params.insert("artist".to_string(), artist.to_owned());
+
+        self.client.get_artist_correction(&params)
+    }
+
+    // Request parameters for a single scrobble; scrobbles without a timestamp are timestamped with the current time
+    pub(crate) fn scrobble_params(scrobble: &Scrobble) -> Result<HashMap<String, String>> {
+        let mut params = scrobble.as_map();
+        let current_time = UNIX_EPOCH.elapsed()?;
+
+        params
+            .entry("timestamp".to_string())
+            .or_insert_with(|| format!("{}", current_time.as_secs()));
+
+        Ok(params)
+    }
+
+    pub(crate) fn validated_batch_params(batch: &ScrobbleBatch) -> Result<HashMap<String, String>> {
+        let batch_count = batch.len();
+        if batch_count > MAX_BATCH_SIZE {
+            return Err(ScrobblerError::InvalidBatch(
+                "Scrobble batch too large (must be 50 or fewer scrobbles)".to_owned(),
+            ));
+        } else if batch_count == 0 {
+            return Err(ScrobblerError::InvalidBatch("Scrobble batch is empty".to_owned()));
+        }
+
+        Self::batch_params(batch.iter())
+    }
+
+    fn batch_params<'a, I>(scrobbles: I) -> Result<HashMap<String, String>>
+    where
+        I: Iterator<Item = &'a Scrobble>,
+    {
+        let mut params = HashMap::new();
+
+        for (i, scrobble) in scrobbles.enumerate() {
+            let scrobble_params = Self::scrobble_params(scrobble)?;
+
+            for (key, val) in &scrobble_params {
+                // batched parameters need array notation suffix ie.
+                // "artist[1] = "Artist 1", "artist[2]" = "Artist 2"
+                #[allow(clippy::uninlined_format_args)]
+                params.insert(format!("{}[{}]", key, i), val.clone());
+            }
+        }
+
+        Ok(params)
+    }
+
+    /// Gets the session key the client is currently authenticated with. Returns `None` if not authenticated. Valid
+    /// session keys can be stored and used to authenticate with [`authenticate_with_session_key`].
+    /// 
+    /// See [`authenticate_with_session_key`] for more information on Last.fm API Session Keys
+    /// 
+    /// [`authenticate_with_session_key`]: struct.Scrobbler.html#method.authenticate_with_session_key
+    #[must_use]
+    pub fn session_key(&self) -> Option<&str> {
+        self.client.session_key()
+    }
+
+    /// Gets the [`Session`] the client is currently authenticated with. Returns `None` if not authenticated.
+    /// 
+    /// `Session` implements serde's `Serialize`/`Deserialize`, and can be saved & later restored with
+    /// [`authenticate_with_session`]. See also [`Scrobbler::save_session`].
+    /// 
+    /// [`Session`]: struct.Session.html
+    /// [`authenticate_with_session`]: struct.Scrobbler.html#method.authenticate_with_session
+    /// [`Scrobbler::save_session`]: struct.Scrobbler.html#method.save_session
+    #[must_use]
+    pub fn session(&self) -> Option<&Session> {
+        self.session.as_ref()
+    }
+}
+
+
+/// Builds a [`Scrobbler`] talking to a custom service or using a custom HTTP transport. Created with
+/// [`Scrobbler::builder`].
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`Scrobbler::builder`]: struct.Scrobbler.html#method.builder
+pub struct ScrobblerBuilder {
+    api_key: String,
+    api_secret: String,
+    endpoints: ApiEndpoints,
+    transport: Option<Box<dyn Transport>>,
+    retrier: Retrier,
+}
+
+impl ScrobblerBuilder {
+    /// Sets the endpoints of the scrobbling service, such as [`ApiEndpoints::libre_fm`]. Defaults to Last.fm.
+    ///
+    /// [`ApiEndpoints::libre_fm`]: struct.ApiEndpoints.html#method.libre_fm
+    #[must_use]
+    pub fn endpoints(mut self, endpoints: ApiEndpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    /// Sets the HTTP transport requests are sent with. Defaults to [`UreqTransport`].
+    ///
+    /// [`UreqTransport`]: struct.UreqTransport.html
+    #[must_use]
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Box::new(transport));
+        self
+    }
+
+    /// Sets the [`RetryPolicy`] API requests that fail with a transient error are retried with. By default, failed
+    /// requests are not retried.
+    ///
+    /// [`RetryPolicy`]: struct.RetryPolicy.html
+    #[must_use]
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retrier.policy = Some(policy);
+        self
+    }
+
+    /// Sets a callback called before each retry, with the failed request's method & error and the delay before the
+    /// next attempt.
+    #[must_use]
+    pub fn on_retry<F>(mut self, on_retry: F) -> Self
+    where
+        F: Fn(&RetryEvent<'_>) + Send + Sync + 'static,
+    {
+        self.retrier.on_retry = Some(Box::new(on_retry));
+        self
+    }
+
+    /// Sets the [`Clock`] used to measure how long a request has been retried for, and how long
+    /// [`Scrobbler::authenticate_with_token_polling`] has waited. Defaults to [`SystemClock`].
+    ///
+    /// [`Clock`]: trait.Clock.html
+    /// [`Scrobbler::authenticate_with_token_polling`]: struct.Scrobbler.html#method.authenticate_with_token_polling
+    /// [`SystemClock`]: struct.SystemClock.html
+    #[must_use]
+    pub fn retry_clock<C: Clock + Send + Sync + 'static>(mut self, clock: C) -> Self {
+        self.retrier.clock = Box::new(clock);
+        self
+    }
+
+    /// Sets the [`Sleeper`] used to wait between retries, and between the polls of
+    /// [`Scrobbler::authenticate_with_token_polling`]. Defaults to [`ThreadSleeper`].
+    ///
+    /// [`Sleeper`]: trait.Sleeper.html
+    /// [`Scrobbler::authenticate_with_token_polling`]: struct.Scrobbler.html#method.authenticate_with_token_polling
+    /// [`ThreadSleeper`]: struct.ThreadSleeper.html
+    #[must_use]
+    pub fn retry_sleeper<S: Sleeper + Send + Sync + 'static>(mut self, sleeper: S) -> Self {
+        self.retrier.sleeper = Box::new(sleeper);
+        self
+    }
+
+    /// Builds the [`Scrobbler`]
+    ///
+    /// [`Scrobbler`]: struct.Scrobbler.html
+    #[must_use]
+    pub fn build(self) -> Scrobbler {
+        let transport = self.transport.unwrap_or_else(|| Box::new(UreqTransport::new()));
+        let mut client = LastFm::with_config(&self.api_key, &self.api_secret, self.endpoints, transport);
+        client.set_retrier(self.retrier);
+
+        Scrobbler {
+            client,
+            session: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::mock;
+    use std::error::Error;
+    use crate::correction::ScrobbleField;
+    use crate::transport::{HttpMethod, RecordingTransport};
+    use crate::dedup::DuplicateFilter;
+    use crate::models::responses::IgnoreReason;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn make_scrobbler_pass_auth() {
+        let _m = mock("POST", mockito::Matcher::Any).create();
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        let resp = scrobbler.authenticate_with_password("user", "pass");
+        assert!(resp.is_err());
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_body(
+                r#"
+                {   
+                    "session": {
+                        "key": "key",
+                        "subscriber": 1337,
+                        "name": "foo floyd"
+                    }
+                }
+            "#,
+            )
+            .create();
+
+        let resp = scrobbler.authenticate_with_password("user", "pass");
+        assert!(resp.is_ok());
+    }
+
+    #[test]
+    fn make_scrobbler_token_auth() {
+        let _m = mock("POST", mockito::Matcher::Any).create();
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        let resp = scrobbler.authenticate_with_token("some_token");
+        assert!(resp.is_err());
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_body(
+                r#"
+                {   
+                    "session": {
+                        "key": "key",
+                        "subscriber": 1337,
+                        "name": "foo floyd"
+                    }
+                }
+            "#,
+            )
+            .create();
+
+        let resp = scrobbler.authenticate_with_token("some_token");
+        assert!(resp.is_ok());
+    }
+
+    #[test]
+    fn make_scrobbler_token_polling_auth() {
+        // A fake clock, advanced by the sleeper
+        let slept = Arc::new(Mutex::new(Vec::new()));
+        let (clock_slept, sleeper_slept) = (Arc::clone(&slept), Arc::clone(&slept));
+        let transport = RecordingTransport::new();
+        let mut scrobbler = Scrobbler::builder("api_key", "api_secret")
+            .transport(transport.clone())
+            .retry_clock(move || UNIX_EPOCH + clock_slept.lock().unwrap().iter().sum::<Duration>())
+            .retry_sleeper(move |delay| sleeper_slept.lock().unwrap().push(delay))
+            .build();
+
+        let interval = Duration::from_secs(5);
+        let not_authorized = r#"{"error": 14, "message": "Unauthorized Token - This token has not been issued"}"#;
+        for _ in 0..3 {
+            transport.push_response(403, not_authorized);
+        }
+
+        let resp = scrobbler.authenticate_with_token_polling("some_token", interval, Duration::from_secs(12));
+        assert!(matches!(resp, Err(ScrobblerError::NotAuthenticated(_))));
+        assert_eq!(transport.requests().len(), 3);
+        assert_eq!(*slept.lock().unwrap(), vec![interval; 2]);
+        assert!(scrobbler.session().is_none());
+        assert!(scrobbler.session_key().is_none());
+        // The timed out token is not reused
+        assert!(matches!(scrobbler.client.authenticate_with_token(), Err(ScrobblerError::NotAuthenticated(_))));
+
+        transport.push_response(403, r#"{"error": 15, "message": "This token has expired"}"#);
+        let resp = scrobbler.authenticate_with_token_polling("some_token", interval, Duration::from_secs(30));
+        assert_eq!(resp.unwrap_err().api_error_code(), Some(15));
+        assert_eq!(slept.lock().unwrap().len(), 2);
+
+        transport.push_response(403, not_authorized);
+        transport.push_response(200, r#"{"session": {"key": "key", "subscriber": 1337, "name": "foo floyd"}}"#);
+        let resp = scrobbler.authenticate_with_token_polling("some_token", interval, Duration::from_secs(30));
+        assert!(resp.is_ok());
+        assert_eq!(scrobbler.session_key(), Some("key"));
+        assert_eq!(slept.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn check_scrobbler_session_export_and_restore() {
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_body(
+                r#"
+                {   
+                    "session": {
+                        "key": "key",
+                        "subscriber": 0,
+                        "name": "foo floyd"
+                    }
+                }
+            "#,
+            )
+            .create();
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        assert!(scrobbler.session().is_none());
+        scrobbler.authenticate_with_password("user", "pass").unwrap();
+
+        let session = scrobbler.session().unwrap().clone();
+        assert_eq!(session.key, "key");
+        assert_eq!(session.name.as_deref(), Some("foo floyd"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = crate::session::FileSessionStore::new(dir.path().join("session.json"));
+
+        let mut restored = Scrobbler::new("api_key", "api_secret");
+        assert!(!restored.restore_session(&store).unwrap());
+        assert!(restored.save_session(&store).is_err());
+
+        scrobbler.save_session(&store).unwrap();
+        assert!(restored.restore_session(&store).unwrap());
+        assert_eq!(restored.session(), Some(&session));
+        assert_eq!(restored.session_key(), Some("key"));
+    }
+
+    #[test]
+    fn check_failed_reauthentication_clears_session() {
+        let transport = RecordingTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport.clone());
+        scrobbler.authenticate_with_session(&Session::from_key("old-key"));
+
+        transport.push_response(403, r#"{"error": 4, "message": "Authentication Failed"}"#);
+        assert!(scrobbler.authenticate_with_password("user", "wrong").is_err());
+        assert!(scrobbler.session().is_none());
+        assert!(scrobbler.session_key().is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = crate::session::FileSessionStore::new(dir.path().join("session.json"));
+        assert!(matches!(scrobbler.save_session(&store), Err(ScrobblerError::NotAuthenticated(_))));
+        assert!(matches!(
+            scrobbler.scrobble(&Scrobble::new("Artist", "Track", "Album")),
+            Err(ScrobblerError::NotAuthenticated(_))
+        ));
+    }
+
+    #[test]
+    #[allow(clippy::uninlined_format_args)]
+    fn check_scrobbler_error() {
+        let err = ScrobblerError::new("test_error".into());
+        let fmt = format!("{}", err);
+        assert_eq!("test_error", fmt);
+
+        let desc = err.to_string();
+        assert_eq!("test_error", &desc);
+
+        assert!(err.source().is_none());
+    }
+
+    #[test]
+    fn check_scrobbler_with_transport() {
+        let transport = crate::transport::RecordingTransport::new();
+        transport.push_response(
+            200,
+            r#"{"session": {"key": "key", "subscriber": 0, "name": "foo floyd"}}"#,
+        );
+        transport.push_response(403, r#"{"error": 9, "message": "Invalid session key"}"#);
+
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport.clone());
+        assert!(scrobbler.authenticate_with_password("user", "pass").is_ok());
+
+        let mut scrobble = Scrobble::new("foo floyd and the fruit flies", "old bananas", "old bananas");
+        scrobble.with_timestamp(1337);
+        let err = scrobbler.scrobble(&scrobble).unwrap_err();
+        assert_eq!(err.api_error_code(), Some(9));
+
+        let err = scrobbler.now_playing(&scrobble).unwrap_err();
+        assert!(matches!(err, ScrobblerError::Transport(_)));
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].method, crate::transport::HttpMethod::Post);
+        assert_eq!(requests[0].params["method"], "auth.getMobileSession");
+        assert_eq!(requests[1].params["method"], "track.scrobble");
+        assert_eq!(requests[1].params["sk"], "key");
+        assert_eq!(requests[1].params["timestamp"], "1337");
+        assert!(requests[1].params.contains_key("api_sig"));
+        assert_eq!(requests[2].params["method"], "track.updateNowPlaying");
+    }
+
+    #[test]
+    fn check_scrobbler_builder_endpoints() {
+        let transport = crate::transport::RecordingTransport::new();
+        transport.push_response(200, r#"{"token": "cf45fe5a3e3cebe168480a086d7fe481"}"#);
+
+        let endpoints = ApiEndpoints::new("https://scrobble.example.com/2.0/", "https://scrobble.example.com/auth/");
+        let scrobbler = Scrobbler::builder("api_key", "api_secret")
+            .endpoints(endpoints.unwrap())
+            .transport(transport.clone())
+            .build();
+
+        let token = scrobbler.get_auth_token().unwrap();
+        assert_eq!(
+            scrobbler.authorization_url(&token),
+            "https://scrobble.example.com/auth/?api_key=api_key&token=cf45fe5a3e3cebe168480a086d7fe481"
+        );
+
+        let request = transport.last_request().unwrap();
+        assert_eq!(request.url, "https://scrobble.example.com/2.0/");
+        assert_eq!(request.params["method"], "auth.getToken");
+        assert_eq!(request.params["format"], "json");
+
+        let scrobbler = Scrobbler::builder("api_key", "api_secret")
+            .endpoints(ApiEndpoints::libre_fm())
+            .build();
+        assert_eq!(scrobbler.endpoints(), &ApiEndpoints::libre_fm());
+        assert_eq!(Scrobbler::new("api_key", "api_secret").endpoints(), &ApiEndpoints::last_fm());
+    }
+
+    #[test]
+    fn check_scrobbler_love_unlove() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey");
+
+        // Signature covers all params in key order, followed by the API secret
+        let signature = crate::auth::md5_hex(
+            "api_keyapi_keyartistfoo floydmethodtrack.loveskseshkeytrackold bananasapi_secret",
+        );
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("method".into(), "track.love".into()),
+                mockito::Matcher::UrlEncoded("artist".into(), "foo floyd".into()),
+                mockito::Matcher::UrlEncoded("track".into(), "old bananas".into()),
+                mockito::Matcher::UrlEncoded("sk".into(), "seshkey".into()),
+                mockito::Matcher::UrlEncoded("api_sig".into(), signature),
+            ]))
+            .with_body("{}")
+            .create();
+
+        let track = Scrobble::new("foo floyd", "old bananas", "old bananas");
+        let resp = scrobbler.love(&track).unwrap();
+        assert_eq!(resp.artist, "foo floyd");
+        assert_eq!(resp.track, "old bananas");
+        assert!(resp.loved);
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::UrlEncoded("method".into(), "track.unlove".into()))
+            .with_body("{}")
+            .create();
+
+        assert!(!scrobbler.unlove(&track).unwrap().loved);
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_status(400)
+            .with_body(r#"{"error": 6, "message": "Track not found"}"#)
+            .create();
+
+        assert_eq!(scrobbler.love(&track).unwrap_err().api_error_code(), Some(6));
+    }
+
+    #[test]
+    fn check_scrobble_batch_validation() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey");
+
+        let err = scrobbler.scrobble_batch(&ScrobbleBatch::new()).unwrap_err();
+        assert!(matches!(err, ScrobblerError::InvalidBatch(_)));
+
+        let tracks = vec![("Artist", "Track", "Album"); 51];
+        let err = scrobbler.scrobble_batch(&ScrobbleBatch::from(tracks)).unwrap_err();
+        assert!(matches!(err, ScrobblerError::InvalidBatch(_)));
+    }
+
+    #[test]
+    fn check_scrobble_batch_optional_params() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey");
+
+        let mut first = Scrobble::new("Artist 1", "Track 1", "Compilation");
+        first.with_album_artist("Various Artists").with_timestamp(1337);
+        let mut second = Scrobble::new("Artist 2", "Track 2", "Compilation");
+        second.with_album_artist("Various Artists").with_duration(180).with_track_number(2);
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("albumArtist[0]".into(), "Various Artists".into()),
+                mockito::Matcher::UrlEncoded("albumArtist[1]".into(), "Various Artists".into()),
+                mockito::Matcher::UrlEncoded("duration[1]".into(), "180".into()),
+                mockito::Matcher::UrlEncoded("trackNumber[1]".into(), "2".into()),
+                mockito::Matcher::UrlEncoded("timestamp[0]".into(), "1337".into()),
+            ]))
+            .with_body(
+                r#"
+            { 
+                "scrobbles": {
+                    "scrobble": []
+                }
+            }
+            "#,
+            )
+            .create();
+
+        let resp = scrobbler.scrobble_batch(&ScrobbleBatch::from(vec![first, second]));
+        assert!(resp.is_ok());
+    }
+
+    fn batch_response_body(count: usize, ignored: &[(usize, u32)]) -> String {
+        let scrobbles: Vec<String> = (0..count)
+            .map(|i| {
+                let code = ignored.iter().find(|(index, _)| *index == i).map_or(0, |(_, code)| *code);
+                format!(
+                    r##"{{
+                        "artist": {{ "corrected": "0", "#text": "foo floyd and the fruit flies" }},
+                        "album": {{ "corrected": "0", "#text": "old bananas" }},
+                        "albumArtist": {{ "corrected": "0", "#text": "" }},
+                        "track": {{ "corrected": "0", "#text": "old bananas" }},
+                        "timestamp": "1337",
+                        "ignoredMessage": {{ "code": "{code}", "#text": "" }}
+                    }}"##
+                )
+            })
+            .collect();
+
+        format!(r#"{{ "scrobbles": {{ "scrobble": [{}] }} }}"#, scrobbles.join(","))
+    }
+
+    #[test]
+    fn check_scrobble_batch_chunked() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey");
+
+        let tracks = vec![("Artist", "Track", "Album"); 120];
+        let batch = ScrobbleBatch::from(tracks);
+
+        let _first = mock("POST", mockito::Matcher::Any)
+            .with_body(batch_response_body(50, &[(3, 1)]))
+            .expect(1)
+            .create();
+        let _second = mock("POST", mockito::Matcher::Any)
+            .with_status(500)
+            .expect(1)
+            .create();
+        let _third = mock("POST", mockito::Matcher::Any)
+            .with_body(batch_response_body(20, &[]))
+            .expect(1)
+            .create();
+
+        let response = scrobbler.scrobble_batch_chunked(&batch);
+
+        assert!(!response.is_success());
+        assert_eq!(response.ignored, vec![3]);
+        assert_eq!(response.accepted.len(), 69);
+        assert!(response.accepted.contains(&100) && response.accepted.contains(&119));
+        assert_eq!(response.scrobbles.len(), 70);
+        assert_eq!(response.failed_chunks.len(), 1);
+        assert_eq!(response.failed_chunks[0].indices, 50..100);
+        assert_eq!(response.failed_chunks[0].error.http_status(), Some(500));
+        assert_eq!(response.failed_indices(), (50..100).collect::<Vec<_>>());
+
+        let response = scrobbler.scrobble_batch_chunked(&ScrobbleBatch::new());
+        assert!(response.is_success());
+        assert!(response.scrobbles.is_empty());
+    }
+
+    #[test]
+    fn check_scrobble_or_queue_and_drain() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey");
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut queue = ScrobbleQueue::open(dir.path().join("queue.journal")).unwrap();
+
+        let _m = mock("POST", mockito::Matcher::Any).with_status(503).create();
+
+        let now = 100 * 24 * 60 * 60;
+        let mut expired = Scrobble::new("Artist", "Expired", "Album");
+        expired.with_timestamp(now - MAX_SCROBBLE_AGE_SECS - 1);
+        let mut second = Scrobble::new("Artist", "Second", "Album");
+        second.with_timestamp(now - 60);
+        let mut first = Scrobble::new("Artist", "First", "Album");
+        first.with_timestamp(now - 120);
+
+        for scrobble in &[&expired, &second, &first] {
+            assert!(scrobbler.scrobble_or_queue(scrobble, &mut queue).is_err());
+        }
+        assert_eq!(queue.len(), 3);
+
+        let report = scrobbler.drain_queue_at(&mut queue, now).unwrap();
+        let expired_reason = DropReason::Expired {
+            age_secs: MAX_SCROBBLE_AGE_SECS + 1,
+        };
+        assert_eq!(report.dropped, vec![(expired.clone(), expired_reason)]);
+        assert!(report.submitted.is_empty());
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.remaining, 2);
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("track[0]".into(), "First".into()),
+                mockito::Matcher::UrlEncoded("track[1]".into(), "Second".into()),
+            ]))
+            .with_body(batch_response_body(2, &[(1, 1)]))
+            .create();
+
+        let report = scrobbler.drain_queue_at(&mut queue, now).unwrap();
+        assert_eq!(report.submitted, vec![first]);
+        assert_eq!(report.dropped.len(), 1);
+        assert_eq!(report.dropped[0].0, second);
+        assert!(matches!(report.dropped[0].1, DropReason::Ignored { reason: IgnoreReason::ArtistIgnored, .. }));
+        assert!(report.errors.is_empty());
+        assert_eq!(report.remaining, 0);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn check_drain_queue_keeps_daily_limit_ignored() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey");
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut queue = ScrobbleQueue::open(dir.path().join("queue.journal")).unwrap();
+
+        let now = 100 * 24 * 60 * 60;
+        let tracks = ["Accepted", "Limited", "Ignored"];
+        for (offset, track) in (1..).zip(&tracks) {
+            let mut scrobble = Scrobble::new("Artist", track, "Album");
+            scrobble.with_timestamp(now - 600 + offset);
+            queue.push(&scrobble).unwrap();
+        }
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_body(batch_response_body(3, &[(1, 5), (2, 2)]))
+            .create();
+
+        let report = scrobbler.drain_queue_at(&mut queue, now).unwrap();
+        assert_eq!(report.submitted.len(), 1);
+        assert_eq!(report.submitted[0].track(), "Accepted");
+        assert_eq!(report.dropped.len(), 1);
+        assert_eq!(report.dropped[0].0.track(), "Ignored");
+        assert!(matches!(report.dropped[0].1, DropReason::Ignored { reason: IgnoreReason::TrackIgnored, .. }));
+        assert!(report.errors.is_empty());
+        assert_eq!(report.remaining, 1);
+
+        let pending: Vec<_> = queue.pending().into_iter().map(|(_, scrobble)| scrobble.track().to_owned()).collect();
+        assert_eq!(pending, vec!["Limited"]);
+    }
+
+    #[test]
+    fn check_drain_queue_skips_unknown_responses() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey");
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut queue = ScrobbleQueue::open(dir.path().join("queue.journal")).unwrap();
+
+        let now = 100 * 24 * 60 * 60;
+        let mut scrobble = Scrobble::new("Artist", "Track", "Album");
+        scrobble.with_timestamp(now - 600);
+        queue.push(&scrobble).unwrap();
+
+        // Last.fm should only list the one submitted scrobble
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_body(batch_response_body(3, &[]))
+            .create();
+
+        let report = scrobbler.drain_queue_at(&mut queue, now).unwrap();
+        assert_eq!(report.submitted.len(), 1);
+        assert!(report.errors.is_empty());
+        assert_eq!(report.remaining, 0);
+    }
+
+    #[test]
+    fn check_scrobbler_now_playing() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
 
         let _m = mock("POST", mockito::Matcher::Any)
             .with_body(
@@ -457,4 +1784,451 @@ mod tests {
         let resp = scrobbler.scrobble(&scrobble);
         assert!(resp.is_ok());
     }
+
+    #[test]
+    fn check_scrobbler_recent_tracks() {
+        let transport = RecordingTransport::new();
+        let scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport.clone());
+
+        transport.push_response(
+            200,
+            r##"{"recenttracks": {
+                "track": [
+                    {
+                        "artist": { "mbid": "", "#text": "foo floyd" },
+                        "album": { "mbid": "", "#text": "old bananas" },
+                        "name": "new bananas",
+                        "@attr": { "nowplaying": "true" }
+                    },
+                    {
+                        "artist": { "mbid": "", "#text": "foo floyd" },
+                        "album": { "mbid": "", "#text": "old bananas" },
+                        "name": "old bananas",
+                        "date": { "uts": "1600000200", "#text": "13 Sep 2020, 12:30" }
+                    }
+                ],
+                "@attr": { "user": "username", "page": "1", "perPage": "1", "totalPages": "2", "total": "2" }
+            }}"##,
+        );
+        transport.push_response(
+            200,
+            r##"{"recenttracks": {
+                "track": {
+                    "artist": { "mbid": "", "#text": "foo floyd" },
+                    "album": { "mbid": "", "#text": "" },
+                    "name": "older bananas",
+                    "date": { "uts": "1600000000", "#text": "13 Sep 2020, 12:26" }
+                },
+                "@attr": { "user": "username", "page": "2", "perPage": "1", "totalPages": "2", "total": "2" }
+            }}"##,
+        );
+
+        let mut query = RecentTracksQuery::new("username");
+        query.with_from(1_500_000_000).with_limit(1);
+
+        let mut tracks = scrobbler.recent_tracks(&query);
+        assert_eq!(tracks.total_pages(), None);
+        // Pages are only requested as they are reached
+        assert!(tracks.next().unwrap().unwrap().now_playing);
+        assert_eq!(transport.requests().len(), 1);
+        assert_eq!(tracks.total_pages(), Some(2));
+
+        let rest: Vec<_> = tracks.map(|track| track.unwrap().timestamp).collect();
+        assert_eq!(rest, vec![Some(1_600_000_200), Some(1_600_000_000)]);
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        let params = &requests[1].params;
+        assert_eq!(requests[1].method, HttpMethod::Get);
+        assert_eq!(params["method"], "user.getRecentTracks");
+        assert_eq!(params["api_key"], "api_key");
+        assert_eq!(params["user"], "username");
+        assert_eq!(params["page"], "2");
+        assert_eq!(params["from"], "1500000000");
+        assert_eq!(params["limit"], "1");
+        // Read requests are unsigned
+        assert!(!params.contains_key("api_sig"));
+        assert!(!params.contains_key("extended"));
+
+        // A failed page ends the iteration after yielding its error
+        transport.push_response(500, "Internal Server Error");
+        let results: Vec<_> = scrobbler.recent_tracks(&query).collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().unwrap_err().http_status(), Some(500));
+    }
+
+    #[test]
+    fn check_scrobbler_track_lookup() {
+        let transport = RecordingTransport::new();
+        let scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport.clone());
+
+        transport.push_response(
+            200,
+            r##"{"track": {
+                "name": "old bananas",
+                "mbid": "a3e8c5b2-0d2c-4a8e-9a7e-6c1f4f1b2c3d",
+                "url": "https://www.last.fm/music/foo+floyd/_/old+bananas",
+                "duration": "241000",
+                "listeners": "5123456789",
+                "playcount": "42",
+                "artist": { "name": "foo floyd", "mbid": "", "url": "https://www.last.fm/music/foo+floyd" },
+                "album": {
+                    "artist": "foo floyd and the fruit flies",
+                    "title": "old bananas",
+                    "mbid": "",
+                    "url": "https://www.last.fm/music/foo+floyd/old+bananas",
+                    "image": [ { "size": "small", "#text": "https://example.com/34s.png" } ]
+                },
+                "userplaycount": "3",
+                "userloved": "1",
+                "toptags": { "tag": [ { "name": "fruit rock", "url": "https://www.last.fm/tag/fruit+rock" } ] }
+            }}"##,
+        );
+
+        let mut options = LookupOptions::new();
+        options.with_autocorrect(true).with_username("username");
+
+        let info = scrobbler.track_info("foo floyd", "old bananas", &options).unwrap();
+        assert_eq!(info.listeners, 5_123_456_789);
+        assert_eq!(info.user_playcount, Some(3));
+        assert_eq!(info.user_loved, Some(true));
+        assert_eq!(info.tags[0].name, "fruit rock");
+
+        let params = transport.last_request().unwrap().params;
+        assert_eq!(params["method"], "track.getInfo");
+        assert_eq!(params["artist"], "foo floyd");
+        assert_eq!(params["track"], "old bananas");
+        assert_eq!(params["autocorrect"], "1");
+        assert_eq!(params["username"], "username");
+
+        let scrobble = info.to_scrobble();
+        assert_eq!(scrobble.album(), "old bananas");
+        assert_eq!(scrobble.album_artist(), Some("foo floyd and the fruit flies"));
+        assert_eq!(scrobble.duration(), Some(241));
+        assert_eq!(scrobble.mbid(), Some("a3e8c5b2-0d2c-4a8e-9a7e-6c1f4f1b2c3d"));
+
+        // Values already set on a scrobble are kept
+        let mut track = Scrobble::new("foo floyd", "old bananas", "");
+        track.with_duration(240).with_timestamp(1_600_000_000);
+        let enriched = info.enrich(&track);
+        assert_eq!(enriched.duration(), Some(240));
+        assert_eq!(enriched.timestamp(), Some(1_600_000_000));
+        assert_eq!(enriched.album(), "old bananas");
+
+        transport.push_response(404, r#"{"error": 6, "message": "Track not found"}"#);
+        let err = scrobbler.track_info("foo floyd", "no bananas", &LookupOptions::new()).unwrap_err();
+        assert_eq!(err.api_error_code(), Some(6));
+    }
+
+    #[test]
+    fn check_scrobbler_artist_album_lookups() {
+        let transport = RecordingTransport::new();
+        let scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport.clone());
+
+        transport.push_response(
+            200,
+            r#"{"artist": {
+                "name": "foo floyd",
+                "mbid": "",
+                "url": "https://www.last.fm/music/foo+floyd",
+                "image": [],
+                "stats": { "listeners": "1000", "playcount": "25000" },
+                "tags": ""
+            }}"#,
+        );
+
+        let info = scrobbler.artist_info("foo floyd", &LookupOptions::new()).unwrap();
+        assert_eq!(info.stats.playcount, 25_000);
+        assert_eq!(info.stats.user_playcount, None);
+        assert!(info.tags.is_empty());
+        let params = transport.last_request().unwrap().params;
+        assert_eq!(params["method"], "artist.getInfo");
+        assert!(!params.contains_key("autocorrect"));
+
+        transport.push_response(
+            200,
+            r#"{"album": {
+                "name": "old bananas",
+                "artist": "foo floyd and the fruit flies",
+                "mbid": "",
+                "url": "https://www.last.fm/music/foo+floyd/old+bananas",
+                "image": [],
+                "listeners": "1000",
+                "playcount": "25000",
+                "tracks": { "track": [
+                    {
+                        "name": "Old Bananas",
+                        "url": "https://www.last.fm/music/foo+floyd/_/old+bananas",
+                        "duration": 241,
+                        "@attr": { "rank": 1 },
+                        "artist": { "name": "foo floyd", "mbid": "", "url": "" }
+                    },
+                    {
+                        "name": "new bananas",
+                        "url": "https://www.last.fm/music/foo+floyd/_/new+bananas",
+                        "duration": null,
+                        "@attr": { "rank": 2 },
+                        "artist": { "name": "foo floyd", "mbid": "", "url": "" }
+                    }
+                ] }
+            }}"#,
+        );
+
+        let info = scrobbler.album_info("foo floyd", "old bananas", &LookupOptions::new()).unwrap();
+        assert_eq!(transport.last_request().unwrap().params["album"], "old bananas");
+
+        let batch = info.to_scrobble_batch();
+        assert_eq!(batch.len(), 2);
+        let second = batch.iter().nth(1).unwrap();
+        assert_eq!(second.track_number(), Some(2));
+        assert_eq!(second.duration(), None);
+        assert_eq!(second.album_artist(), Some("foo floyd and the fruit flies"));
+
+        let enriched = info.enrich(&Scrobble::new("foo floyd", "old bananas", ""));
+        assert_eq!(enriched.track_number(), Some(1));
+        assert_eq!(enriched.duration(), Some(241));
+    }
+
+    #[test]
+    fn check_scrobbler_corrections() {
+        let transport = RecordingTransport::new();
+        let scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport.clone());
+
+        transport.push_response(
+            200,
+            r#"{"corrections": {"correction": {
+                "track": {
+                    "name": "Old Bananas",
+                    "url": "https://www.last.fm/music/Foo+Floyd/_/Old+Bananas",
+                    "artist": { "name": "Foo Floyd", "mbid": "", "url": "https://www.last.fm/music/Foo+Floyd" }
+                },
+                "@attr": { "index": "0", "artistcorrected": "0", "trackcorrected": "1" }
+            }}}"#,
+        );
+
+        let mut track = Scrobble::new("foo floyd", "old bananas", "old bananas");
+        track.with_timestamp(1_600_000_000);
+
+        let correction = scrobbler.track_correction("foo floyd", "old bananas").unwrap().unwrap();
+        let params = transport.last_request().unwrap().params;
+        assert_eq!(params["method"], "track.getCorrection");
+        assert_eq!(params["artist"], "foo floyd");
+        assert_eq!(params["track"], "old bananas");
+
+        // Only fields flagged as corrected are changed
+        let corrected = correction.apply(&track);
+        assert_eq!(corrected.scrobble.artist(), "foo floyd");
+        assert_eq!(corrected.scrobble.track(), "Old Bananas");
+        assert_eq!(corrected.scrobble.timestamp(), Some(1_600_000_000));
+        assert_eq!(corrected.changes.len(), 1);
+        assert_eq!(corrected.change(ScrobbleField::Track).unwrap().from, "old bananas");
+
+        transport.push_response(
+            200,
+            r#"{"corrections": {"correction": {
+                "artist": { "name": "Foo Floyd", "mbid": "", "url": "https://www.last.fm/music/Foo+Floyd" },
+                "@attr": { "index": "0" }
+            }}}"#,
+        );
+
+        let corrected = scrobbler.artist_correction("foo floyd").unwrap().unwrap().apply(&track);
+        assert_eq!(transport.last_request().unwrap().params["method"], "artist.getCorrection");
+        assert_eq!(corrected.scrobble.artist(), "Foo Floyd");
+        assert!(corrected.change(ScrobbleField::Track).is_none());
+
+        transport.push_response(200, "{\"corrections\": \"\\n            \"}");
+        assert!(scrobbler.artist_correction("Foo Floyd").unwrap().is_none());
+    }
+
+    #[test]
+    fn check_scrobble_response_corrections() {
+        let transport = RecordingTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport.clone());
+        scrobbler.authenticate_with_session_key("seshkey");
+
+        let body = r##"{"scrobbles": {
+                "scrobble": [
+                    {
+                        "artist": { "corrected": "0", "#text": "foo floyd" },
+                        "album": { "corrected": "0", "#text": "old bananas" },
+                        "albumArtist": { "corrected": "0", "#text": "" },
+                        "track": { "corrected": "0", "#text": "old bananas" },
+                        "timestamp": "1600000000",
+                        "ignoredMessage": { "code": "0", "#text": "" }
+                    },
+                    {
+                        "artist": { "corrected": "1", "#text": "Björk" },
+                        "album": { "corrected": "0", "#text": "Homogenic" },
+                        "albumArtist": { "corrected": "1", "#text": "Björk" },
+                        "track": { "corrected": "0", "#text": "Jóga" },
+                        "timestamp": "1600000300",
+                        "ignoredMessage": { "code": "0", "#text": "" }
+                    }
+                ],
+                "@attr": { "accepted": 2, "ignored": 0 }
+            }}"##;
+        transport.push_response(200, body);
+
+        let mut first = Scrobble::new("foo floyd", "old bananas", "old bananas");
+        first.with_timestamp(1_600_000_000);
+        let mut second = Scrobble::new("Bjork", "Jóga", "Homogenic");
+        second.with_timestamp(1_600_000_300).with_duration(305);
+        let batch = ScrobbleBatch::from(vec![first, second]);
+
+        let corrections = scrobbler.scrobble_batch(&batch).unwrap().corrections(&batch);
+        assert_eq!(corrections.len(), 2);
+        assert!(!corrections[0].1.is_changed());
+
+        let (index, corrected) = &corrections[1];
+        assert_eq!(*index, 1);
+        assert_eq!(corrected.scrobble.artist(), "Björk");
+        assert_eq!(corrected.scrobble.album_artist(), Some("Björk"));
+        assert_eq!(corrected.scrobble.duration(), Some(305));
+        assert_eq!(corrected.change(ScrobbleField::Artist).unwrap().from, "Bjork");
+        assert_eq!(corrected.changes.len(), 2);
+
+        // Chunked responses keep the original batch indices
+        transport.push_response(200, body);
+        let corrections = scrobbler.scrobble_batch_chunked(&batch).corrections(&batch);
+        assert_eq!(corrections.len(), 2);
+        assert_eq!(corrections[1].0, 1);
+        assert_eq!(corrections[1].1.scrobble.artist(), "Björk");
+    }
+
+    #[test]
+    fn check_scrobbler_deduplication() {
+        let transport = RecordingTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport.clone());
+        scrobbler.authenticate_with_session_key("seshkey");
+
+        transport.push_response(
+            200,
+            r##"{"recenttracks": {
+                "track": [
+                    {
+                        "artist": { "mbid": "", "#text": "foo floyd" },
+                        "album": { "mbid": "", "#text": "old bananas" },
+                        "name": "new bananas",
+                        "@attr": { "nowplaying": "true" }
+                    },
+                    {
+                        "artist": { "mbid": "", "#text": "foo floyd" },
+                        "album": { "mbid": "", "#text": "old bananas" },
+                        "name": "old bananas",
+                        "date": { "uts": "1600000000", "#text": "13 Sep 2020, 12:26" }
+                    }
+                ],
+                "@attr": { "user": "username", "page": "1", "perPage": "50", "totalPages": "1", "total": "1" }
+            }}"##,
+        );
+
+        let mut filter = DuplicateFilter::new();
+        let seeded = scrobbler.seed_duplicate_filter(&mut filter, &RecentTracksQuery::new("username")).unwrap();
+        assert_eq!(seeded, 1);
+
+        // Duplicates are dropped without a request
+        let mut replayed = Scrobble::new("foo floyd", "old bananas", "old bananas");
+        replayed.with_timestamp(1_600_000_010);
+        assert!(scrobbler.scrobble_deduplicated(&replayed, &mut filter).unwrap().is_none());
+        assert_eq!(transport.requests().len(), 1);
+
+        let plays = [
+            ("old bananas", 1_600_000_000),
+            ("new bananas", 1_600_000_300),
+            ("new bananas", 1_600_000_300),
+            ("older bananas", 1_600_000_600),
+        ];
+        let batch: ScrobbleBatch = plays
+            .iter()
+            .map(|(track, timestamp)| {
+                let mut scrobble = Scrobble::new("foo floyd", track, "old bananas");
+                scrobble.with_timestamp(*timestamp);
+                scrobble
+            })
+            .collect();
+        transport.push_response(200, &batch_response_body(2, &[(1, 1)]));
+
+        let response = scrobbler.scrobble_batch_deduplicated(&batch, &mut filter).unwrap();
+        assert_eq!(response.duplicates, vec![0, 2]);
+        assert_eq!(response.submitted, vec![1, 3]);
+        assert_eq!(response.response.ignored, vec![1]);
+        assert_eq!(response.batch_index(1), Some(3));
+
+        let params = transport.last_request().unwrap().params;
+        assert_eq!(params["track[0]"], "new bananas");
+        assert_eq!(params["track[1]"], "older bananas");
+        assert!(!params.contains_key("track[2]"));
+
+        // Only scrobbles accepted by Last.fm are added to the filter
+        assert!(filter.is_duplicate(batch.iter().nth(1).unwrap()));
+        assert!(!filter.is_duplicate(batch.iter().nth(3).unwrap()));
+        assert_eq!(filter.len(), 2);
+    }
+
+    #[test]
+    fn check_scrobbler_retries() {
+        let transport = RecordingTransport::new();
+        let retries = Arc::new(Mutex::new(Vec::new()));
+        let slept = Arc::new(Mutex::new(Vec::new()));
+        let (event_retries, sleeper_slept) = (Arc::clone(&retries), Arc::clone(&slept));
+
+        let mut policy = RetryPolicy::new();
+        policy.with_max_attempts(3).with_jitter(0.0);
+        let mut scrobbler = Scrobbler::builder("api_key", "api_secret")
+            .transport(transport.clone())
+            .retry_policy(policy)
+            .on_retry(move |event| {
+                let retry = (event.method.to_owned(), event.attempt, event.error.api_error_code(), event.delay);
+                event_retries.lock().unwrap().push(retry);
+            })
+            .retry_clock(|| UNIX_EPOCH)
+            .retry_sleeper(move |delay| sleeper_slept.lock().unwrap().push(delay))
+            .build();
+        scrobbler.authenticate_with_session_key("seshkey");
+
+        let unavailable = r#"{"error": 16, "message": "There was a temporary error processing your request."}"#;
+        transport.push_response(503, unavailable);
+        transport.push_response(503, "Service Unavailable");
+        transport.push_response(
+            200,
+            r#"{"nowplaying": {
+                "artist": [ "0", "foo floyd" ],
+                "album": [ "0", "old bananas" ],
+                "albumArtist": [ "0", ""],
+                "track": [ "0", "old bananas"],
+                "timestamp": "2019-10-04 13:23:40"
+            }}"#,
+        );
+
+        let scrobble = Scrobble::new("foo floyd", "old bananas", "old bananas");
+        assert!(scrobbler.now_playing(&scrobble).is_ok());
+        assert_eq!(transport.requests().len(), 3);
+        assert_eq!(
+            *retries.lock().unwrap(),
+            vec![
+                ("track.updateNowPlaying".to_owned(), 1, Some(16), Duration::from_secs(1)),
+                ("track.updateNowPlaying".to_owned(), 2, None, Duration::from_secs(2)),
+            ]
+        );
+        assert_eq!(*slept.lock().unwrap(), vec![Duration::from_secs(1), Duration::from_secs(2)]);
+
+        // Other API errors, and server or transport failures of writes, are not retried
+        transport.push_response(403, r#"{"error": 9, "message": "Invalid session key"}"#);
+        assert_eq!(scrobbler.scrobble(&scrobble).unwrap_err().api_error_code(), Some(9));
+        transport.push_response(502, "Bad Gateway");
+        let result = scrobbler.scrobble(&scrobble);
+        assert!(matches!(result, Err(ScrobblerError::HttpStatus { status: 502, .. })));
+        transport.push_result(Err(ScrobblerError::Transport("Connection reset".to_owned())));
+        assert!(scrobbler.love(&scrobble).is_err());
+        assert_eq!(transport.requests().len(), 6);
+
+        // Reads are retried after transport failures, up to the maximum number of attempts
+        for _ in 0..3 {
+            transport.push_result(Err(ScrobblerError::Transport("Connection reset".to_owned())));
+        }
+        let result = scrobbler.track_info("foo floyd", "old bananas", &LookupOptions::new());
+        assert!(matches!(result, Err(ScrobblerError::Transport(_))));
+        assert_eq!(transport.requests().len(), 9);
+        assert_eq!(retries.lock().unwrap().len(), 4);
+    }
 }
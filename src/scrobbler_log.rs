@@ -0,0 +1,228 @@
+// Importer for the `.scrobbler.log` files written by Rockbox & other portable players
+use std::fs;
+use std::path::Path;
+
+use crate::error::ScrobblerError;
+use crate::import::{ImportReport, RejectReason};
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+const HEADER: &str = "#AUDIOSCROBBLER/";
+const TIMEZONE_UTC: &str = "#TZ/UTC";
+const MIN_FIELDS: usize = 7;
+const MAX_FIELDS: usize = 8;
+
+/// Parser for the `.scrobbler.log` files written by Rockbox and other portable music players, following the
+/// [Audioscrobbler portable player format](https://web.archive.org/web/20170107015006/http://www.audioscrobbler.net/wiki/Portable_Player_Logging).
+///
+/// Each line of the log is a tab-separated entry of artist, album, title, track number, length (seconds), rating
+/// (`L` listened, `S` skipped), timestamp & MusicBrainz track ID. Tracks rated `S` are not imported. Timestamps are
+/// UNIX timestamps if the log's header says `#TZ/UTC`; otherwise they are in the player's local time, and converted
+/// with the offset set by [`ScrobblerLog::with_utc_offset`].
+///
+/// # Usage
+/// ```ignore
+/// let mut log = ScrobblerLog::new();
+/// log.with_utc_offset(2 * 60 * 60); // Player clock set to UTC+2
+///
+/// let report = log.read("/media/player/.scrobbler.log")?;
+/// for rejected in &report.rejected {
+///     println!("{rejected}");
+/// }
+///
+/// let response = scrobbler.scrobble_batch_chunked(&report.batch);
+/// for index in response.failed_indices() {
+///     println!("Line {:?} was not submitted", report.line(index));
+/// }
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct ScrobblerLog {
+    utc_offset: i64,
+}
+
+impl ScrobblerLog {
+    /// Creates a log importer treating local time as UTC
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the offset from UTC, in seconds, of the player's clock. Only used for logs in local time (a `#TZ/UNKNOWN`
+    /// header); defaults to 0, treating local time as UTC.
+    pub fn with_utc_offset(&mut self, utc_offset: i64) -> &mut Self {
+        self.utc_offset = utc_offset;
+        self
+    }
+
+    /// Reads & parses the log file at the given path
+    ///
+    /// # Errors
+    /// Returns a `ScrobblerError::Io` error if the file can't be read, otherwise fails in the same cases as
+    /// `ScrobblerLog::parse`.
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> Result<ImportReport> {
+        let contents = fs::read_to_string(path)?;
+        self.parse(&contents)
+    }
+
+    /// Parses the contents of a log file. Entries which can't be imported are listed in the report's `rejected`
+    /// lines.
+    ///
+    /// # Errors
+    /// Returns a `ScrobblerError::Import` error if the log has no `#AUDIOSCROBBLER/` header.
+    pub fn parse(&self, contents: &str) -> Result<ImportReport> {
+        let contents = contents.trim_start_matches('\u{feff}');
+        if !contents.starts_with(HEADER) {
+            return Err(ScrobblerError::Import(
+                "Missing #AUDIOSCROBBLER header, not a .scrobbler.log file".to_owned(),
+            ));
+        }
+
+        let mut report = ImportReport::new();
+        let mut scrobbles = Vec::new();
+        let mut utc_offset = self.utc_offset;
+
+        for (i, line) in contents.lines().enumerate() {
+            let line_number = i + 1;
+            if line.starts_with('#') {
+                if line.trim_end() == TIMEZONE_UTC {
+                    utc_offset = 0;
+                }
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match Self::parse_entry(line, utc_offset) {
+                Ok(scrobble) => {
+                    scrobbles.push(scrobble);
+                    report.lines.push(line_number);
+                }
+                Err(reason) => report.reject(line_number, reason),
+            }
+        }
+
+        report.batch = ScrobbleBatch::from(scrobbles);
+        Ok(report)
+    }
+
+    fn parse_entry(line: &str, utc_offset: i64) -> std::result::Result<Scrobble, RejectReason> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < MIN_FIELDS || fields.len() > MAX_FIELDS {
+            return Err(RejectReason::FieldCount {
+                expected: MAX_FIELDS,
+                found: fields.len(),
+            });
+        }
+
+        let (artist, album, title) = (fields[0], fields[1], fields[2]);
+        match fields[5] {
+            "L" => {}
+            "S" => return Err(RejectReason::Skipped),
+            rating => return Err(invalid("rating", rating)),
+        }
+        if artist.is_empty() {
+            return Err(RejectReason::MissingField("artist"));
+        }
+        if title.is_empty() {
+            return Err(RejectReason::MissingField("title"));
+        }
+
+        let duration: u64 = fields[4].parse().map_err(|_| invalid("length", fields[4]))?;
+        let timestamp = fields[6]
+            .parse::<i64>()
+            .ok()
+            .and_then(|timestamp| timestamp.checked_sub(utc_offset))
+            .and_then(|timestamp| u64::try_from(timestamp).ok())
+            .filter(|timestamp| *timestamp > 0)
+            .ok_or_else(|| invalid("timestamp", fields[6]))?;
+
+        let mut scrobble = Scrobble::new(artist, title, album);
+        scrobble.with_timestamp(timestamp);
+        if duration > 0 {
+            scrobble.with_duration(duration);
+        }
+        if !fields[3].is_empty() {
+            let track_number = fields[3].parse().map_err(|_| invalid("track number", fields[3]))?;
+            scrobble.with_track_number(track_number);
+        }
+        if let Some(mbid) = fields.get(7).filter(|mbid| !mbid.is_empty()) {
+            scrobble.with_mbid(mbid);
+        }
+
+        Ok(scrobble)
+    }
+}
+
+fn invalid(field: &'static str, value: &str) -> RejectReason {
+    RejectReason::InvalidField {
+        field,
+        value: value.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "#AUDIOSCROBBLER/1.1\n\
+        #TZ/UNKNOWN\n\
+        #CLIENT/Rockbox sansaclipplus $Revision$\n\
+        foo floyd\told bananas\told bananas\t1\t241\tL\t1600007200\t0a1b2c3d-0000-1111-2222-333344445555\n\
+        foo floyd\told bananas\tnew bananas\t2\t180\tS\t1600007441\t\n\
+        foo floyd\t\tloose bananas\t\t200\tL\t1600007700\n\
+        foo floyd\told bananas\n\
+        \told bananas\tno artist\t3\t100\tL\t1600008000\t\n\
+        foo floyd\told bananas\tbad length\t4\tlong\tL\t1600008100\t\n";
+
+    #[test]
+    fn check_parse_local_time_log() {
+        let mut log = ScrobblerLog::new();
+        log.with_utc_offset(7200);
+        let report = log.parse(LOG).unwrap();
+
+        assert_eq!(report.batch.len(), 2);
+        assert_eq!(report.lines, vec![4, 6]);
+        assert_eq!(report.line(1), Some(6));
+
+        let first = report.batch.iter().next().unwrap();
+        assert_eq!(first.artist(), "foo floyd");
+        assert_eq!(first.track(), "old bananas");
+        assert_eq!(first.timestamp(), Some(1_600_000_000));
+        assert_eq!(first.duration(), Some(241));
+        assert_eq!(first.track_number(), Some(1));
+        assert_eq!(first.mbid(), Some("0a1b2c3d-0000-1111-2222-333344445555"));
+
+        let second = report.batch.iter().nth(1).unwrap();
+        assert_eq!(second.album(), "");
+        assert_eq!(second.track_number(), None);
+        assert_eq!(second.mbid(), None);
+
+        let reasons: Vec<_> = report.rejected.iter().map(|rejected| (rejected.line, &rejected.reason)).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (5, &RejectReason::Skipped),
+                (7, &RejectReason::FieldCount { expected: 8, found: 2 }),
+                (8, &RejectReason::MissingField("artist")),
+                (9, &RejectReason::InvalidField { field: "length", value: "long".to_owned() }),
+            ]
+        );
+        assert_eq!(report.rejected[3].to_string(), "Line 9: Invalid length \"long\"");
+    }
+
+    #[test]
+    fn check_parse_utc_log() {
+        let contents = LOG.replace("#TZ/UNKNOWN", "#TZ/UTC");
+        let mut log = ScrobblerLog::new();
+        // The offset is only applied to logs in local time
+        log.with_utc_offset(7200);
+
+        let report = log.parse(&contents).unwrap();
+        assert_eq!(report.batch.iter().next().unwrap().timestamp(), Some(1_600_007_200));
+
+        let err = log.parse("foo floyd\told bananas\n").unwrap_err();
+        assert!(matches!(err, ScrobblerError::Import(_)));
+    }
+}
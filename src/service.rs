@@ -0,0 +1,138 @@
+// Common interface of the scrobbling service clients
+use crate::error::ScrobblerError;
+use crate::listenbrainz::ListenBrainz;
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::scrobbler::Scrobbler;
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+/// A service that song plays can be submitted to, implemented by both [`Scrobbler`] (Last.fm and compatible
+/// services) and [`ListenBrainz`].
+///
+/// Lets player code submit [`Scrobble`]s without caring which service is on the other end. Service-specific
+/// responses are discarded; use the clients' own methods to inspect them.
+///
+/// # Usage
+/// ```ignore
+/// let services: Vec<Box<dyn ScrobbleService>> = vec![Box::new(scrobbler), Box::new(listenbrainz)];
+///
+/// for service in &services {
+///     service.scrobble(&song)?;
+/// }
+/// ```
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`ListenBrainz`]: struct.ListenBrainz.html
+/// [`Scrobble`]: struct.Scrobble.html
+pub trait ScrobbleService {
+    /// Registers the given track as the user's currently playing track
+    ///
+    /// # Errors
+    /// Returns the client's error if the request fails or is rejected by the service.
+    fn now_playing(&self, scrobble: &Scrobble) -> Result<()>;
+
+    /// Registers a play of the given track. The current time is used if the scrobble has no timestamp.
+    ///
+    /// # Errors
+    /// Returns the client's error if the request fails or is rejected by the service.
+    fn scrobble(&self, scrobble: &Scrobble) -> Result<()>;
+
+    /// Registers plays of a collection of tracks. Batches larger than the service accepts in one request are split
+    /// into several requests, sent in order.
+    ///
+    /// Tracks the service received but chose to ignore (for example Last.fm's daily scrobble limit) are not
+    /// reported as errors; use the clients' own batch methods, such as [`Scrobbler::scrobble_batch_chunked`], to
+    /// inspect them.
+    ///
+    /// # Errors
+    /// Returns the error of the first failed request; later requests are not sent. Tracks in the requests before it
+    /// have already been submitted.
+    ///
+    /// [`Scrobbler::scrobble_batch_chunked`]: struct.Scrobbler.html#method.scrobble_batch_chunked
+    fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<()>;
+}
+
+impl ScrobbleService for Scrobbler {
+    fn now_playing(&self, scrobble: &Scrobble) -> Result<()> {
+        Scrobbler::now_playing(self, scrobble).map(|_| ())
+    }
+
+    fn scrobble(&self, scrobble: &Scrobble) -> Result<()> {
+        Scrobbler::scrobble(self, scrobble).map(|_| ())
+    }
+
+    fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<()> {
+        self.scrobble_chunked(batch)
+    }
+}
+
+impl ScrobbleService for ListenBrainz {
+    fn now_playing(&self, scrobble: &Scrobble) -> Result<()> {
+        self.playing_now(scrobble)
+    }
+
+    fn scrobble(&self, scrobble: &Scrobble) -> Result<()> {
+        self.submit_single(scrobble)
+    }
+
+    fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<()> {
+        self.import_chunked(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::RecordingTransport;
+
+    #[test]
+    fn check_scrobble_services() {
+        let last_fm = RecordingTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", last_fm.clone());
+        scrobbler.authenticate_with_session_key("key");
+
+        let listenbrainz = RecordingTransport::new();
+        let services: Vec<Box<dyn ScrobbleService>> = vec![
+            Box::new(scrobbler),
+            Box::new(ListenBrainz::with_transport("user-token", listenbrainz.clone())),
+        ];
+
+        last_fm.push_response(
+            200,
+            r#"{"nowplaying": {
+                "artist": [ "0", "foo floyd and the fruit flies" ],
+                "album": [ "1", "old bananas" ],
+                "albumArtist": [ "0", "foo floyd"],
+                "track": [ "1", "old bananas"],
+                "timestamp": "2019-10-04 13:23:40"
+            }}"#,
+        );
+        listenbrainz.push_response(200, r#"{"status": "ok"}"#);
+
+        let scrobble = Scrobble::new("foo floyd and the fruit flies", "old bananas", "old bananas");
+        for service in &services {
+            assert!(service.now_playing(&scrobble).is_ok());
+            assert!(matches!(
+                service.scrobble_batch(&ScrobbleBatch::new()),
+                Err(ScrobblerError::InvalidBatch(_))
+            ));
+        }
+
+        assert_eq!(last_fm.last_request().unwrap().params["method"], "track.updateNowPlaying");
+        assert!(listenbrainz.last_request().unwrap().body.unwrap().contains("playing_now"));
+    }
+
+    #[test]
+    fn check_scrobble_batch_stops_at_failed_chunk() {
+        let last_fm = RecordingTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", last_fm.clone());
+        scrobbler.authenticate_with_session_key("key");
+
+        last_fm.push_response(400, r#"{"error": 6, "message": "Invalid parameters"}"#);
+
+        let batch = ScrobbleBatch::from(vec![("Artist", "Track", "Album"); 120]);
+        let err = ScrobbleService::scrobble_batch(&scrobbler, &batch).unwrap_err();
+        assert_eq!(err.api_error_code(), Some(6));
+        assert_eq!(last_fm.requests().len(), 1);
+    }
+}
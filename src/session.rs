@@ -0,0 +1,254 @@
+// Persistable Last.fm user sessions
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ScrobblerError;
+use crate::models::responses::SessionResponse;
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+/// An authenticated Last.fm user session.
+///
+/// Holds the session key used to authenticate API requests, along with the username and subscriber status reported
+/// by Last.fm when the session was created. Session keys are valid indefinitely, so a `Session` can be saved (it
+/// implements serde's `Serialize`/`Deserialize`) and restored later with [`Scrobbler::authenticate_with_session`],
+/// avoiding the need to keep user passwords around.
+///
+/// The username is unknown (`None`) for sessions created from a bare session key via
+/// [`Scrobbler::authenticate_with_session_key`].
+///
+/// [`Scrobbler::authenticate_with_session`]: struct.Scrobbler.html#method.authenticate_with_session
+/// [`Scrobbler::authenticate_with_session_key`]: struct.Scrobbler.html#method.authenticate_with_session_key
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Session {
+    pub key: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub subscriber: bool,
+}
+
+impl Session {
+    /// Creates a `Session` from a bare session key, with no known username
+    #[must_use]
+    pub fn from_key(key: &str) -> Self {
+        Self {
+            key: key.to_owned(),
+            name: None,
+            subscriber: false,
+        }
+    }
+}
+
+impl From<&SessionResponse> for Session {
+    fn from(response: &SessionResponse) -> Self {
+        Self {
+            key: response.key.clone(),
+            name: Some(response.name.clone()),
+            subscriber: response.subscriber != 0,
+        }
+    }
+}
+
+/// Storage backend for persisting a [`Session`] between runs of an application.
+///
+/// Used with [`Scrobbler::save_session`] and [`Scrobbler::restore_session`]. [`FileSessionStore`] is provided as a
+/// file-backed implementation; implement this trait to store sessions elsewhere (a keyring, a database...).
+///
+/// [`Session`]: struct.Session.html
+/// [`Scrobbler::save_session`]: struct.Scrobbler.html#method.save_session
+/// [`Scrobbler::restore_session`]: struct.Scrobbler.html#method.restore_session
+/// [`FileSessionStore`]: struct.FileSessionStore.html
+pub trait SessionStore {
+    /// Loads the stored session. Returns `Ok(None)` if no session has been stored.
+    ///
+    /// # Errors
+    /// Returns an error if the stored session exists but can't be read or decoded.
+    fn load(&self) -> Result<Option<Session>>;
+
+    /// Stores the given session, replacing any previously stored session
+    ///
+    /// # Errors
+    /// Returns an error if the session can't be written.
+    fn save(&self, session: &Session) -> Result<()>;
+
+    /// Removes any stored session
+    ///
+    /// # Errors
+    /// Returns an error if the stored session exists but can't be removed.
+    fn clear(&self) -> Result<()>;
+}
+
+/// A [`SessionStore`] that keeps the session in a JSON file.
+///
+/// Session keys are credentials, so on Unix platforms the file is created readable & writable by its owner only
+/// (mode `0600`). Writes are atomic: the session is written to a temporary file in the same directory, which is then
+/// renamed over the existing file, so a crash mid-write never leaves a corrupted session behind.
+///
+/// # Usage
+/// ```ignore
+/// let store = FileSessionStore::new("/home/user/.config/my-player/lastfm-session.json");
+/// let mut scrobbler = Scrobbler::new(api_key, api_secret);
+/// if !scrobbler.restore_session(&store)? {
+///     scrobbler.authenticate_with_password(username, password)?;
+///     scrobbler.save_session(&store)?;
+/// }
+/// ```
+///
+/// [`SessionStore`]: trait.SessionStore.html
+#[derive(Clone, Debug)]
+pub struct FileSessionStore {
+    path: PathBuf,
+}
+
+impl FileSessionStore {
+    /// Creates a store backed by the file at `path`. The file is not touched until the store is used.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+        }
+    }
+
+    /// Returns the path of the session file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn temp_path(&self) -> PathBuf {
+        let mut file_name = self.path.file_name().unwrap_or_default().to_owned();
+        file_name.push(".tmp");
+        self.path.with_file_name(file_name)
+    }
+}
+
+impl SessionStore for FileSessionStore {
+    fn load(&self) -> Result<Option<Session>> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save(&self, session: &Session) -> Result<()> {
+        let contents = serde_json::to_vec_pretty(session)?;
+        let temp_path = self.temp_path();
+
+        // A temp file left by an interrupted save may have looser permissions, and `mode` only applies to new files
+        match fs::remove_file(&temp_path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options.open(&temp_path)?;
+        file.write_all(&contents)?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_session_from_response() {
+        let response = SessionResponse {
+            key: "key".into(),
+            subscriber: 1,
+            name: "foo floyd".into(),
+        };
+
+        let session = Session::from(&response);
+        assert_eq!(session.key, "key");
+        assert_eq!(session.name.as_deref(), Some("foo floyd"));
+        assert!(session.subscriber);
+
+        let session = Session::from_key("key");
+        assert_eq!(session.name, None);
+        assert!(!session.subscriber);
+    }
+
+    #[test]
+    fn check_file_session_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileSessionStore::new(dir.path().join("session.json"));
+        assert_eq!(store.load().unwrap(), None);
+
+        let session = Session {
+            key: "key".into(),
+            name: Some("foo floyd".into()),
+            subscriber: false,
+        };
+        store.save(&session).unwrap();
+        assert_eq!(store.load().unwrap(), Some(session.clone()));
+        assert!(!store.temp_path().exists());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(store.path()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let updated = Session::from_key("new key");
+        store.save(&updated).unwrap();
+        assert_eq!(store.load().unwrap(), Some(updated));
+
+        store.clear().unwrap();
+        assert_eq!(store.load().unwrap(), None);
+        store.clear().unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn check_file_session_store_replaces_stale_temp_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileSessionStore::new(dir.path().join("session.json"));
+
+        fs::write(store.temp_path(), "stale").unwrap();
+        fs::set_permissions(store.temp_path(), fs::Permissions::from_mode(0o644)).unwrap();
+
+        let session = Session::from_key("key");
+        store.save(&session).unwrap();
+        assert_eq!(store.load().unwrap(), Some(session));
+        assert!(!store.temp_path().exists());
+
+        let mode = fs::metadata(store.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn check_file_session_store_bad_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        fs::write(&path, "not a session").unwrap();
+
+        let store = FileSessionStore::new(&path);
+        assert!(matches!(store.load(), Err(ScrobblerError::Decode(_))));
+    }
+}
@@ -0,0 +1,338 @@
+// Importer for Spotify's extended streaming history export
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json as json;
+
+use crate::error::ScrobblerError;
+use crate::import::{RejectReason, RejectedRecord};
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::timestamp::parse_rfc3339;
+use crate::tracker::required_play_time;
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+// Spotify's `reason_end` when a track played through to its end
+const TRACK_DONE: &str = "trackdone";
+
+/// Collects scrobbles from the `Streaming_History_Audio_*.json` files of a Spotify extended streaming history export.
+///
+/// Every file of an export (and files from overlapping exports) can be added; records already seen in another file
+/// are skipped, so each play is imported once. Podcast episodes & audiobooks are dropped, as are plays that don't
+/// qualify under Last.fm's scrobbling rules (see [`PlayTracker`]): the export has no track lengths, so plays that
+/// ended before the end of the track must have lasted 4 minutes, unless a shorter minimum is set with
+/// [`SpotifyHistory::with_min_play_time`]. Plays which reached the end of the track qualify if the track was longer
+/// than 30 seconds.
+///
+/// Spotify's `ts` is the time a play ended; scrobbles are timestamped with the time it started.
+/// [`SpotifyHistory::records`] maps the scrobbles of the [`SpotifyHistory::batch`] back to the records they were read
+/// from.
+///
+/// # Usage
+/// ```ignore
+/// let mut history = SpotifyHistory::new();
+/// history.with_min_play_time(Duration::from_secs(30));
+///
+/// for path in export_files {
+///     let summary = history.read(path)?;
+///     println!("{} scrobbles, {} duplicates", summary.imported, summary.duplicates);
+/// }
+///
+/// let response = scrobbler.scrobble_batch_chunked(&history.batch());
+/// ```
+///
+/// [`PlayTracker`]: struct.PlayTracker.html
+/// [`SpotifyHistory::with_min_play_time`]: struct.SpotifyHistory.html#method.with_min_play_time
+/// [`SpotifyHistory::records`]: struct.SpotifyHistory.html#method.records
+/// [`SpotifyHistory::batch`]: struct.SpotifyHistory.html#method.batch
+#[derive(Clone, Debug, Default)]
+pub struct SpotifyHistory {
+    min_play_time: Option<Duration>,
+    seen: HashSet<(String, u64, String)>,
+    files: usize,
+    scrobbles: Vec<(Scrobble, SpotifyRecord)>,
+}
+
+/// The record of a Spotify export a scrobble was imported from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SpotifyRecord {
+    /// The (0-based) number of the file, in the order files were added to the [`SpotifyHistory`]
+    ///
+    /// [`SpotifyHistory`]: struct.SpotifyHistory.html
+    pub file: usize,
+    /// The (0-based) position of the record in the file's array
+    pub index: usize,
+}
+
+/// What was imported from one file of a Spotify export by [`SpotifyHistory::read`]
+///
+/// [`SpotifyHistory::read`]: struct.SpotifyHistory.html#method.read
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SpotifyFileSummary {
+    /// The (0-based) number the file was given, see [`SpotifyRecord::file`]
+    ///
+    /// [`SpotifyRecord::file`]: struct.SpotifyRecord.html#structfield.file
+    pub file: usize,
+    /// Number of records imported as scrobbles
+    pub imported: usize,
+    /// Number of podcast episode & audiobook records dropped
+    pub episodes: usize,
+    /// Number of plays dropped because they were too short to scrobble
+    pub too_short: usize,
+    /// Number of records dropped because they were already imported from another file
+    pub duplicates: usize,
+    /// Records which could not be read
+    pub rejected: Vec<RejectedRecord>,
+}
+
+#[derive(Deserialize)]
+struct StreamRecord {
+    ts: String,
+    ms_played: u64,
+    master_metadata_track_name: Option<String>,
+    master_metadata_album_artist_name: Option<String>,
+    master_metadata_album_album_name: Option<String>,
+    spotify_track_uri: Option<String>,
+    episode_name: Option<String>,
+    spotify_episode_uri: Option<String>,
+    audiobook_title: Option<String>,
+    reason_end: Option<String>,
+}
+
+impl StreamRecord {
+    fn is_episode(&self) -> bool {
+        self.episode_name.is_some() || self.spotify_episode_uri.is_some() || self.audiobook_title.is_some()
+    }
+}
+
+// Result of reading a single record
+enum Play {
+    Scrobble(Box<Scrobble>),
+    Episode,
+    TooShort,
+    Duplicate,
+}
+
+impl SpotifyHistory {
+    /// Creates an empty history, only scrobbling plays stopped early after 4 minutes
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also scrobbles plays lasting at least this long which were stopped before the end of the track. Last.fm's
+    /// rules need the track length to judge these plays, which the export does not contain, so by default they need 4
+    /// minutes of play.
+    pub fn with_min_play_time(&mut self, min_play_time: Duration) -> &mut Self {
+        self.min_play_time = Some(min_play_time);
+        self
+    }
+
+    /// Reads & imports a `Streaming_History_Audio_*.json` file
+    ///
+    /// # Errors
+    /// Returns a `ScrobblerError::Io` error if the file can't be read, otherwise fails in the same cases as
+    /// `SpotifyHistory::parse`.
+    pub fn read<P: AsRef<Path>>(&mut self, path: P) -> Result<SpotifyFileSummary> {
+        let contents = fs::read_to_string(path)?;
+        self.parse(&contents)
+    }
+
+    /// Imports the contents of a `Streaming_History_Audio_*.json` file. Records which can't be read are listed in the
+    /// summary's `rejected` records.
+    ///
+    /// # Errors
+    /// Returns a `ScrobblerError::Import` error if the file is not a JSON array.
+    pub fn parse(&mut self, contents: &str) -> Result<SpotifyFileSummary> {
+        let records: Vec<json::Value> = json::from_str(contents.trim_start_matches('\u{feff}'))
+            .map_err(|err| ScrobblerError::Import(format!("Not a Spotify streaming history file: {err}")))?;
+
+        let file = self.files;
+        self.files += 1;
+
+        let mut summary = SpotifyFileSummary {
+            file,
+            ..SpotifyFileSummary::default()
+        };
+        for (index, record) in records.into_iter().enumerate() {
+            match self.import_record(record) {
+                Ok(Play::Scrobble(scrobble)) => {
+                    self.scrobbles.push((*scrobble, SpotifyRecord { file, index }));
+                    summary.imported += 1;
+                }
+                Ok(Play::Episode) => summary.episodes += 1,
+                Ok(Play::TooShort) => summary.too_short += 1,
+                Ok(Play::Duplicate) => summary.duplicates += 1,
+                Err(reason) => summary.rejected.push(RejectedRecord { index, reason }),
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Returns all imported scrobbles, oldest first
+    #[must_use]
+    pub fn batch(&self) -> ScrobbleBatch {
+        self.sorted().into_iter().map(|(scrobble, _)| scrobble.clone()).collect()
+    }
+
+    /// Returns the record each scrobble of the [`batch`] was imported from, by batch index
+    ///
+    /// [`batch`]: struct.SpotifyHistory.html#method.batch
+    #[must_use]
+    pub fn records(&self) -> Vec<SpotifyRecord> {
+        self.sorted().into_iter().map(|(_, record)| *record).collect()
+    }
+
+    /// Returns the number of imported scrobbles
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.scrobbles.len()
+    }
+
+    /// Returns true if no scrobbles have been imported
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.scrobbles.is_empty()
+    }
+
+    fn sorted(&self) -> Vec<&(Scrobble, SpotifyRecord)> {
+        let mut scrobbles: Vec<_> = self.scrobbles.iter().collect();
+        scrobbles.sort_by_key(|(scrobble, _)| scrobble.timestamp());
+        scrobbles
+    }
+
+    fn import_record(&mut self, record: json::Value) -> std::result::Result<Play, RejectReason> {
+        let record: StreamRecord =
+            json::from_value(record).map_err(|err| RejectReason::Malformed(err.to_string()))?;
+        if record.is_episode() {
+            return Ok(Play::Episode);
+        }
+
+        let track = record.master_metadata_track_name.as_deref().unwrap_or_default();
+        let artist = record.master_metadata_album_artist_name.as_deref().unwrap_or_default();
+        if track.is_empty() {
+            return Err(RejectReason::MissingField("track"));
+        }
+        if artist.is_empty() {
+            return Err(RejectReason::MissingField("artist"));
+        }
+
+        let ended = parse_rfc3339(&record.ts).ok_or_else(|| RejectReason::InvalidField {
+            field: "timestamp",
+            value: record.ts.clone(),
+        })?;
+
+        // A play that reached the end of the track lasted as long as the track
+        let played = Duration::from_millis(record.ms_played);
+        let completed = record.reason_end.as_deref() == Some(TRACK_DONE);
+        let qualifies = match required_play_time(completed.then_some(played)) {
+            Some(required) => played >= required || self.min_play_time.is_some_and(|min| played >= min),
+            None => false,
+        };
+        if !qualifies {
+            return Ok(Play::TooShort);
+        }
+
+        let id = record.spotify_track_uri.clone().unwrap_or_else(|| format!("{artist}\t{track}"));
+        if !self.seen.insert((record.ts.clone(), record.ms_played, id)) {
+            return Ok(Play::Duplicate);
+        }
+
+        let album = record.master_metadata_album_album_name.as_deref().unwrap_or_default();
+        let mut scrobble = Scrobble::new(artist, track, album);
+        scrobble.with_timestamp(ended.saturating_sub(played.as_secs()));
+        if completed {
+            scrobble.with_duration(played.as_secs());
+        }
+
+        Ok(Play::Scrobble(Box::new(scrobble)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(ts: &str, track: &str, ms_played: u64, reason_end: &str) -> String {
+        format!(
+            r#"{{"ts": "{ts}", "platform": "linux", "ms_played": {ms_played}, "conn_country": "GB",
+                "master_metadata_track_name": "{track}", "master_metadata_album_artist_name": "foo floyd",
+                "master_metadata_album_album_name": "old bananas", "spotify_track_uri": "spotify:track:{track}",
+                "episode_name": null, "episode_show_name": null, "spotify_episode_uri": null,
+                "reason_start": "trackdone", "reason_end": "{reason_end}", "shuffle": false, "skipped": null}}"#
+        )
+    }
+
+    #[test]
+    fn check_spotify_history_import() {
+        let first_file = format!(
+            "[{}, {}, {}, {}, {}, {}]",
+            // Played to the end: timestamped with the start of the play
+            record("2020-09-13T12:30:41Z", "old bananas", 241_500, "trackdone"),
+            // Stopped after 3 minutes, track length unknown
+            record("2020-09-13T12:40:00Z", "new bananas", 180_000, "fwdbtn"),
+            // Stopped after 4 minutes
+            record("2020-09-13T12:50:00Z", "long bananas", 240_000, "endplay"),
+            // A 20 second track
+            record("2020-09-13T13:00:00Z", "short bananas", 20_000, "trackdone"),
+            r#"{"ts": "2020-09-13T14:00:00Z", "ms_played": 1800000, "master_metadata_track_name": null,
+                "master_metadata_album_artist_name": null, "master_metadata_album_album_name": null,
+                "spotify_track_uri": null, "episode_name": "Episode 1", "episode_show_name": "Banana Talk",
+                "spotify_episode_uri": "spotify:episode:1", "reason_end": "trackdone"}"#,
+            r#"{"ts": "yesterday"}"#,
+        );
+        let second_file = format!(
+            "[{}, {}]",
+            record("2020-09-13T12:50:00Z", "long bananas", 240_000, "endplay"),
+            record("2020-09-13T12:20:00Z", "older bananas", 300_000, "trackdone"),
+        );
+
+        let mut history = SpotifyHistory::new();
+        let summary = history.parse(&first_file).unwrap();
+        assert_eq!(summary.imported, 2);
+        assert_eq!(summary.too_short, 2);
+        assert_eq!(summary.episodes, 1);
+        assert_eq!(summary.rejected.len(), 1);
+        assert_eq!(summary.rejected[0].index, 5);
+        assert!(matches!(summary.rejected[0].reason, RejectReason::Malformed(_)));
+
+        let summary = history.parse(&second_file).unwrap();
+        assert_eq!(summary.file, 1);
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.duplicates, 1);
+
+        let batch = history.batch();
+        let tracks: Vec<_> = batch.iter().map(|scrobble| (scrobble.track(), scrobble.timestamp())).collect();
+        assert_eq!(
+            tracks,
+            vec![
+                ("older bananas", Some(1_599_999_300)),
+                ("old bananas", Some(1_600_000_000)),
+                ("long bananas", Some(1_600_001_160)),
+            ]
+        );
+        assert_eq!(
+            history.records(),
+            vec![
+                SpotifyRecord { file: 1, index: 1 },
+                SpotifyRecord { file: 0, index: 0 },
+                SpotifyRecord { file: 0, index: 2 },
+            ]
+        );
+        let first = batch.iter().nth(1).unwrap();
+        assert_eq!(first.artist(), "foo floyd");
+        assert_eq!(first.album(), "old bananas");
+        assert_eq!(first.duration(), Some(241));
+
+        // A minimum play time also counts plays stopped early
+        let mut history = SpotifyHistory::new();
+        history.with_min_play_time(Duration::from_secs(30));
+        assert_eq!(history.parse(&first_file).unwrap().imported, 3);
+
+        assert!(matches!(history.parse("{}"), Err(ScrobblerError::Import(_))));
+    }
+}
@@ -0,0 +1,187 @@
+// Parsing & formatting of the timestamps used by listening history files
+use std::fmt;
+
+const SECS_PER_DAY: i64 = 86_400;
+
+/// How timestamps are written in a listening history file
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TimestampFormat {
+    /// UNIX timestamps, in seconds, e.g. `1600000000`
+    #[default]
+    UnixSeconds,
+    /// [RFC 3339](https://www.rfc-editor.org/rfc/rfc3339) date-times, e.g. `2020-09-13T12:26:40Z`. Written in UTC;
+    /// any UTC offset is accepted when reading.
+    Rfc3339,
+}
+
+impl TimestampFormat {
+    /// Parses a timestamp in this format into a UNIX timestamp
+    #[must_use]
+    pub fn parse(self, value: &str) -> Option<u64> {
+        match self {
+            Self::UnixSeconds => value.trim().parse().ok(),
+            Self::Rfc3339 => parse_rfc3339(value),
+        }
+    }
+
+    /// Formats a UNIX timestamp in this format
+    #[must_use]
+    pub fn format(self, timestamp: u64) -> String {
+        match self {
+            Self::UnixSeconds => timestamp.to_string(),
+            Self::Rfc3339 => format_rfc3339(timestamp),
+        }
+    }
+}
+
+impl fmt::Display for TimestampFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnixSeconds => write!(f, "UNIX timestamp"),
+            Self::Rfc3339 => write!(f, "RFC 3339 date-time"),
+        }
+    }
+}
+
+/// Parses an RFC 3339 date-time (`2020-09-13T12:26:40Z`, `2020-09-13 14:26:40.5+02:00`...) into a UNIX timestamp.
+/// Fractional seconds are truncated. Returns `None` for malformed or pre-1970 date-times.
+pub(crate) fn parse_rfc3339(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let bytes = value.as_bytes();
+    if !value.is_ascii() || bytes.len() < 20 {
+        return None;
+    }
+    if bytes[4] != b'-' || bytes[7] != b'-' || !matches!(bytes[10], b'T' | b't' | b' ') || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+
+    let year = number(&value[0..4])?;
+    let month = number(&value[5..7])?;
+    let day = number(&value[8..10])?;
+    let hour = number(&value[11..13])?;
+    let minute = number(&value[14..16])?;
+    let second = number(&value[17..19])?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) || hour > 23 || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    // Skip fractional seconds
+    let mut rest = &value[19..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return None;
+        }
+        rest = &fraction[digits..];
+    }
+
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let (sign, hours_minutes) = match rest.as_bytes().first()? {
+                b'+' => (1, &rest[1..]),
+                b'-' => (-1, &rest[1..]),
+                _ => return None,
+            };
+            let (hours, minutes) = hours_minutes.split_once(':')?;
+            if hours.len() != 2 || minutes.len() != 2 {
+                return None;
+            }
+            sign * (number(hours)? * 3600 + number(minutes)? * 60)
+        }
+    };
+
+    let timestamp = days_from_civil(year, month, day) * SECS_PER_DAY + hour * 3600 + minute * 60 + second - offset;
+    u64::try_from(timestamp).ok()
+}
+
+/// Formats a UNIX timestamp as an RFC 3339 date-time in UTC, e.g. `2020-09-13T12:26:40Z`
+pub(crate) fn format_rfc3339(timestamp: u64) -> String {
+    let timestamp = i64::try_from(timestamp).unwrap_or(i64::MAX);
+    let (days, secs) = (timestamp.div_euclid(SECS_PER_DAY), timestamp.rem_euclid(SECS_PER_DAY));
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+fn number(digits: &str) -> Option<i64> {
+    if digits.bytes().all(|b| b.is_ascii_digit()) {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date, see http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_rfc3339_timestamps() {
+        assert_eq!(parse_rfc3339("2020-09-13T12:26:40Z"), Some(1_600_000_000));
+        assert_eq!(parse_rfc3339("2020-09-13 14:26:40.123+02:00"), Some(1_600_000_000));
+        assert_eq!(parse_rfc3339("2020-09-13T07:56:40-04:30"), Some(1_600_000_000));
+        assert_eq!(parse_rfc3339("2024-02-29T00:00:00Z"), Some(1_709_164_800));
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+
+        assert_eq!(parse_rfc3339("2023-02-29T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339("2020-09-13T12:26:40"), None);
+        assert_eq!(parse_rfc3339("2020-09-13T12:26:40+0200"), None);
+        assert_eq!(parse_rfc3339("1969-12-31T23:59:59Z"), None);
+        assert_eq!(parse_rfc3339("13/09/2020 12:26:40Z"), None);
+        assert_eq!(parse_rfc3339("2020-09-13T12:26:40\u{2013}02:00"), None);
+
+        assert_eq!(format_rfc3339(1_600_000_000), "2020-09-13T12:26:40Z");
+        assert_eq!(format_rfc3339(1_709_164_800), "2024-02-29T00:00:00Z");
+        assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00Z");
+
+        for timestamp in [0, 951_782_400, 1_600_000_000, 4_102_444_799] {
+            assert_eq!(parse_rfc3339(&format_rfc3339(timestamp)), Some(timestamp));
+        }
+        assert_eq!(TimestampFormat::UnixSeconds.parse(" 1600000000"), Some(1_600_000_000));
+        assert_eq!(TimestampFormat::UnixSeconds.format(1_600_000_000), "1600000000");
+    }
+}
@@ -0,0 +1,478 @@
+// Play tracking according to Last.fm's scrobbling rules
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::ScrobblerError;
+use crate::models::metadata::Scrobble;
+use crate::models::responses::NowPlayingResponse;
+use crate::scrobbler::Scrobbler;
+
+// Tracks must be longer than 30 seconds to be scrobbled
+const MIN_TRACK_DURATION: Duration = Duration::from_secs(30);
+
+// A track qualifies once it has been played for half its duration, or for 4 minutes, whichever comes first
+#[allow(clippy::duration_suboptimal_units)]
+const MAX_REQUIRED_PLAY_TIME: Duration = Duration::from_secs(4 * 60);
+
+/// Tracks the playback of tracks and decides when they qualify to be scrobbled, following Last.fm's scrobbling rules.
+///
+/// A track qualifies for scrobbling when it is longer than 30 seconds, and has been played for at least half its
+/// duration or for 4 minutes, whichever occurs first. Tracks without a known duration (see
+/// [`Scrobble::with_duration`]) qualify after 4 minutes of play. Only time spent actually playing counts: paused time,
+/// and the parts of a track skipped over by seeking, do not.
+///
+/// Feed player events into the tracker with [`start`], [`pause`], [`resume`] and [`stop`]. Seeking needs no call:
+/// the tracker counts time spent playing rather than the playback position, so skipped parts of a track are never
+/// counted and replayed parts are counted again. Qualifying tracks are returned as a [`Scrobble`] timestamped with the
+/// time playback started, ready to submit with [`Scrobbler::scrobble`]. Use [`PlaySession`] to also have the now
+/// playing track sent to Last.fm automatically.
+///
+/// Time is read from a [`Clock`], which can be replaced (see [`PlayTracker::with_clock`]) to test player
+/// integrations deterministically.
+///
+/// # Usage
+/// ```ignore
+/// let mut tracker = PlayTracker::new();
+/// let mut track = Scrobble::new("Example Artist", "Example Track", "Example Album");
+/// track.with_duration(215);
+///
+/// tracker.start(track);
+/// ...
+/// if let Some(scrobble) = tracker.stop() {
+///     scrobbler.scrobble(&scrobble)?;
+/// }
+/// ```
+///
+/// # Last.fm API Documentation
+/// [Scrobbling Rules Documentation](https://www.last.fm/api/scrobbling#when-is-a-scrobble-a-scrobble)
+///
+/// [`Scrobble::with_duration`]: struct.Scrobble.html#method.with_duration
+/// [`start`]: struct.PlayTracker.html#method.start
+/// [`pause`]: struct.PlayTracker.html#method.pause
+/// [`resume`]: struct.PlayTracker.html#method.resume
+/// [`stop`]: struct.PlayTracker.html#method.stop
+/// [`Scrobble`]: struct.Scrobble.html
+/// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+/// [`PlaySession`]: struct.PlaySession.html
+/// [`Clock`]: trait.Clock.html
+/// [`PlayTracker::with_clock`]: struct.PlayTracker.html#method.with_clock
+#[derive(Debug)]
+pub struct PlayTracker<C: Clock = SystemClock> {
+    clock: C,
+    current: Option<TrackPlay>,
+}
+
+#[derive(Debug)]
+struct TrackPlay {
+    scrobble: Scrobble,
+    // Play time accumulated before the last pause
+    played: Duration,
+    // When playback was last started/resumed, `None` while paused
+    playing_since: Option<SystemTime>,
+    // Whether the play has already been returned as a scrobble
+    scrobbled: bool,
+}
+
+impl PlayTracker<SystemClock> {
+    /// Creates a new tracker, reading time from the system clock
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl Default for PlayTracker<SystemClock> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> PlayTracker<C> {
+    /// Creates a new tracker, reading time from the given [`Clock`].
+    ///
+    /// [`Clock`]: trait.Clock.html
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            clock,
+            current: None,
+        }
+    }
+
+    /// Starts playback of a new track, which is timestamped with the current time.
+    ///
+    /// If another track was being tracked, it is finished as if [`stop`] had been called: its [`Scrobble`] is
+    /// returned if it qualifies for scrobbling.
+    ///
+    /// [`stop`]: struct.PlayTracker.html#method.stop
+    /// [`Scrobble`]: struct.Scrobble.html
+    pub fn start(&mut self, track: Scrobble) -> Option<Scrobble> {
+        self.start_track(track).0
+    }
+
+    // Starts playback of a new track, returning the finished track's scrobble and the new track's scrobble
+    fn start_track(&mut self, track: Scrobble) -> (Option<Scrobble>, &Scrobble) {
+        let finished = self.stop();
+
+        let now = self.clock.now();
+        let mut scrobble = track;
+        scrobble.with_timestamp(now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs());
+
+        let play = self.current.insert(TrackPlay {
+            scrobble,
+            played: Duration::default(),
+            playing_since: Some(now),
+            scrobbled: false,
+        });
+
+        (finished, &play.scrobble)
+    }
+
+    /// Pauses playback of the current track. Paused time does not count towards the track's play time.
+    pub fn pause(&mut self) {
+        let now = self.clock.now();
+        if let Some(ref mut play) = self.current {
+            if let Some(since) = play.playing_since.take() {
+                play.played += now.duration_since(since).unwrap_or_default();
+            }
+        }
+    }
+
+    /// Resumes playback of the current track after a [`pause`].
+    ///
+    /// [`pause`]: struct.PlayTracker.html#method.pause
+    pub fn resume(&mut self) {
+        let now = self.clock.now();
+        if let Some(ref mut play) = self.current {
+            if play.playing_since.is_none() {
+                play.playing_since = Some(now);
+            }
+        }
+    }
+
+    /// Stops playback of the current track, returning its [`Scrobble`] if it qualifies for scrobbling (and has not
+    /// already been returned by [`poll`]).
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`poll`]: struct.PlayTracker.html#method.poll
+    pub fn stop(&mut self) -> Option<Scrobble> {
+        let scrobble = self.poll();
+        self.current = None;
+        scrobble
+    }
+
+    /// Returns the current track's [`Scrobble`] as soon as it qualifies for scrobbling.
+    ///
+    /// Players which scrobble tracks as soon as they qualify, rather than when they finish, can call this
+    /// periodically. Each play is only returned once, by either `poll` or [`stop`].
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`stop`]: struct.PlayTracker.html#method.stop
+    pub fn poll(&mut self) -> Option<Scrobble> {
+        if !self.is_scrobblable() {
+            return None;
+        }
+
+        let play = self.current.as_mut()?;
+        play.scrobbled = true;
+        Some(play.scrobble.clone())
+    }
+
+    /// Returns the track currently being tracked, if any. Its timestamp is the time playback started.
+    pub fn current(&self) -> Option<&Scrobble> {
+        self.current.as_ref().map(|play| &play.scrobble)
+    }
+
+    /// Returns true if the current track is paused
+    pub fn is_paused(&self) -> bool {
+        self.current
+            .as_ref()
+            .is_some_and(|play| play.playing_since.is_none())
+    }
+
+    /// Returns the time the current track has been played for
+    pub fn played_time(&self) -> Duration {
+        self.current.as_ref().map_or_else(Duration::default, |play| {
+            let playing = play.playing_since.map_or_else(Duration::default, |since| {
+                self.clock.now().duration_since(since).unwrap_or_default()
+            });
+            play.played + playing
+        })
+    }
+
+    /// Returns true if the current track qualifies for scrobbling and has not already been returned as a scrobble
+    pub fn is_scrobblable(&self) -> bool {
+        let Some(ref play) = self.current else {
+            return false;
+        };
+
+        if play.scrobbled {
+            return false;
+        }
+
+        match required_play_time(play.scrobble.duration().map(Duration::from_secs)) {
+            Some(required) => self.played_time() >= required,
+            None => false,
+        }
+    }
+}
+
+// How long a track of the given duration must be played to qualify for scrobbling, or `None` if it is too short to be
+// scrobbled at all
+pub(crate) fn required_play_time(duration: Option<Duration>) -> Option<Duration> {
+    match duration {
+        Some(duration) if duration <= MIN_TRACK_DURATION => None,
+        Some(duration) => Some((duration / 2).min(MAX_REQUIRED_PLAY_TIME)),
+        None => Some(MAX_REQUIRED_PLAY_TIME),
+    }
+}
+
+/// Result of starting a new track in a [`PlaySession`].
+///
+/// [`PlaySession`]: struct.PlaySession.html
+#[derive(Debug)]
+pub struct TrackStart {
+    /// The previous track's [`Scrobble`], if it qualified for scrobbling
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    pub finished: Option<Scrobble>,
+    /// Result of the now playing request sent for the new track
+    pub now_playing: Result<NowPlayingResponse, ScrobblerError>,
+}
+
+/// A [`PlayTracker`] that sends each started track to Last.fm as the user's now playing track.
+///
+/// Player events are handled exactly as in [`PlayTracker`], and qualifying tracks are returned as [`Scrobble`]s
+/// rather than submitted, so the application decides how to send them (directly, via a [`ScrobbleQueue`]...).
+///
+/// # Usage
+/// ```ignore
+/// let mut session = PlaySession::new(&scrobbler);
+/// let started = session.start(track);
+/// if let Some(scrobble) = started.finished {
+///     scrobbler.scrobble(&scrobble)?;
+/// }
+/// ```
+///
+/// [`PlayTracker`]: struct.PlayTracker.html
+/// [`Scrobble`]: struct.Scrobble.html
+/// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+pub struct PlaySession<'a, C: Clock = SystemClock> {
+    scrobbler: &'a Scrobbler,
+    tracker: PlayTracker<C>,
+}
+
+impl<'a> PlaySession<'a, SystemClock> {
+    /// Creates a new play session sending now playing requests with the given (authenticated) [`Scrobbler`]
+    ///
+    /// [`Scrobbler`]: struct.Scrobbler.html
+    #[must_use]
+    pub fn new(scrobbler: &'a Scrobbler) -> Self {
+        Self::with_clock(scrobbler, SystemClock)
+    }
+}
+
+impl<'a, C: Clock> PlaySession<'a, C> {
+    /// Creates a new play session, reading time from the given [`Clock`]
+    ///
+    /// [`Clock`]: trait.Clock.html
+    pub fn with_clock(scrobbler: &'a Scrobbler, clock: C) -> Self {
+        Self {
+            scrobbler,
+            tracker: PlayTracker::with_clock(clock),
+        }
+    }
+
+    /// Starts playback of a new track and sends it to Last.fm as the now playing track. See [`PlayTracker::start`].
+    ///
+    /// [`PlayTracker::start`]: struct.PlayTracker.html#method.start
+    pub fn start(&mut self, track: Scrobble) -> TrackStart {
+        let (finished, current) = self.tracker.start_track(track);
+        let now_playing = self.scrobbler.now_playing(current);
+
+        TrackStart {
+            finished,
+            now_playing,
+        }
+    }
+
+    /// See [`PlayTracker::pause`]
+    ///
+    /// [`PlayTracker::pause`]: struct.PlayTracker.html#method.pause
+    pub fn pause(&mut self) {
+        self.tracker.pause();
+    }
+
+    /// See [`PlayTracker::resume`]
+    ///
+    /// [`PlayTracker::resume`]: struct.PlayTracker.html#method.resume
+    pub fn resume(&mut self) {
+        self.tracker.resume();
+    }
+
+    /// See [`PlayTracker::stop`]
+    ///
+    /// [`PlayTracker::stop`]: struct.PlayTracker.html#method.stop
+    pub fn stop(&mut self) -> Option<Scrobble> {
+        self.tracker.stop()
+    }
+
+    /// See [`PlayTracker::poll`]
+    ///
+    /// [`PlayTracker::poll`]: struct.PlayTracker.html#method.poll
+    pub fn poll(&mut self) -> Option<Scrobble> {
+        self.tracker.poll()
+    }
+
+    /// Returns the underlying [`PlayTracker`]
+    ///
+    /// [`PlayTracker`]: struct.PlayTracker.html
+    pub fn tracker(&self) -> &PlayTracker<C> {
+        &self.tracker
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn make_tracker() -> (PlayTracker<impl Clock>, Rc<Cell<u64>>) {
+        let time = Rc::new(Cell::new(1000));
+        let clock_time = Rc::clone(&time);
+        let tracker = PlayTracker::with_clock(move || UNIX_EPOCH + Duration::from_secs(clock_time.get()));
+        (tracker, time)
+    }
+
+    fn make_track(duration: Option<u64>) -> Scrobble {
+        let mut track = Scrobble::new("foo floyd and the fruit flies", "old bananas", "old bananas");
+        if let Some(duration) = duration {
+            track.with_duration(duration);
+        }
+        track
+    }
+
+    #[test]
+    fn check_half_duration_rule() {
+        let (mut tracker, time) = make_tracker();
+        assert!(tracker.start(make_track(Some(200))).is_none());
+
+        time.set(1099);
+        assert!(!tracker.is_scrobblable());
+        time.set(1100);
+        assert!(tracker.is_scrobblable());
+
+        let scrobble = tracker.stop().unwrap();
+        assert_eq!(scrobble.timestamp(), Some(1000));
+        assert!(tracker.current().is_none());
+    }
+
+    #[test]
+    fn check_four_minute_rule() {
+        let (mut tracker, time) = make_tracker();
+        tracker.start(make_track(Some(3600)));
+
+        time.set(1000 + 239);
+        assert!(!tracker.is_scrobblable());
+        time.set(1000 + 240);
+        assert!(tracker.is_scrobblable());
+
+        // Unknown durations also need 4 minutes of play
+        tracker.start(make_track(None));
+        time.set(1000 + 240 + 239);
+        assert!(!tracker.is_scrobblable());
+        time.set(1000 + 240 + 240);
+        assert!(tracker.is_scrobblable());
+    }
+
+    #[test]
+    fn check_short_tracks_never_qualify() {
+        let (mut tracker, time) = make_tracker();
+        tracker.start(make_track(Some(30)));
+        time.set(2000);
+        assert!(tracker.stop().is_none());
+    }
+
+    #[test]
+    fn check_pause_and_resume() {
+        let (mut tracker, time) = make_tracker();
+        tracker.start(make_track(Some(100)));
+
+        time.set(1040);
+        tracker.pause();
+        assert!(tracker.is_paused());
+        time.set(5000);
+        assert_eq!(tracker.played_time(), Duration::from_secs(40));
+        assert!(!tracker.is_scrobblable());
+
+        tracker.resume();
+        time.set(5009);
+        assert!(!tracker.is_scrobblable());
+        time.set(5010);
+        assert_eq!(tracker.played_time(), Duration::from_secs(50));
+        assert!(tracker.is_scrobblable());
+    }
+
+    #[test]
+    fn check_track_change_and_poll() {
+        let (mut tracker, time) = make_tracker();
+        tracker.start(make_track(Some(100)));
+
+        time.set(1010);
+        let skipped = tracker.start(make_track(Some(100)));
+        assert!(skipped.is_none());
+
+        time.set(1060);
+        let polled = tracker.poll().unwrap();
+        assert_eq!(polled.timestamp(), Some(1010));
+        assert!(tracker.poll().is_none());
+
+        // Already scrobbled via poll, so not returned again on track change
+        time.set(1100);
+        assert!(tracker.start(make_track(Some(100))).is_none());
+        time.set(1150);
+        assert_eq!(tracker.start(make_track(None)).unwrap().timestamp(), Some(1100));
+    }
+
+    #[test]
+    fn check_play_session_now_playing() {
+        use mockito::mock;
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey");
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("method".into(), "track.updateNowPlaying".into()),
+                mockito::Matcher::UrlEncoded("track".into(), "old bananas".into()),
+            ]))
+            .with_body(
+                r#"
+            {
+                "nowplaying": {
+                            "artist": [ "0", "foo floyd and the fruit flies" ],
+                            "album": [ "1", "old bananas" ],
+                            "albumArtist": [ "0", "foo floyd"],
+                            "track": [ "1", "old bananas"],
+                            "timestamp": "2019-10-04 13:23:40"
+                        }
+            }
+            "#,
+            )
+            .create();
+
+        let time = Rc::new(Cell::new(1000));
+        let clock_time = Rc::clone(&time);
+        let clock = move || UNIX_EPOCH + Duration::from_secs(clock_time.get());
+        let mut session = PlaySession::with_clock(&scrobbler, clock);
+
+        let started = session.start(make_track(Some(100)));
+        assert!(started.finished.is_none());
+        assert!(started.now_playing.is_ok());
+
+        time.set(1050);
+        let started = session.start(make_track(Some(100)));
+        assert_eq!(started.finished.unwrap().timestamp(), Some(1000));
+        assert!(session.stop().is_none());
+    }
+}
@@ -0,0 +1,316 @@
+// Pluggable HTTP transports used by the Last.fm API client
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::error::ScrobblerError;
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+/// HTTP method of an [`HttpRequest`]
+///
+/// [`HttpRequest`]: struct.HttpRequest.html
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HttpMethod {
+    Get,
+    Post,
+}
+
+impl fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match *self {
+            Self::Get => "GET",
+            Self::Post => "POST",
+        };
+        write!(f, "{str}")
+    }
+}
+
+/// An HTTP request to a scrobbling service API, to be sent by a [`Transport`].
+///
+/// `params` are the complete, already signed, request parameters: they are sent as a URL-encoded form body for
+/// `POST` requests, and as the query string for `GET` requests. Requests with a JSON `body` (used by ListenBrainz)
+/// send it instead of the form body, with an `application/json` content type.
+///
+/// [`Transport`]: trait.Transport.html
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub params: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+}
+
+impl HttpRequest {
+    /// Creates a request with the given parameters, without extra headers or a JSON body
+    #[must_use]
+    pub fn new(method: HttpMethod, url: &str, params: HashMap<String, String>) -> Self {
+        Self {
+            method,
+            url: url.to_owned(),
+            params,
+            headers: HashMap::new(),
+            body: None,
+        }
+    }
+}
+
+/// An HTTP response returned by a [`Transport`]
+///
+/// [`Transport`]: trait.Transport.html
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// Creates a response with the given HTTP status code and body
+    #[must_use]
+    pub fn new(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            body: body.to_owned(),
+        }
+    }
+}
+
+/// Sends HTTP requests on behalf of a [`Scrobbler`].
+///
+/// The crate uses [`UreqTransport`] by default. Implement this trait to route API requests through a different HTTP
+/// stack (a corporate proxy, custom TLS configuration...), and set it with [`Scrobbler::with_transport`].
+/// [`RecordingTransport`] is provided for testing code that uses a `Scrobbler` without network access.
+///
+/// Implementations only move bytes: request signing and interpreting the response (including Last.fm API errors
+/// and non-success statuses) is done by the `Scrobbler`. Failures to send the request or read the response should be
+/// returned as [`ScrobblerError::Transport`].
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`UreqTransport`]: struct.UreqTransport.html
+/// [`Scrobbler::with_transport`]: struct.Scrobbler.html#method.with_transport
+/// [`RecordingTransport`]: struct.RecordingTransport.html
+/// [`ScrobblerError::Transport`]: enum.ScrobblerError.html#variant.Transport
+pub trait Transport: Send + Sync {
+    /// Sends the request, returning the response status & body
+    ///
+    /// # Errors
+    /// Returns a `ScrobblerError::Transport` error if the request can't be sent or the response can't be read.
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse>;
+}
+
+/// The default [`Transport`], sending requests with a [`ureq`](https://docs.rs/ureq) agent.
+///
+/// [`Transport`]: trait.Transport.html
+pub struct UreqTransport {
+    agent: ureq::Agent,
+}
+
+impl UreqTransport {
+    /// Creates a transport with a default `ureq` agent
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_agent(ureq::agent())
+    }
+
+    /// Creates a transport sending requests with the given, pre-configured, `ureq` agent
+    #[must_use]
+    pub fn with_agent(agent: ureq::Agent) -> Self {
+        Self { agent }
+    }
+}
+
+impl Default for UreqTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transport for UreqTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        let mut req = match request.method {
+            HttpMethod::Post => self.agent.post(&request.url),
+            HttpMethod::Get => self.agent.get(&request.url),
+        };
+        for (k, v) in &request.headers {
+            req.set(k, v);
+        }
+
+        let resp = match (request.method, &request.body) {
+            (_, Some(body)) => req.set("Content-Type", "application/json").send_string(body),
+            (HttpMethod::Post, None) => {
+                let params: Vec<(&str, &str)> = request
+                    .params
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect();
+
+                req.send_form(&params[..])
+            }
+            (HttpMethod::Get, None) => {
+                for (k, v) in &request.params {
+                    req.query(k, v);
+                }
+
+                req.call()
+            }
+        };
+
+        if let Some(e) = resp.synthetic_error() {
+            return Err(ScrobblerError::Transport(e.to_string()));
+        }
+
+        let status = resp.status();
+        let body = resp
+            .into_string()
+            .map_err(|err| ScrobblerError::Transport(format!("Failed to read response body: {err}")))?;
+
+        Ok(HttpResponse { status, body })
+    }
+}
+
+/// An in-memory [`Transport`] that records requests and replies with queued responses, for tests.
+///
+/// Responses are returned in the order they were queued; once the queue is empty a
+/// [`ScrobblerError::Transport`] error is returned. `RecordingTransport` is cheaply cloneable and clones share
+/// their state, so a clone can be kept to inspect requests after handing the transport to a [`Scrobbler`].
+///
+/// # Usage
+/// ```ignore
+/// let transport = RecordingTransport::new();
+/// transport.push_response(200, r#"{"session": {"key": "key", "subscriber": 0, "name": "user"}}"#);
+///
+/// let mut scrobbler = Scrobbler::with_transport(api_key, api_secret, transport.clone());
+/// scrobbler.authenticate_with_password("user", "pass")?;
+///
+/// assert_eq!(transport.requests()[0].params["method"], "auth.getMobileSession");
+/// ```
+///
+/// [`Transport`]: trait.Transport.html
+/// [`ScrobblerError::Transport`]: enum.ScrobblerError.html#variant.Transport
+/// [`Scrobbler`]: struct.Scrobbler.html
+#[derive(Clone, Default)]
+pub struct RecordingTransport {
+    state: Arc<Mutex<RecordingState>>,
+}
+
+#[derive(Default)]
+struct RecordingState {
+    requests: Vec<HttpRequest>,
+    responses: VecDeque<Result<HttpResponse>>,
+}
+
+impl RecordingTransport {
+    /// Creates a transport with no queued responses
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a response with the given HTTP status code and body
+    pub fn push_response(&self, status: u16, body: &str) {
+        self.push_result(Ok(HttpResponse::new(status, body)));
+    }
+
+    /// Queues a result to be returned by the transport, which can be an error to simulate transport failures
+    pub fn push_result(&self, result: Result<HttpResponse>) {
+        self.lock().responses.push_back(result);
+    }
+
+    /// Returns all requests sent through the transport so far, oldest first
+    #[must_use]
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.lock().requests.clone()
+    }
+
+    /// Returns the most recent request sent through the transport
+    #[must_use]
+    pub fn last_request(&self) -> Option<HttpRequest> {
+        self.lock().requests.last().cloned()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RecordingState> {
+        self.state.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        let mut state = self.lock();
+        state.requests.push(request.clone());
+        state
+            .responses
+            .pop_front()
+            .unwrap_or_else(|| Err(ScrobblerError::Transport("No response queued".to_owned())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_request(method: HttpMethod) -> HttpRequest {
+        let mut params = HashMap::new();
+        params.insert("method".to_string(), "track.scrobble".to_string());
+        params.insert("artist".to_string(), "foo floyd & the fruit flies".to_string());
+
+        HttpRequest::new(method, &mockito::server_url(), params)
+    }
+
+    #[test]
+    fn check_recording_transport() {
+        let transport = RecordingTransport::new();
+        let shared = transport.clone();
+        shared.push_response(200, "first");
+        shared.push_result(Err(ScrobblerError::Transport("offline".to_owned())));
+
+        let request = make_request(HttpMethod::Post);
+        assert_eq!(transport.send(&request).unwrap(), HttpResponse::new(200, "first"));
+        assert!(transport.send(&request).is_err());
+        assert!(matches!(transport.send(&request), Err(ScrobblerError::Transport(_))));
+
+        assert_eq!(shared.requests().len(), 3);
+        assert_eq!(shared.last_request(), Some(request));
+    }
+
+    #[test]
+    fn check_ureq_transport() {
+        let _m = mockito::mock("POST", "/")
+            .match_body(mockito::Matcher::UrlEncoded(
+                "artist".into(),
+                "foo floyd & the fruit flies".into(),
+            ))
+            .with_status(201)
+            .with_body("posted")
+            .create();
+
+        let transport = UreqTransport::new();
+        let resp = transport.send(&make_request(HttpMethod::Post)).unwrap();
+        assert_eq!(resp, HttpResponse::new(201, "posted"));
+
+        let _m = mockito::mock("GET", "/")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "method".into(),
+                "track.scrobble".into(),
+            ))
+            .with_status(404)
+            .with_body("not found")
+            .create();
+
+        let resp = transport.send(&make_request(HttpMethod::Get)).unwrap();
+        assert_eq!(resp, HttpResponse::new(404, "not found"));
+
+        let _m = mockito::mock("POST", "/")
+            .match_header("authorization", "Token abc")
+            .match_header("content-type", "application/json")
+            .match_body(r#"{"listen_type":"single"}"#)
+            .with_body("json")
+            .create();
+
+        let mut request = make_request(HttpMethod::Post);
+        request.headers.insert("Authorization".to_owned(), "Token abc".to_owned());
+        request.body = Some(r#"{"listen_type":"single"}"#.to_owned());
+        let resp = transport.send(&request).unwrap();
+        assert_eq!(resp, HttpResponse::new(200, "json"));
+    }
+}